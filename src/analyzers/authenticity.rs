@@ -1,10 +1,13 @@
 use async_trait::async_trait;
 use solana_sdk::pubkey::Pubkey;
-use crate::core::types::{AnalysisResult, CoralError, RiskLevel};
+use std::collections::BTreeMap;
+use crate::core::types::{AnalysisResult, CoralError, RiskFactor, RiskFactorKind, RiskLevel};
+use crate::utils::{mint::MintProfile, solana::SolanaUtils};
 use super::{Analyzer, AnalysisMetrics};
 
 pub struct AuthenticityAnalyzer {
     rpc_client: solana_client::rpc_client::RpcClient,
+    solana: SolanaUtils,
     metrics: AnalysisMetrics,
 }
 
@@ -12,6 +15,7 @@ impl AuthenticityAnalyzer {
     pub fn new(rpc_url: &str) -> Self {
         Self {
             rpc_client: solana_client::rpc_client::RpcClient::new(rpc_url.to_string()),
+            solana: SolanaUtils::new(rpc_url),
             metrics: AnalysisMetrics {
                 confidence_score: 0.0,
                 risk_score: 0.0,
@@ -51,21 +55,89 @@ impl AuthenticityAnalyzer {
         // Implementation for finding similar projects
         todo!()
     }
+
+    /// Verified official channels corroborate the rest of the checks
+    fn calculate_confidence(&self, source: &SourceVerification) -> f64 {
+        if source.official_sources.is_empty() {
+            return 0.5;
+        }
+        let verified = source.official_sources.iter().filter(|s| s.verified).count();
+        0.5 + 0.5 * verified as f64 / source.official_sources.len() as f64
+    }
+
+    fn collect_risk_factors(
+        &self,
+        originality: &OriginalityCheck,
+        source: &SourceVerification,
+        mint: &MintProfile,
+    ) -> Vec<RiskFactor> {
+        // Token-2022 extensions such as a transfer fee or permanent delegate
+        let mut factors = mint.risk_factors();
+
+        if !originality.is_original {
+            factors.push(
+                RiskFactor::new(RiskFactorKind::ImitatesExistingProject, RiskLevel::High)
+                    .with_evidence("similar_projects", originality.similar_projects.len())
+                    .with_evidence("originality_risk", format!("{:.2}", originality.risk_score)),
+            );
+        }
+        if !source.verified_contract {
+            factors.push(RiskFactor::new(RiskFactorKind::UnverifiedContract, RiskLevel::Medium));
+        }
+        if source.official_sources.iter().all(|s| !s.verified) {
+            factors.push(
+                RiskFactor::new(RiskFactorKind::NoVerifiedOfficialChannels, RiskLevel::Medium)
+                    .with_evidence("official_sources", source.official_sources.len()),
+            );
+        }
+
+        factors
+    }
+
+    fn collect_metrics(&self, originality: &OriginalityCheck, source: &SourceVerification) -> BTreeMap<String, f64> {
+        BTreeMap::from([
+            ("similar_projects".to_string(), originality.similar_projects.len() as f64),
+            ("originality_risk".to_string(), originality.risk_score),
+            ("official_sources".to_string(), source.official_sources.len() as f64),
+            ("trust_score".to_string(), source.trust_score),
+        ])
+    }
 }
 
 #[async_trait]
 impl Analyzer for AuthenticityAnalyzer {
+    fn name(&self) -> &'static str {
+        "authenticity"
+    }
+
+    fn version(&self) -> u32 {
+        1
+    }
+
     async fn analyze(&self, token: &Pubkey) -> Result<AnalysisResult, CoralError> {
+        let mint = self.solana.get_mint_profile(token).await?;
         let originality = self.check_token_originality(token).await?;
         let source_verification = self.verify_token_source(token).await?;
-        
-        let analysis = AnalysisResult {
-            token_address: *token,
-            authenticity_score: self.calculate_authenticity_score(&originality, &source_verification),
-            risk_level: self.determine_risk_level(&originality, &source_verification),
-            recommendation: self.generate_recommendation(&originality, &source_verification),
-            analysis_timestamp: chrono::Utc::now().timestamp(),
-        };
+
+        // A single Extreme mint extension outweighs an otherwise clean project
+        let risk_level = mint
+            .risk_factors()
+            .iter()
+            .map(|factor| factor.severity)
+            .fold(self.determine_risk_level(&originality, &source_verification), RiskLevel::max);
+
+        let analysis = AnalysisResult::new(
+            *token,
+            self.calculate_authenticity_score(&originality, &source_verification),
+            risk_level,
+            self.generate_recommendation(&originality, &source_verification),
+        )
+        .with_findings(
+            self.name(),
+            self.calculate_confidence(&source_verification),
+            self.collect_risk_factors(&originality, &source_verification, &mint),
+            self.collect_metrics(&originality, &source_verification),
+        );
 
         Ok(analysis)
     }
@@ -0,0 +1,167 @@
+//! Per-analyzer result cache
+//!
+//! Entries are keyed by token, analyzer name and analyzer version, so bumping
+//! `Analyzer::version` retires every result produced by the old logic.
+
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use crate::core::types::AnalysisResult;
+use crate::utils::metrics::MetricsCollector;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    token: Pubkey,
+    analyzer: String,
+    version: u32,
+}
+
+#[derive(Debug)]
+struct CachedResult {
+    result: AnalysisResult,
+    expires_at: Instant,
+}
+
+pub struct AnalysisCache {
+    entries: RwLock<HashMap<CacheKey, CachedResult>>,
+    metrics: Arc<MetricsCollector>,
+}
+
+impl AnalysisCache {
+    pub fn new(metrics: Arc<MetricsCollector>) -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+            metrics,
+        }
+    }
+
+    /// Returns a live entry and records the hit or miss
+    pub async fn get(&self, token: &Pubkey, analyzer: &str, version: u32) -> Option<AnalysisResult> {
+        let key = CacheKey {
+            token: *token,
+            analyzer: analyzer.to_string(),
+            version,
+        };
+
+        let cached = self
+            .entries
+            .read()
+            .await
+            .get(&key)
+            .filter(|entry| entry.expires_at > Instant::now())
+            .map(|entry| entry.result.clone());
+
+        match cached {
+            Some(_) => self.metrics.record_cache_hit(),
+            None => self.metrics.record_cache_miss(),
+        }
+
+        cached
+    }
+
+    /// Caches `result` for `ttl`, dropping entries that have already expired so the cache
+    /// does not grow with every token ever analyzed
+    pub async fn insert(&self, token: &Pubkey, analyzer: &str, version: u32, result: AnalysisResult, ttl: Duration) {
+        if ttl.is_zero() {
+            return;
+        }
+
+        let key = CacheKey {
+            token: *token,
+            analyzer: analyzer.to_string(),
+            version,
+        };
+        let now = Instant::now();
+        let mut entries = self.entries.write().await;
+        entries.retain(|_, entry| entry.expires_at > now);
+        entries.insert(key, CachedResult {
+            result,
+            expires_at: now + ttl,
+        });
+    }
+
+    /// Drops every cached result for `token`
+    pub async fn invalidate_token(&self, token: &Pubkey) {
+        self.entries.write().await.retain(|key, _| key.token != *token);
+    }
+
+    /// Drops every cached result produced by `analyzer`
+    pub async fn invalidate_analyzer(&self, analyzer: &str) {
+        self.entries.write().await.retain(|key, _| key.analyzer != analyzer);
+    }
+
+    pub async fn clear(&self) {
+        self.entries.write().await.clear();
+    }
+
+    /// Removes expired entries and returns how many were dropped
+    pub async fn purge_expired(&self) -> usize {
+        let now = Instant::now();
+        let mut entries = self.entries.write().await;
+        let before = entries.len();
+        entries.retain(|_, entry| entry.expires_at > now);
+        before - entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::amount::Price;
+    use crate::core::types::{RiskLevel, TradeRecommendation};
+
+    fn result(token: Pubkey) -> AnalysisResult {
+        AnalysisResult::new(token, 0.5, RiskLevel::Medium, TradeRecommendation::Hold {
+            duration: "1h".to_string(),
+            reevaluation_price: Price::ZERO,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_cache_hits_misses_and_invalidation() {
+        let metrics = Arc::new(MetricsCollector::new());
+        let cache = AnalysisCache::new(metrics.clone());
+        let token = Pubkey::new_unique();
+
+        assert!(cache.get(&token, "wallet", 1).await.is_none());
+        cache.insert(&token, "wallet", 1, result(token), Duration::from_secs(60)).await;
+        assert!(cache.get(&token, "wallet", 1).await.is_some());
+
+        // A new analyzer version never sees the old entry
+        assert!(cache.get(&token, "wallet", 2).await.is_none());
+
+        cache.invalidate_token(&token).await;
+        assert!(cache.get(&token, "wallet", 1).await.is_none());
+
+        let stats = metrics.get_cache_stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 3);
+    }
+
+    #[tokio::test]
+    async fn test_expired_entries_are_not_served() {
+        let cache = AnalysisCache::new(Arc::new(MetricsCollector::new()));
+        let token = Pubkey::new_unique();
+
+        cache.insert(&token, "market", 1, result(token), Duration::from_millis(1)).await;
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        assert!(cache.get(&token, "market", 1).await.is_none());
+        assert_eq!(cache.purge_expired().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_insert_drops_expired_entries() {
+        let cache = AnalysisCache::new(Arc::new(MetricsCollector::new()));
+        let (stale, fresh) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        cache.insert(&stale, "market", 1, result(stale), Duration::from_millis(1)).await;
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        cache.insert(&fresh, "market", 1, result(fresh), Duration::from_secs(60)).await;
+
+        assert_eq!(cache.entries.read().await.len(), 1);
+        assert_eq!(cache.purge_expired().await, 0);
+    }
+}
@@ -0,0 +1,219 @@
+//! Groups of holders that are likely controlled by one party
+//!
+//! Two signals are used, both computed from data the wallet analyzer collects for the
+//! largest holders:
+//! - Shared funder: wallets whose first SOL came from the same address within a short
+//!   window. Fresh wallets seeded in a burst from one source are the usual sybil setup.
+//! - Bundled buy: wallets that first received the token in the same slot shortly after
+//!   launch, i.e. buys packed into one bundle by the deployer.
+//!
+//! Every cluster carries the share of the supply its wallets hold, which is what decides
+//! how much damage a coordinated sell can do.
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClusterConfig {
+    /// Largest holders whose funding and first acquisition are traced
+    pub depth: usize,
+    /// Wallets funded by one source within this many seconds of each other form a cluster
+    pub funding_window_secs: i64,
+    /// Slots after the first acquisition that still count as the launch
+    pub launch_window_slots: u64,
+    /// Fewest wallets that make a cluster
+    pub min_cluster_size: usize,
+    /// Funders that seed many unrelated wallets, such as exchange hot wallets
+    #[serde(with = "crate::core::config::pubkey_list")]
+    pub ignored_funders: Vec<Pubkey>,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            depth: 50,
+            funding_window_secs: 3_600,
+            launch_window_slots: 150,
+            min_cluster_size: 3,
+            ignored_funders: Vec::new(),
+        }
+    }
+}
+
+/// The transfer that first gave `wallet` SOL
+#[derive(Debug, Clone, PartialEq)]
+pub struct Funding {
+    pub wallet: Pubkey,
+    pub funder: Pubkey,
+    pub timestamp: i64,
+    pub lamports: u64,
+}
+
+/// The slot in which `wallet` first received the token
+#[derive(Debug, Clone, PartialEq)]
+pub struct Acquisition {
+    pub wallet: Pubkey,
+    pub slot: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ClusterKind {
+    SharedFunder { funder: Pubkey },
+    BundledBuy { slot: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WalletCluster {
+    pub kind: ClusterKind,
+    pub wallets: Vec<Pubkey>,
+    /// Fraction of the total supply the wallets hold together
+    pub supply_share: f64,
+}
+
+/// Wallets funded by the same source in bursts no more than `funding_window_secs` apart
+pub fn funding_clusters(
+    fundings: &[Funding],
+    config: &ClusterConfig,
+    balances: &HashMap<Pubkey, u64>,
+    supply: u64,
+) -> Vec<WalletCluster> {
+    let mut by_funder: BTreeMap<Pubkey, Vec<&Funding>> = BTreeMap::new();
+    for funding in fundings.iter().filter(|f| !config.ignored_funders.contains(&f.funder)) {
+        by_funder.entry(funding.funder).or_default().push(funding);
+    }
+
+    let mut clusters = Vec::new();
+    for (funder, mut funded) in by_funder {
+        funded.sort_by_key(|funding| funding.timestamp);
+
+        let mut burst: Vec<Pubkey> = Vec::new();
+        let mut last_timestamp = i64::MIN;
+        for funding in funded {
+            if !burst.is_empty() && funding.timestamp - last_timestamp > config.funding_window_secs {
+                push_cluster(&mut clusters, ClusterKind::SharedFunder { funder }, std::mem::take(&mut burst), config, balances, supply);
+            }
+            burst.push(funding.wallet);
+            last_timestamp = funding.timestamp;
+        }
+        push_cluster(&mut clusters, ClusterKind::SharedFunder { funder }, burst, config, balances, supply);
+    }
+
+    clusters
+}
+
+/// Wallets that first received the token in the same slot within the launch window
+pub fn bundled_buys(
+    acquisitions: &[Acquisition],
+    config: &ClusterConfig,
+    balances: &HashMap<Pubkey, u64>,
+    supply: u64,
+) -> Vec<WalletCluster> {
+    let launch_slot = match acquisitions.iter().map(|a| a.slot).min() {
+        Some(slot) => slot,
+        None => return Vec::new(),
+    };
+
+    let mut by_slot: BTreeMap<u64, Vec<Pubkey>> = BTreeMap::new();
+    for acquisition in acquisitions.iter().filter(|a| a.slot - launch_slot <= config.launch_window_slots) {
+        by_slot.entry(acquisition.slot).or_default().push(acquisition.wallet);
+    }
+
+    let mut clusters = Vec::new();
+    for (slot, wallets) in by_slot {
+        push_cluster(&mut clusters, ClusterKind::BundledBuy { slot }, wallets, config, balances, supply);
+    }
+    clusters
+}
+
+fn push_cluster(
+    clusters: &mut Vec<WalletCluster>,
+    kind: ClusterKind,
+    mut wallets: Vec<Pubkey>,
+    config: &ClusterConfig,
+    balances: &HashMap<Pubkey, u64>,
+    supply: u64,
+) {
+    wallets.sort();
+    wallets.dedup();
+    if wallets.len() < config.min_cluster_size.max(2) {
+        return;
+    }
+
+    let held: u64 = wallets.iter().filter_map(|wallet| balances.get(wallet)).sum();
+    let supply_share = if supply == 0 { 0.0 } else { held as f64 / supply as f64 };
+    clusters.push(WalletCluster {
+        kind,
+        wallets,
+        supply_share,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ClusterConfig {
+        ClusterConfig {
+            funding_window_secs: 600,
+            launch_window_slots: 10,
+            min_cluster_size: 2,
+            ..Default::default()
+        }
+    }
+
+    fn funding(wallet: Pubkey, funder: Pubkey, timestamp: i64) -> Funding {
+        Funding {
+            wallet,
+            funder,
+            timestamp,
+            lamports: 50_000_000,
+        }
+    }
+
+    #[test]
+    fn test_funding_clusters_split_bursts_and_skip_ignored_funders() {
+        let (funder, exchange) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let wallets: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let balances: HashMap<Pubkey, u64> = wallets.iter().map(|w| (*w, 100)).collect();
+        let config = ClusterConfig {
+            ignored_funders: vec![exchange],
+            ..config()
+        };
+
+        let fundings = vec![
+            funding(wallets[0], funder, 1_000),
+            funding(wallets[1], funder, 1_300),
+            funding(wallets[2], funder, 1_800),
+            // More than the window after the previous one: a separate, single-wallet burst
+            funding(wallets[3], funder, 5_000),
+            funding(wallets[4], exchange, 1_000),
+            funding(wallets[3], exchange, 1_000),
+        ];
+
+        let clusters = funding_clusters(&fundings, &config, &balances, 1_000);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].kind, ClusterKind::SharedFunder { funder });
+        assert_eq!(clusters[0].wallets.len(), 3);
+        assert!((clusters[0].supply_share - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_bundled_buys_only_count_the_launch_window() {
+        let wallets: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let balances: HashMap<Pubkey, u64> = wallets.iter().map(|w| (*w, 200)).collect();
+        let acquisitions = vec![
+            Acquisition { wallet: wallets[0], slot: 100 },
+            Acquisition { wallet: wallets[1], slot: 102 },
+            Acquisition { wallet: wallets[2], slot: 102 },
+            Acquisition { wallet: wallets[3], slot: 500 },
+            Acquisition { wallet: wallets[4], slot: 500 },
+        ];
+
+        let clusters = bundled_buys(&acquisitions, &config(), &balances, 1_000);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].kind, ClusterKind::BundledBuy { slot: 102 });
+        assert!((clusters[0].supply_share - 0.4).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,186 @@
+//! Standard concentration measures over a holder distribution
+//!
+//! All measures are computed on holder shares of the supply held by real holders, i.e.
+//! after burn, vault and locker accounts are excluded (see `HolderConfig`), so they stay
+//! comparable across tokens with different supplies and over time:
+//! - Gini coefficient: 0 when every holder has the same balance, approaching 1 when one
+//!   holder has everything.
+//! - Herfindahl-Hirschman index: sum of squared shares, from 1/n (even) to 1 (a single
+//!   holder). Multiply by 10,000 for the antitrust convention.
+//! - Top-N share: combined share of the N largest holders.
+//! - Nakamoto coefficient: fewest holders that together hold more than half.
+
+use serde::{Deserialize, Serialize};
+use crate::core::types::RiskLevel;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ConcentrationMetrics {
+    pub gini: f64,
+    pub hhi: f64,
+    pub top_n: usize,
+    pub top_n_share: f64,
+    pub nakamoto: usize,
+}
+
+impl ConcentrationMetrics {
+    /// Measures for `balances` in any order; all zero for an empty distribution
+    pub fn from_balances(balances: &[u64], top_n: usize) -> Self {
+        let mut sorted: Vec<u64> = balances.iter().copied().filter(|b| *b > 0).collect();
+        sorted.sort_unstable_by(|a, b| b.cmp(a));
+        let total: u128 = sorted.iter().map(|b| *b as u128).sum();
+        if total == 0 {
+            return Self {
+                gini: 0.0,
+                hhi: 0.0,
+                top_n,
+                top_n_share: 0.0,
+                nakamoto: 0,
+            };
+        }
+
+        let shares: Vec<f64> = sorted.iter().map(|b| *b as f64 / total as f64).collect();
+        let n = shares.len() as f64;
+
+        // Largest first, so the rank weight runs from n down to 1
+        let weighted: f64 = shares.iter().enumerate().map(|(i, share)| (n - i as f64) * share).sum();
+        let gini = ((2.0 * weighted) / n - (n + 1.0) / n).clamp(0.0, 1.0);
+
+        // In base units so an exact half is never rounded past
+        let mut cumulative: u128 = 0;
+        let nakamoto = sorted
+            .iter()
+            .position(|balance| {
+                cumulative += *balance as u128;
+                cumulative * 2 > total
+            })
+            .map_or(sorted.len(), |index| index + 1);
+
+        Self {
+            gini,
+            hhi: shares.iter().map(|share| share * share).sum(),
+            top_n,
+            top_n_share: shares.iter().take(top_n).sum(),
+            nakamoto,
+        }
+    }
+}
+
+/// Value at which a measure becomes a Medium and a High risk
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bands<T> {
+    pub medium: T,
+    pub high: T,
+}
+
+impl Bands<f64> {
+    /// Level for a measure where higher is more concentrated
+    pub fn level(&self, value: f64) -> Option<RiskLevel> {
+        if value >= self.high {
+            Some(RiskLevel::High)
+        } else if value >= self.medium {
+            Some(RiskLevel::Medium)
+        } else {
+            None
+        }
+    }
+}
+
+impl Bands<usize> {
+    /// Level for a measure where lower is more concentrated
+    pub fn level_at_most(&self, value: usize) -> Option<RiskLevel> {
+        if value <= self.high {
+            Some(RiskLevel::High)
+        } else if value <= self.medium {
+            Some(RiskLevel::Medium)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConcentrationThresholds {
+    pub gini: Bands<f64>,
+    pub hhi: Bands<f64>,
+    /// Holders counted in the top-N share
+    pub top_n: usize,
+    pub top_n_share: Bands<f64>,
+    /// Holder counts at or below which control is Medium or High risk
+    pub nakamoto: Bands<usize>,
+}
+
+impl Default for ConcentrationThresholds {
+    fn default() -> Self {
+        Self {
+            gini: Bands { medium: 0.85, high: 0.95 },
+            hhi: Bands { medium: 0.10, high: 0.25 },
+            top_n: 10,
+            top_n_share: Bands { medium: 0.4, high: 0.6 },
+            nakamoto: Bands { medium: 10, high: 3 },
+        }
+    }
+}
+
+impl ConcentrationThresholds {
+    /// Most severe level among the measures of who controls the supply (Gini, HHI and
+    /// Nakamoto coefficient); the top-N share is rated on its own
+    pub fn control_level(&self, metrics: &ConcentrationMetrics) -> Option<RiskLevel> {
+        if metrics.nakamoto == 0 {
+            return None;
+        }
+        [
+            self.gini.level(metrics.gini),
+            self.hhi.level(metrics.hhi),
+            self.nakamoto.level_at_most(metrics.nakamoto),
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+    }
+
+    pub fn top_n_level(&self, metrics: &ConcentrationMetrics) -> Option<RiskLevel> {
+        self.top_n_share.level(metrics.top_n_share)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_even_distribution_is_unconcentrated() {
+        let metrics = ConcentrationMetrics::from_balances(&[100; 20], 10);
+        assert!(close(metrics.gini, 0.0));
+        assert!(close(metrics.hhi, 0.05));
+        assert!(close(metrics.top_n_share, 0.5));
+        assert_eq!(metrics.nakamoto, 11);
+        assert_eq!(ConcentrationThresholds::default().control_level(&metrics), None);
+    }
+
+    #[test]
+    fn test_skewed_distribution() {
+        // Shares 0.6, 0.2, 0.1, 0.1
+        let metrics = ConcentrationMetrics::from_balances(&[100, 600, 0, 200, 100], 2);
+        assert!(close(metrics.gini, 0.4));
+        assert!(close(metrics.hhi, 0.42));
+        assert!(close(metrics.top_n_share, 0.8));
+        assert_eq!(metrics.nakamoto, 1);
+
+        let thresholds = ConcentrationThresholds::default();
+        assert_eq!(thresholds.control_level(&metrics), Some(RiskLevel::High));
+        assert_eq!(thresholds.top_n_level(&metrics), Some(RiskLevel::High));
+    }
+
+    #[test]
+    fn test_empty_distribution() {
+        let metrics = ConcentrationMetrics::from_balances(&[], 10);
+        assert_eq!(metrics.nakamoto, 0);
+        assert_eq!(ConcentrationThresholds::default().control_level(&metrics), None);
+    }
+}
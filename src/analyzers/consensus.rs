@@ -0,0 +1,549 @@
+//! Weighted consensus over the results of individual analyzers
+//!
+//! Rules, applied in order:
+//! 1. The combined score is the weight-averaged analyzer score.
+//! 2. The combined risk level is the most severe level any analyzer reported, including
+//!    analyzers weighted 0, so their findings can still veto.
+//! 3. If that level reaches `veto_risk_level`, the verdict is `Avoid` regardless of votes.
+//! 4. Otherwise each analyzer votes for its recommendation kind with its weight. The
+//!    heaviest kind wins; ties go to the more conservative kind (Avoid > Sell > Hold > Buy).
+//!    Analyzers that abstain or are weighted 0 do not vote; if none votes, the verdict
+//!    abstains.
+//! 5. The winning recommendations are merged conservatively (see `merge` below).
+//!
+//! Confidence is the weight-averaged analyzer confidence of the side that decided the
+//! verdict, scaled by the share of the voting weight that agreed with it.
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use crate::core::amount::Price;
+use crate::core::types::{AnalysisResult, AnalyzerBreakdown, CoralError, RiskFactor, RiskLevel, TradeRecommendation};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConsensusConfig {
+    /// Any analyzer reporting this level or worse forces `Avoid`
+    pub veto_risk_level: RiskLevel,
+}
+
+impl Default for ConsensusConfig {
+    fn default() -> Self {
+        Self {
+            veto_risk_level: RiskLevel::Extreme,
+        }
+    }
+}
+
+/// One analyzer's result together with the weight its vote carries (see `AnalyzerSettings`)
+#[derive(Debug, Clone)]
+pub struct AnalyzerOutput {
+    pub analyzer: String,
+    pub weight: f64,
+    pub result: AnalysisResult,
+}
+
+pub struct ConsensusEngine {
+    config: ConsensusConfig,
+}
+
+impl ConsensusEngine {
+    pub fn new(config: ConsensusConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn combine(&self, token: &Pubkey, outputs: Vec<AnalyzerOutput>) -> Result<AnalysisResult, CoralError> {
+        let total_weight: f64 = outputs.iter().map(|o| o.weight.max(0.0)).sum();
+
+        if total_weight <= 0.0 {
+            return Err(CoralError::AnalysisFailed {
+                token: *token,
+                reason: "no weighted analyzer results to combine".to_string(),
+            });
+        }
+
+        let mut explanation: Vec<String> = outputs
+            .iter()
+            .map(|o| {
+                format!(
+                    "{} (weight {:.2}): score {:.2}, risk {:?}, {}",
+                    o.analyzer,
+                    o.weight,
+                    o.result.authenticity_score,
+                    o.result.risk_level,
+                    RecommendationKind::of(&o.result.recommendation)
+                        .map_or("abstains".to_string(), |kind| format!("recommends {:?}", kind)),
+                )
+            })
+            .collect();
+
+        let score = outputs
+            .iter()
+            .map(|o| o.result.authenticity_score * o.weight)
+            .sum::<f64>()
+            / total_weight;
+
+        let risk_level = outputs
+            .iter()
+            .map(|o| o.result.risk_level)
+            .max()
+            .unwrap_or(RiskLevel::Low);
+        let risk_sources: Vec<&str> = outputs
+            .iter()
+            .filter(|o| o.result.risk_level == risk_level)
+            .map(|o| o.analyzer.as_str())
+            .collect();
+        explanation.push(format!(
+            "Risk level {:?} set by the most severe analyzer(s): {}",
+            risk_level,
+            risk_sources.join(", ")
+        ));
+
+        let (recommendation, confidence) = if risk_level >= self.config.veto_risk_level {
+            explanation.push(format!(
+                "Veto: risk {:?} reaches the veto level {:?}, forcing Avoid",
+                risk_level, self.config.veto_risk_level
+            ));
+            let vetoing: Vec<&AnalyzerOutput> = outputs
+                .iter()
+                .filter(|o| o.result.risk_level >= self.config.veto_risk_level)
+                .collect();
+            let confidence = weighted_confidence(&vetoing);
+            (self.veto(&outputs, &vetoing), confidence)
+        } else {
+            match self.tally(&outputs, &mut explanation) {
+                Some((kind, agreement)) => {
+                    let voters: Vec<&AnalyzerOutput> = outputs
+                        .iter()
+                        .filter(|o| o.weight > 0.0 && RecommendationKind::of(&o.result.recommendation) == Some(kind))
+                        .collect();
+                    let confidence = weighted_confidence(&voters) * agreement;
+                    (self.merge(kind, &voters, agreement, &mut explanation), confidence)
+                }
+                None => {
+                    explanation.push("Vote: every analyzer abstained".to_string());
+                    (TradeRecommendation::Abstain, 0.0)
+                }
+            }
+        };
+        explanation.push(format!("Confidence {:.2}", confidence));
+
+        let mut result = AnalysisResult::new(*token, score, risk_level, recommendation);
+        result.confidence = confidence;
+        result.breakdown = outputs.iter().flat_map(breakdown_of).collect();
+        result.explanation = explanation;
+
+        Ok(result)
+    }
+
+    /// Weighted vote over recommendation kinds; returns the winner and the share of the
+    /// voting weight behind it, or `None` when every analyzer abstained.
+    fn tally(&self, outputs: &[AnalyzerOutput], explanation: &mut Vec<String>) -> Option<(RecommendationKind, f64)> {
+        let mut votes: Vec<(RecommendationKind, f64)> = Vec::new();
+        for output in outputs.iter().filter(|o| o.weight > 0.0) {
+            let kind = match RecommendationKind::of(&output.result.recommendation) {
+                Some(kind) => kind,
+                None => continue,
+            };
+            match votes.iter_mut().find(|(k, _)| *k == kind) {
+                Some((_, weight)) => *weight += output.weight,
+                None => votes.push((kind, output.weight)),
+            }
+        }
+
+        // Heaviest first; equal weight resolves to the more conservative kind
+        votes.sort_by(|(ka, wa), (kb, wb)| {
+            wb.partial_cmp(wa)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| kb.cmp(ka))
+        });
+
+        let total: f64 = votes.iter().map(|(_, w)| w).sum();
+        let tally: Vec<String> = votes
+            .iter()
+            .map(|(kind, weight)| format!("{:?} {:.2}", kind, weight))
+            .collect();
+        let (winner, weight) = *votes.first()?;
+        explanation.push(format!(
+            "Vote: {} -> {:?} with {:.0}% of the weight",
+            tally.join(", "),
+            winner,
+            weight / total * 100.0
+        ));
+
+        Some((winner, weight / total))
+    }
+
+    /// Avoid carrying the findings of the vetoing analyzers plus any factors another
+    /// analyzer already attached to its own Avoid
+    fn veto(&self, outputs: &[AnalyzerOutput], vetoing: &[&AnalyzerOutput]) -> TradeRecommendation {
+        let mut risk_factors = Vec::new();
+
+        for output in vetoing {
+            for entry in &output.result.breakdown {
+                merge_factors(&mut risk_factors, &entry.risk_factors);
+            }
+        }
+        for output in outputs {
+            if let TradeRecommendation::Avoid { risk_factors: f } = &output.result.recommendation {
+                merge_factors(&mut risk_factors, f);
+            }
+        }
+
+        TradeRecommendation::Avoid { risk_factors }
+    }
+
+    /// Merges the recommendations of the winning kind. `agreement` is the share of the
+    /// total weight that voted for it and scales the merged confidence.
+    fn merge(
+        &self,
+        kind: RecommendationKind,
+        voters: &[&AnalyzerOutput],
+        agreement: f64,
+        explanation: &mut Vec<String>,
+    ) -> TradeRecommendation {
+        let heaviest = voters
+            .iter()
+            .max_by(|a, b| a.weight.partial_cmp(&b.weight).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|o| &o.result.recommendation);
+        let voter_weight: f64 = voters.iter().map(|o| o.weight).sum();
+
+        match kind {
+            // Smallest size and tightest exits; entry taken from the heaviest voter. Voters
+            // with disjoint exit bands can leave the stop loss at or above the take profit,
+            // which no order could honour, so that falls back to Hold.
+            RecommendationKind::Buy => {
+                let mut confidence = 0.0;
+                let mut suggested_amount = u64::MAX;
+                let mut stop_loss = Price::ZERO;
+                let mut take_profit: Option<Price> = None;
+
+                for voter in voters {
+                    if let TradeRecommendation::Buy { confidence: c, suggested_amount: a, stop_loss: sl, take_profit: tp, .. } =
+                        &voter.result.recommendation
+                    {
+                        confidence += c * voter.weight / voter_weight;
+                        suggested_amount = suggested_amount.min(*a);
+                        stop_loss = stop_loss.max(*sl);
+                        take_profit = Some(take_profit.map_or(*tp, |current| current.min(*tp)));
+                    }
+                }
+
+                let entry_price = match heaviest {
+                    Some(TradeRecommendation::Buy { entry_price, .. }) => *entry_price,
+                    _ => Price::ZERO,
+                };
+                let take_profit = take_profit.unwrap_or(entry_price);
+
+                if stop_loss >= take_profit {
+                    explanation.push(format!(
+                        "Merged Buy has stop loss {} at or above take profit {}, holding instead",
+                        stop_loss, take_profit
+                    ));
+                    return TradeRecommendation::Hold {
+                        duration: String::new(),
+                        reevaluation_price: entry_price,
+                    };
+                }
+
+                TradeRecommendation::Buy {
+                    confidence: confidence * agreement,
+                    suggested_amount,
+                    entry_price,
+                    stop_loss,
+                    take_profit,
+                }
+            }
+            // Largest exit percentage, every risk factor kept
+            RecommendationKind::Sell => {
+                let mut confidence = 0.0;
+                let mut percentage: f64 = 0.0;
+                let mut risk_factors = Vec::new();
+
+                for voter in voters {
+                    if let TradeRecommendation::Sell { confidence: c, percentage: p, risk_factors: f } =
+                        &voter.result.recommendation
+                    {
+                        confidence += c * voter.weight / voter_weight;
+                        percentage = percentage.max(*p);
+                        merge_factors(&mut risk_factors, f);
+                    }
+                }
+
+                TradeRecommendation::Sell {
+                    confidence: confidence * agreement,
+                    percentage,
+                    risk_factors,
+                }
+            }
+            RecommendationKind::Hold => heaviest.cloned().unwrap_or(TradeRecommendation::Hold {
+                duration: String::new(),
+                reevaluation_price: Price::ZERO,
+            }),
+            RecommendationKind::Avoid => {
+                let mut risk_factors = Vec::new();
+
+                for voter in voters {
+                    if let TradeRecommendation::Avoid { risk_factors: f } = &voter.result.recommendation {
+                        merge_factors(&mut risk_factors, f);
+                    }
+                }
+
+                TradeRecommendation::Avoid { risk_factors }
+            }
+        }
+    }
+}
+
+/// Confidence of `outputs` weighted by vote; plain average when none of them carries
+/// weight, as with a veto raised only by analyzers weighted 0
+fn weighted_confidence(outputs: &[&AnalyzerOutput]) -> f64 {
+    let weight: f64 = outputs.iter().map(|o| o.weight.max(0.0)).sum();
+    if weight <= 0.0 {
+        if outputs.is_empty() {
+            return 0.0;
+        }
+        return outputs.iter().map(|o| o.result.confidence).sum::<f64>() / outputs.len() as f64;
+    }
+    outputs.iter().map(|o| o.result.confidence * o.weight.max(0.0)).sum::<f64>() / weight
+}
+
+/// The analyzer's own breakdown entries re-weighted for this consensus, or a bare entry
+/// for analyzers that report no findings.
+fn breakdown_of(output: &AnalyzerOutput) -> Vec<AnalyzerBreakdown> {
+    if output.result.breakdown.is_empty() {
+        return vec![AnalyzerBreakdown {
+            analyzer: output.analyzer.clone(),
+            weight: output.weight,
+            score: output.result.authenticity_score,
+            risk_level: output.result.risk_level,
+            confidence: output.result.confidence,
+            risk_factors: Vec::new(),
+            metrics: Default::default(),
+        }];
+    }
+
+    output
+        .result
+        .breakdown
+        .iter()
+        .cloned()
+        .map(|mut entry| {
+            entry.weight = output.weight;
+            entry
+        })
+        .collect()
+}
+
+/// One factor per kind; when two analyzers report the same kind the more severe
+/// finding wins
+fn merge_factors(target: &mut Vec<RiskFactor>, items: &[RiskFactor]) {
+    for item in items {
+        match target.iter_mut().find(|f| f.kind == item.kind) {
+            Some(existing) if item.severity > existing.severity => *existing = item.clone(),
+            Some(_) => {}
+            None => target.push(item.clone()),
+        }
+    }
+}
+
+/// Recommendation variants ordered from least to most conservative
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum RecommendationKind {
+    Buy,
+    Hold,
+    Sell,
+    Avoid,
+}
+
+impl RecommendationKind {
+    /// `None` for an abstention, which is no vote at all
+    fn of(recommendation: &TradeRecommendation) -> Option<Self> {
+        match recommendation {
+            TradeRecommendation::Buy { .. } => Some(Self::Buy),
+            TradeRecommendation::Hold { .. } => Some(Self::Hold),
+            TradeRecommendation::Sell { .. } => Some(Self::Sell),
+            TradeRecommendation::Avoid { .. } => Some(Self::Avoid),
+            TradeRecommendation::Abstain => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::RiskFactorKind;
+
+    fn price(value: &str) -> Price {
+        Price::try_from(value.to_string()).unwrap()
+    }
+
+    fn buy(confidence: f64, amount: u64, stop_loss: &str, take_profit: &str) -> TradeRecommendation {
+        TradeRecommendation::Buy {
+            confidence,
+            suggested_amount: amount,
+            entry_price: price("1"),
+            stop_loss: price(stop_loss),
+            take_profit: price(take_profit),
+        }
+    }
+
+    fn output(analyzer: &str, weight: f64, score: f64, risk: RiskLevel, recommendation: TradeRecommendation) -> AnalyzerOutput {
+        AnalyzerOutput {
+            analyzer: analyzer.to_string(),
+            weight,
+            result: AnalysisResult::new(Pubkey::new_unique(), score, risk, recommendation)
+                .with_findings(analyzer, 1.0, vec![], Default::default()),
+        }
+    }
+
+    #[test]
+    fn test_extreme_risk_vetoes_buy() {
+        let engine = ConsensusEngine::new(ConsensusConfig::default());
+        let result = engine
+            .combine(&Pubkey::new_unique(), vec![
+                output("authenticity", 3.0, 0.9, RiskLevel::Low, buy(0.9, 100, "0.8", "1.5")),
+                output("wallet", 1.0, 0.2, RiskLevel::Extreme, buy(0.5, 100, "0.8", "1.5")),
+            ])
+            .unwrap();
+
+        assert_eq!(result.risk_level, RiskLevel::Extreme);
+        assert!(matches!(result.recommendation, TradeRecommendation::Avoid { .. }));
+        assert!((result.authenticity_score - 0.725).abs() < 1e-9);
+        assert_eq!(result.breakdown.len(), 2);
+        assert_eq!(result.breakdown[0].weight, 3.0);
+    }
+
+    #[test]
+    fn test_veto_keeps_most_severe_factor_per_kind() {
+        let engine = ConsensusEngine::new(ConsensusConfig::default());
+        let mut wallet = output("wallet", 1.0, 0.2, RiskLevel::Extreme, buy(0.5, 100, "0.8", "1.5"));
+        wallet.result.breakdown[0].risk_factors = vec![
+            RiskFactor::new(RiskFactorKind::HolderConcentration, RiskLevel::Medium),
+            RiskFactor::new(RiskFactorKind::SuspiciousFunding, RiskLevel::Extreme),
+        ];
+        let market = output("market", 1.0, 0.3, RiskLevel::High, TradeRecommendation::Avoid {
+            risk_factors: vec![RiskFactor::new(RiskFactorKind::HolderConcentration, RiskLevel::High)],
+        });
+
+        let result = engine.combine(&Pubkey::new_unique(), vec![wallet, market]).unwrap();
+
+        match result.recommendation {
+            TradeRecommendation::Avoid { risk_factors } => {
+                assert_eq!(risk_factors.len(), 2);
+                assert_eq!(risk_factors[0].kind, RiskFactorKind::HolderConcentration);
+                assert_eq!(risk_factors[0].severity, RiskLevel::High);
+                assert_eq!(risk_factors[1].kind, RiskFactorKind::SuspiciousFunding);
+            }
+            other => panic!("expected Avoid, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_weighted_vote_merges_buys_conservatively() {
+        let engine = ConsensusEngine::new(ConsensusConfig::default());
+        let result = engine
+            .combine(&Pubkey::new_unique(), vec![
+                output("authenticity", 2.0, 0.8, RiskLevel::Low, buy(0.8, 500, "0.7", "2.0")),
+                output("developer", 1.0, 0.7, RiskLevel::Medium, buy(0.5, 200, "0.9", "1.6")),
+                output("wallet", 1.0, 0.4, RiskLevel::Medium, TradeRecommendation::Hold {
+                    duration: "24h".to_string(),
+                    reevaluation_price: price("1.1"),
+                }),
+            ])
+            .unwrap();
+
+        assert_eq!(result.risk_level, RiskLevel::Medium);
+        assert!((result.confidence - 0.75).abs() < 1e-9);
+        match result.recommendation {
+            TradeRecommendation::Buy { confidence, suggested_amount, stop_loss, take_profit, .. } => {
+                assert!((confidence - 0.7 * 0.75).abs() < 1e-9);
+                assert_eq!(suggested_amount, 200);
+                assert_eq!(stop_loss, price("0.9"));
+                assert_eq!(take_profit, price("1.6"));
+            }
+            other => panic!("expected Buy, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_buys_with_disjoint_exit_bands_fall_back_to_hold() {
+        let engine = ConsensusEngine::new(ConsensusConfig::default());
+        let result = engine
+            .combine(&Pubkey::new_unique(), vec![
+                output("authenticity", 1.0, 0.8, RiskLevel::Low, buy(0.8, 500, "0.5", "0.9")),
+                output("developer", 1.0, 0.7, RiskLevel::Low, buy(0.6, 200, "1.2", "2.0")),
+            ])
+            .unwrap();
+
+        match result.recommendation {
+            TradeRecommendation::Hold { reevaluation_price, .. } => assert_eq!(reevaluation_price, price("1")),
+            other => panic!("expected Hold, got {:?}", other),
+        }
+        assert!(result.explanation.iter().any(|line| line.contains("holding instead")));
+    }
+
+    #[test]
+    fn test_tied_vote_prefers_conservative_kind() {
+        let engine = ConsensusEngine::new(ConsensusConfig::default());
+        let result = engine
+            .combine(&Pubkey::new_unique(), vec![
+                output("authenticity", 1.0, 0.8, RiskLevel::Low, buy(0.8, 500, "0.7", "2.0")),
+                output("wallet", 1.0, 0.3, RiskLevel::High, TradeRecommendation::Avoid {
+                    risk_factors: vec![RiskFactor::new(RiskFactorKind::HolderConcentration, RiskLevel::High)],
+                }),
+            ])
+            .unwrap();
+
+        assert!(matches!(result.recommendation, TradeRecommendation::Avoid { .. }));
+    }
+
+    #[test]
+    fn test_analyzer_weighted_zero_can_veto_but_not_vote() {
+        let engine = ConsensusEngine::new(ConsensusConfig::default());
+        let observer = output("mint_authority", 0.0, 0.1, RiskLevel::Extreme, TradeRecommendation::Avoid {
+            risk_factors: vec![RiskFactor::new(RiskFactorKind::SupplyIncreased, RiskLevel::Extreme)],
+        });
+
+        let result = engine
+            .combine(&Pubkey::new_unique(), vec![
+                output("authenticity", 1.0, 0.8, RiskLevel::Low, buy(0.8, 500, "0.7", "2.0")),
+                observer.clone(),
+            ])
+            .unwrap();
+        assert_eq!(result.risk_level, RiskLevel::Extreme);
+        assert!((result.authenticity_score - 0.8).abs() < 1e-9);
+        assert!((result.confidence - 1.0).abs() < 1e-9);
+        match result.recommendation {
+            TradeRecommendation::Avoid { risk_factors } => {
+                assert_eq!(risk_factors[0].kind, RiskFactorKind::SupplyIncreased);
+            }
+            other => panic!("expected Avoid, got {:?}", other),
+        }
+
+        // Nothing weighted left to decide with
+        assert!(engine.combine(&Pubkey::new_unique(), vec![observer]).is_err());
+    }
+
+    #[test]
+    fn test_abstaining_analyzer_counts_for_risk_but_not_the_vote() {
+        let engine = ConsensusEngine::new(ConsensusConfig::default());
+        let result = engine
+            .combine(&Pubkey::new_unique(), vec![
+                output("authenticity", 1.0, 0.8, RiskLevel::Low, buy(0.8, 500, "0.7", "2.0")),
+                output("mint_authority", 3.0, 0.9, RiskLevel::Medium, TradeRecommendation::Abstain),
+            ])
+            .unwrap();
+
+        assert_eq!(result.risk_level, RiskLevel::Medium);
+        assert!((result.confidence - 1.0).abs() < 1e-9);
+        assert!(matches!(result.recommendation, TradeRecommendation::Buy { .. }));
+
+        let result = engine
+            .combine(&Pubkey::new_unique(), vec![
+                output("mint_authority", 1.0, 0.9, RiskLevel::Low, TradeRecommendation::Abstain),
+            ])
+            .unwrap();
+        assert!(matches!(result.recommendation, TradeRecommendation::Abstain));
+        assert_eq!(result.confidence, 0.0);
+    }
+}
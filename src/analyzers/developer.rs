@@ -1,6 +1,8 @@
 use async_trait::async_trait;
 use solana_sdk::pubkey::Pubkey;
-use crate::core::types::{AnalysisResult, CoralError};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use crate::core::types::{AnalysisResult, CoralError, RiskFactor, RiskFactorKind, RiskLevel};
 use super::Analyzer;
 
 pub struct DeveloperAnalyzer {
@@ -48,10 +50,74 @@ impl DeveloperAnalyzer {
             previous_projects: self.analyze_previous_projects(&team_info),
         })
     }
+
+    /// Without GitHub access only on-chain activity is known
+    fn calculate_confidence(&self) -> f64 {
+        if self.github_client.is_some() {
+            1.0
+        } else {
+            0.5
+        }
+    }
+
+    fn collect_risk_factors(&self, commitment: &DeveloperCommitment, credentials: &DeveloperCredentials) -> Vec<RiskFactor> {
+        let mut factors = Vec::new();
+
+        if commitment.active_developers == 0 {
+            factors.push(RiskFactor::new(RiskFactorKind::NoActiveDevelopers, RiskLevel::Medium));
+        }
+        if commitment.commit_frequency < 1.0 {
+            factors.push(
+                RiskFactor::new(RiskFactorKind::LowCommitActivity, RiskLevel::Low)
+                    .with_evidence("commits_per_week", format!("{:.1}", commitment.commit_frequency)),
+            );
+        }
+        if credentials.verified_developers.is_empty() {
+            factors.push(RiskFactor::new(RiskFactorKind::UnverifiedDevelopers, RiskLevel::Low));
+        }
+
+        factors
+    }
+
+    fn collect_metrics(
+        &self,
+        activity: &ContractActivity,
+        commitment: &DeveloperCommitment,
+        credentials: &DeveloperCredentials,
+    ) -> BTreeMap<String, f64> {
+        BTreeMap::from([
+            ("last_contract_update".to_string(), activity.last_update as f64),
+            ("update_frequency".to_string(), activity.update_frequency),
+            ("major_changes".to_string(), activity.major_changes.len() as f64),
+            ("security_updates".to_string(), activity.security_updates.len() as f64),
+            ("active_developers".to_string(), commitment.active_developers as f64),
+            ("commit_frequency".to_string(), commitment.commit_frequency),
+            ("verified_developers".to_string(), credentials.verified_developers.len() as f64),
+            ("previous_projects".to_string(), credentials.previous_projects.len() as f64),
+        ])
+    }
 }
 
 #[async_trait]
 impl Analyzer for DeveloperAnalyzer {
+    fn name(&self) -> &'static str {
+        "developer"
+    }
+
+    fn version(&self) -> u32 {
+        1
+    }
+
+    // Repository and team data changes slowly
+    fn cache_ttl(&self) -> Duration {
+        Duration::from_secs(3600)
+    }
+
+    // GitHub lookups are the slowest part of any analysis
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(15)
+    }
+
     async fn analyze(&self, token: &Pubkey) -> Result<AnalysisResult, CoralError> {
         let contract_activity = self.analyze_contract_updates(token).await?;
         let developer_commitment = self.analyze_developer_commitment(token).await?;
@@ -63,21 +129,26 @@ impl Analyzer for DeveloperAnalyzer {
             &credentials,
         );
 
-        Ok(AnalysisResult {
-            token_address: *token,
-            authenticity_score: self.calculate_developer_score(
+        Ok(AnalysisResult::new(
+            *token,
+            self.calculate_developer_score(
                 &contract_activity,
                 &developer_commitment,
                 &credentials,
             ),
             risk_level,
-            recommendation: self.generate_recommendation(
+            self.generate_recommendation(
                 &contract_activity,
                 &developer_commitment,
                 &credentials,
             ),
-            analysis_timestamp: chrono::Utc::now().timestamp(),
-        })
+        )
+        .with_findings(
+            self.name(),
+            self.calculate_confidence(),
+            self.collect_risk_factors(&developer_commitment, &credentials),
+            self.collect_metrics(&contract_activity, &developer_commitment, &credentials),
+        ))
     }
 }
 
@@ -0,0 +1,287 @@
+//! Changes in a token's holder distribution between two snapshots
+//!
+//! A single analysis only sees the distribution as it is now. Comparing periodic
+//! snapshots (see `HolderSnapshot`) catches what happens in between: a holder base that
+//! grows or empties out, the top holders tightening their grip, large wallets appearing
+//! from nowhere and whales selling out.
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use crate::notifiers::{NotificationCategory, NotificationMessage, NotificationPriority};
+use crate::storage::HolderSnapshot;
+
+/// Holders counted in the top-holder share that is tracked for drift
+const TOP_HOLDERS: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SnapshotConfig {
+    /// Seconds between snapshots of each watched mint
+    pub interval_secs: u64,
+    /// Largest holders kept per snapshot; wallets below them are only counted
+    pub tracked_holders: usize,
+    /// Fractional change in holder count that raises an alert
+    pub holder_growth: f64,
+    /// Change in the top-10 share, in fractions of the supply, that raises an alert
+    pub top_10_drift: f64,
+    /// Share at which a wallet that was not there before counts as a large new holder
+    pub large_wallet_share: f64,
+    /// Share at which a holder counts as a whale
+    pub whale_share: f64,
+    /// Fraction of its balance a whale must sell to count as leaving
+    pub whale_exit_fraction: f64,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 3_600,
+            tracked_holders: 50,
+            holder_growth: 0.25,
+            top_10_drift: 0.05,
+            large_wallet_share: 0.01,
+            whale_share: 0.02,
+            whale_exit_fraction: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LargeWallet {
+    pub owner: Pubkey,
+    pub share: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WhaleExit {
+    pub owner: Pubkey,
+    pub previous_share: f64,
+    /// Fraction of the previous balance that is gone; at least this much when the wallet
+    /// dropped out of the tracked holders
+    pub sold_fraction: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HolderChanges {
+    pub mint: Pubkey,
+    pub elapsed_secs: i64,
+    pub previous_holders: usize,
+    pub holders: usize,
+    /// Fractional change in holder count; zero when there were no holders before
+    pub holder_growth: f64,
+    pub top_10_share: f64,
+    /// Change in the top-10 share; positive when the top holders hold more
+    pub top_10_drift: f64,
+    pub new_large_wallets: Vec<LargeWallet>,
+    pub departed_whales: Vec<WhaleExit>,
+}
+
+impl HolderChanges {
+    pub fn between(previous: &HolderSnapshot, current: &HolderSnapshot, config: &SnapshotConfig) -> Self {
+        let holder_growth = if previous.holder_count == 0 {
+            0.0
+        } else {
+            (current.holder_count as f64 - previous.holder_count as f64) / previous.holder_count as f64
+        };
+
+        let new_large_wallets = current
+            .top_holders
+            .iter()
+            .map(|holder| LargeWallet {
+                owner: holder.owner,
+                share: current.share(holder.balance),
+            })
+            .filter(|wallet| wallet.share >= config.large_wallet_share)
+            .filter(|wallet| {
+                let before = previous.balance_of(&wallet.owner).map_or(0.0, |balance| previous.share(balance));
+                before < config.large_wallet_share
+            })
+            .collect();
+
+        // A wallet missing from a full snapshot holds no more than its smallest tracked holder
+        let untracked_ceiling = if current.top_holders.len() < current.holder_count {
+            current.top_holders.last().map_or(0, |holder| holder.balance)
+        } else {
+            0
+        };
+        let departed_whales = previous
+            .top_holders
+            .iter()
+            .filter(|holder| previous.share(holder.balance) >= config.whale_share)
+            .filter_map(|holder| {
+                let balance = current.balance_of(&holder.owner).unwrap_or(untracked_ceiling);
+                let sold_fraction = 1.0 - balance.min(holder.balance) as f64 / holder.balance as f64;
+                (sold_fraction >= config.whale_exit_fraction).then(|| WhaleExit {
+                    owner: holder.owner,
+                    previous_share: previous.share(holder.balance),
+                    sold_fraction,
+                })
+            })
+            .collect();
+
+        let top_10_share = current.top_share(TOP_HOLDERS);
+        Self {
+            mint: current.mint,
+            elapsed_secs: current.timestamp - previous.timestamp,
+            previous_holders: previous.holder_count,
+            holders: current.holder_count,
+            holder_growth,
+            top_10_share,
+            top_10_drift: top_10_share - previous.top_share(TOP_HOLDERS),
+            new_large_wallets,
+            departed_whales,
+        }
+    }
+
+    /// Risk alerts for the changes that cross `config`'s thresholds, most urgent first.
+    /// `token` is how the mint is named in the messages.
+    pub fn alerts(&self, token: &str, config: &SnapshotConfig) -> Vec<NotificationMessage> {
+        let mut alerts = Vec::new();
+        let period = format!("over the last {} minutes", self.elapsed_secs / 60);
+
+        if !self.departed_whales.is_empty() {
+            let exits: Vec<String> = self
+                .departed_whales
+                .iter()
+                .map(|exit| {
+                    format!(
+                        "{} sold {:.0}% of a {:.2}% stake",
+                        exit.owner,
+                        exit.sold_fraction * 100.0,
+                        exit.previous_share * 100.0
+                    )
+                })
+                .collect();
+            alerts.push(alert(
+                format!("Whales leaving {}", token),
+                format!("{}:\n{}", period, exits.join("\n")),
+                NotificationPriority::High,
+            ));
+        }
+
+        if !self.new_large_wallets.is_empty() {
+            let wallets: Vec<String> = self
+                .new_large_wallets
+                .iter()
+                .map(|wallet| format!("{} holds {:.2}%", wallet.owner, wallet.share * 100.0))
+                .collect();
+            alerts.push(alert(
+                format!("New large holders in {}", token),
+                format!("{}:\n{}", period, wallets.join("\n")),
+                NotificationPriority::Medium,
+            ));
+        }
+
+        if self.top_10_drift.abs() >= config.top_10_drift {
+            alerts.push(alert(
+                format!("Top holders of {} {}", token, if self.top_10_drift > 0.0 { "accumulating" } else { "distributing" }),
+                format!(
+                    "Top {} share moved {:+.2} points to {:.2}% {}",
+                    TOP_HOLDERS,
+                    self.top_10_drift * 100.0,
+                    self.top_10_share * 100.0,
+                    period
+                ),
+                NotificationPriority::Medium,
+            ));
+        }
+
+        if self.holder_growth.abs() >= config.holder_growth {
+            let (direction, priority) = if self.holder_growth < 0.0 {
+                ("falling", NotificationPriority::Medium)
+            } else {
+                ("rising", NotificationPriority::Low)
+            };
+            alerts.push(alert(
+                format!("Holder count of {} {}", token, direction),
+                format!(
+                    "{} to {} holders ({:+.1}%) {}",
+                    self.previous_holders,
+                    self.holders,
+                    self.holder_growth * 100.0,
+                    period
+                ),
+                priority,
+            ));
+        }
+
+        alerts
+    }
+}
+
+fn alert(title: String, content: String, priority: NotificationPriority) -> NotificationMessage {
+    NotificationMessage {
+        title,
+        content,
+        priority,
+        category: NotificationCategory::RiskAlert,
+        timestamp: chrono::Utc::now(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(mint: Pubkey, timestamp: i64, balances: &[(Pubkey, u64)], tracked: usize) -> HolderSnapshot {
+        let mut snapshot = HolderSnapshot::now(mint, balances.to_vec(), tracked);
+        snapshot.timestamp = timestamp;
+        snapshot
+    }
+
+    #[test]
+    fn test_changes_between_snapshots() {
+        let mint = Pubkey::new_unique();
+        let (whale, seller, newcomer) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let minnows: Vec<(Pubkey, u64)> = (0..97).map(|_| (Pubkey::new_unique(), 10)).collect();
+
+        let mut before = vec![(whale, 3_000), (seller, 2_000), (newcomer, 5)];
+        before.extend(&minnows);
+        let mut after = vec![(whale, 3_000), (seller, 500), (newcomer, 800)];
+        after.extend(&minnows[..47]);
+
+        let config = SnapshotConfig::default();
+        let changes = HolderChanges::between(
+            &snapshot(mint, 0, &before, 50),
+            &snapshot(mint, 3_600, &after, 50),
+            &config,
+        );
+
+        assert_eq!((changes.previous_holders, changes.holders), (100, 50));
+        assert!((changes.holder_growth + 0.5).abs() < 1e-9);
+        assert_eq!(changes.new_large_wallets.len(), 1);
+        assert_eq!(changes.new_large_wallets[0].owner, newcomer);
+        assert_eq!(changes.departed_whales.len(), 1);
+        assert_eq!(changes.departed_whales[0].owner, seller);
+        assert!((changes.departed_whales[0].sold_fraction - 0.75).abs() < 1e-9);
+
+        let priorities: Vec<NotificationPriority> =
+            changes.alerts("TKN", &config).iter().map(|alert| alert.priority).collect();
+        assert_eq!(priorities.first(), Some(&NotificationPriority::High));
+        assert!(priorities.contains(&NotificationPriority::Medium));
+    }
+
+    #[test]
+    fn test_whale_dropping_out_of_tracked_holders_counts_as_leaving() {
+        let mint = Pubkey::new_unique();
+        let whale = Pubkey::new_unique();
+        let others: Vec<(Pubkey, u64)> = (0..20).map(|_| (Pubkey::new_unique(), 100)).collect();
+
+        let mut before = vec![(whale, 1_000)];
+        before.extend(&others);
+        let mut after = vec![(whale, 50)];
+        after.extend(&others);
+
+        let changes = HolderChanges::between(
+            &snapshot(mint, 0, &before, 5),
+            &snapshot(mint, 60, &after, 5),
+            &SnapshotConfig::default(),
+        );
+        assert_eq!(changes.departed_whales.len(), 1);
+        // Only known to hold at most the smallest tracked balance
+        assert!((changes.departed_whales[0].sold_fraction - 0.9).abs() < 1e-9);
+        assert!(changes.alerts("TKN", &SnapshotConfig::default()).iter().all(|alert| {
+            matches!(alert.category, NotificationCategory::RiskAlert)
+        }));
+    }
+}
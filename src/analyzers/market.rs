@@ -1,18 +1,33 @@
 use async_trait::async_trait;
 use solana_sdk::pubkey::Pubkey;
-use crate::core::types::{AnalysisResult, CoralError};
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+use crate::core::types::{AnalysisResult, CoralError, RiskFactor, RiskFactorKind, RiskLevel};
+use crate::utils::mint::MintProfile;
+use crate::utils::solana::SolanaUtils;
+use super::pools::{
+    token_account_amount, Dex, LiquidityConfig, LiquidityPool, LpDistribution, MeteoraVault, PoolAccount,
+    ReserveAccounts,
+};
 use super::Analyzer;
 
+/// Most accounts a single getMultipleAccounts call returns
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
 pub struct MarketAnalyzer {
     rpc_client: solana_client::rpc_client::RpcClient,
+    solana: SolanaUtils,
     jupiter_client: JupiterClient,
+    config: LiquidityConfig,
 }
 
 impl MarketAnalyzer {
-    pub fn new(rpc_url: &str, jupiter_api_key: &str) -> Self {
+    pub fn new(rpc_url: &str, jupiter_api_key: &str, config: LiquidityConfig) -> Self {
         Self {
             rpc_client: solana_client::rpc_client::RpcClient::new(rpc_url.to_string()),
+            solana: SolanaUtils::new(rpc_url),
             jupiter_client: JupiterClient::new(jupiter_api_key),
+            config,
         }
     }
 
@@ -24,9 +39,150 @@ impl MarketAnalyzer {
             liquidity_distribution: self.analyze_liquidity_distribution(&pools),
             pool_stability: self.assess_pool_stability(&pools),
             liquidity_depth: self.calculate_liquidity_depth(&pools),
+            pools,
         })
     }
 
+    /// Pools with `token` on either side on every supported DEX. A DEX whose scan the node
+    /// refuses is skipped with a warning rather than failing the analysis.
+    async fn fetch_liquidity_pools(&self, token: &Pubkey) -> Result<Vec<LiquidityPool>, CoralError> {
+        let mint = token.to_bytes();
+        let mut accounts = Vec::new();
+        for dex in Dex::ALL {
+            let discriminator = dex.discriminator();
+            for offset in dex.mint_offsets() {
+                let mut matches: Vec<(usize, &[u8])> = vec![(offset, mint.as_slice())];
+                if let Some(discriminator) = &discriminator {
+                    matches.push((0, discriminator.as_slice()));
+                }
+
+                match self.solana.find_program_accounts(&dex.program_id(), dex.data_size(), &matches).await {
+                    Ok(found) => accounts.extend(
+                        found
+                            .into_iter()
+                            .filter_map(|(address, data)| PoolAccount::parse(dex, address, &data)),
+                    ),
+                    Err(e) => log::warn!("{:?} pool scan for {} failed: {}", dex, token, e),
+                }
+            }
+        }
+
+        let mut pools = Vec::with_capacity(accounts.len());
+        for account in accounts {
+            let (reserve_a, reserve_b) = self.fetch_reserves(&account.reserves).await?;
+            let lp = match account.lp_mint {
+                Some(lp_mint) => self.fetch_lp_distribution(&account, &lp_mint).await?,
+                None => None,
+            };
+            pools.push(LiquidityPool {
+                dex: account.dex,
+                address: account.address,
+                mint_a: account.mint_a,
+                mint_b: account.mint_b,
+                reserve_a,
+                reserve_b,
+                lp,
+            });
+        }
+
+        Ok(pools)
+    }
+
+    async fn fetch_reserves(&self, reserves: &ReserveAccounts) -> Result<(u64, u64), CoralError> {
+        match *reserves {
+            ReserveAccounts::Vaults { a, b } => {
+                let vaults = self.solana.get_accounts_data(&[a, b]).await?;
+                let amount = |i: usize| vaults[i].as_deref().and_then(token_account_amount).unwrap_or(0);
+                Ok((amount(0), amount(1)))
+            }
+            ReserveAccounts::MeteoraVaults {
+                vault_a,
+                vault_b,
+                vault_lp_a,
+                vault_lp_b,
+            } => {
+                let accounts = self.solana.get_accounts_data(&[vault_a, vault_b, vault_lp_a, vault_lp_b]).await?;
+                let vaults: Vec<Option<MeteoraVault>> =
+                    accounts[..2].iter().map(|data| data.as_deref().and_then(MeteoraVault::parse)).collect();
+                let held: Vec<Option<u64>> =
+                    accounts[2..].iter().map(|data| data.as_deref().and_then(token_account_amount)).collect();
+
+                let lp_mints: Vec<Pubkey> = vaults.iter().map(|vault| vault.map_or_else(Pubkey::default, |v| v.lp_mint)).collect();
+                let lp_supplies: Vec<Option<u64>> = self
+                    .solana
+                    .get_accounts(&lp_mints)
+                    .await?
+                    .into_iter()
+                    .map(|account| account.and_then(|a| MintProfile::parse(&a.owner, &a.data)).map(|lp| lp.supply))
+                    .collect();
+
+                let reserve = |i: usize| match (vaults[i], held[i], lp_supplies[i]) {
+                    (Some(vault), Some(held), Some(supply)) => vault.underlying(held, supply),
+                    _ => 0,
+                };
+                Ok((reserve(0), reserve(1)))
+            }
+        }
+    }
+
+    /// Who holds the LP of `pool`. LP counts as locked when its holder is a locker, or an
+    /// account owned by a locker or by the pool's own program (Meteora keeps locked LP in
+    /// escrows of its own); the deployer is whoever had the pool account created.
+    async fn fetch_lp_distribution(&self, pool: &PoolAccount, lp_mint: &Pubkey) -> Result<Option<LpDistribution>, CoralError> {
+        let lp = match self
+            .solana
+            .get_accounts(&[*lp_mint])
+            .await?
+            .pop()
+            .flatten()
+            .and_then(|account| MintProfile::parse(&account.owner, &account.data))
+        {
+            Some(lp) => lp,
+            None => return Ok(None),
+        };
+        let holders = self.solana.get_mint_token_accounts(lp_mint, lp.program).await?;
+
+        let owners: Vec<Pubkey> = holders
+            .iter()
+            .map(|(owner, _)| *owner)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let mut locked_owners = HashSet::new();
+        for chunk in owners.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            for (owner, account) in chunk.iter().zip(self.solana.get_accounts(chunk).await?) {
+                let controlled_by_locker = account.map_or(false, |account| {
+                    self.config.is_locker(&account.owner) || account.owner == pool.dex.program_id()
+                });
+                if self.config.is_locker(owner) || controlled_by_locker {
+                    locked_owners.insert(*owner);
+                }
+            }
+        }
+
+        // Unknown for pools with a deep history; a later signer would be the wrong wallet
+        let deployer = self.solana.get_first_signer(&pool.address).await?;
+        Ok(Some(LpDistribution::classify(*lp_mint, lp.supply, pool.lp_issued, &holders, &locked_owners, deployer)))
+    }
+
+    /// Pools with an LP token that hold at least `min_pool_share` of the pooled `token`,
+    /// with that share
+    fn rated_pools<'a>(&self, token: &Pubkey, pools: &'a [LiquidityPool]) -> Vec<(&'a LiquidityPool, &'a LpDistribution, f64)> {
+        let pooled: u64 = pools.iter().map(|pool| pool.reserve_of(token)).sum();
+        if pooled == 0 {
+            return Vec::new();
+        }
+
+        pools
+            .iter()
+            .filter_map(|pool| {
+                let share = pool.reserve_of(token) as f64 / pooled as f64;
+                let lp = pool.lp.as_ref()?;
+                (share >= self.config.min_pool_share).then_some((pool, lp, share))
+            })
+            .collect()
+    }
+
     async fn analyze_price_action(&self, token: &Pubkey) -> Result<PriceAnalysis, CoralError> {
         let price_data = self.fetch_price_history(token).await?;
         
@@ -48,10 +204,98 @@ impl MarketAnalyzer {
             market_momentum: self.calculate_market_momentum(&trading_data),
         })
     }
+
+    fn collect_risk_factors(&self, token: &Pubkey, liquidity: &LiquidityAnalysis) -> Vec<RiskFactor> {
+        let mut factors = Vec::new();
+
+        for (pool, lp, pool_share) in self.rated_pools(token, &liquidity.pools) {
+            let withdrawable = lp.withdrawable_share();
+            if withdrawable <= self.config.max_withdrawable_lp_share {
+                continue;
+            }
+            // The deployer pulling its own liquidity is the rug itself
+            let severity = if lp.share(lp.deployer_held) > self.config.max_withdrawable_lp_share {
+                RiskLevel::Extreme
+            } else {
+                RiskLevel::High
+            };
+
+            let mut factor = RiskFactor::new(RiskFactorKind::UnlockedLiquidity, severity)
+                .with_evidence("pool", pool.address)
+                .with_evidence("dex", format!("{:?}", pool.dex))
+                .with_evidence("pool_share", format!("{:.4}", pool_share))
+                .with_evidence("withdrawable_lp_share", format!("{:.4}", withdrawable))
+                .with_evidence("burned_lp_share", format!("{:.4}", lp.share(lp.burned)))
+                .with_evidence("locked_lp_share", format!("{:.4}", lp.share(lp.locked)));
+            if let Some(deployer) = lp.deployer {
+                factor = factor
+                    .with_evidence("deployer", deployer)
+                    .with_evidence("deployer_lp_share", format!("{:.4}", lp.share(lp.deployer_held)));
+            }
+            factors.push(factor);
+        }
+
+        if liquidity.pool_stability.pool_age < 86_400 {
+            factors.push(
+                RiskFactor::new(RiskFactorKind::NewLiquidityPool, RiskLevel::High)
+                    .with_evidence("pool_age_secs", liquidity.pool_stability.pool_age),
+            );
+        }
+        if liquidity.liquidity_distribution.concentration_index > 0.8 {
+            factors.push(
+                RiskFactor::new(RiskFactorKind::ConcentratedLiquidity, RiskLevel::Medium).with_evidence(
+                    "concentration_index",
+                    format!("{:.2}", liquidity.liquidity_distribution.concentration_index),
+                ),
+            );
+        }
+        if liquidity.pool_stability.impermanent_loss_risk > 0.5 {
+            factors.push(
+                RiskFactor::new(RiskFactorKind::ImpermanentLossRisk, RiskLevel::Medium).with_evidence(
+                    "impermanent_loss_risk",
+                    format!("{:.2}", liquidity.pool_stability.impermanent_loss_risk),
+                ),
+            );
+        }
+
+        factors
+    }
+
+    fn collect_metrics(&self, token: &Pubkey, liquidity: &LiquidityAnalysis) -> BTreeMap<String, f64> {
+        let max_withdrawable_lp_share = self
+            .rated_pools(token, &liquidity.pools)
+            .iter()
+            .map(|(_, lp, _)| lp.withdrawable_share())
+            .fold(0.0, f64::max);
+
+        BTreeMap::from([
+            ("liquidity_pools".to_string(), liquidity.pools.len() as f64),
+            ("max_withdrawable_lp_share".to_string(), max_withdrawable_lp_share),
+            ("total_liquidity".to_string(), liquidity.total_liquidity),
+            ("liquidity_concentration".to_string(), liquidity.liquidity_distribution.concentration_index),
+            ("liquidity_stability".to_string(), liquidity.liquidity_distribution.stability_score),
+            ("pool_age".to_string(), liquidity.pool_stability.pool_age as f64),
+            ("volatility_index".to_string(), liquidity.pool_stability.volatility_index),
+            ("liquidity_depth_levels".to_string(), liquidity.liquidity_depth.bid_depth.len() as f64),
+        ])
+    }
 }
 
 #[async_trait]
 impl Analyzer for MarketAnalyzer {
+    fn name(&self) -> &'static str {
+        "market"
+    }
+
+    fn version(&self) -> u32 {
+        2
+    }
+
+    // Prices and pool reserves go stale fastest
+    fn cache_ttl(&self) -> Duration {
+        Duration::from_secs(30)
+    }
+
     async fn analyze(&self, token: &Pubkey) -> Result<AnalysisResult, CoralError> {
         let liquidity = self.analyze_liquidity(token).await?;
         let price_action = self.analyze_price_action(token).await?;
@@ -63,21 +307,26 @@ impl Analyzer for MarketAnalyzer {
             &sentiment,
         );
 
-        Ok(AnalysisResult {
-            token_address: *token,
-            authenticity_score: self.calculate_market_score(
+        Ok(AnalysisResult::new(
+            *token,
+            self.calculate_market_score(
                 &liquidity,
                 &price_action,
                 &sentiment,
             ),
             risk_level,
-            recommendation: self.generate_market_recommendation(
+            self.generate_market_recommendation(
                 &liquidity,
                 &price_action,
                 &sentiment,
             ),
-            analysis_timestamp: chrono::Utc::now().timestamp(),
-        })
+        )
+        .with_findings(
+            self.name(),
+            liquidity.liquidity_distribution.stability_score.clamp(0.0, 1.0),
+            self.collect_risk_factors(token, &liquidity),
+            self.collect_metrics(token, &liquidity),
+        ))
     }
 }
 
@@ -87,6 +336,7 @@ struct LiquidityAnalysis {
     liquidity_distribution: LiquidityDistribution,
     pool_stability: StabilityMetrics,
     liquidity_depth: DepthMetrics,
+    pools: Vec<LiquidityPool>,
 }
 
 #[derive(Debug)]
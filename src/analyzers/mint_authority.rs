@@ -0,0 +1,206 @@
+use async_trait::async_trait;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use crate::core::types::{AnalysisResult, CoralError, RiskFactor, RiskFactorKind, RiskLevel, TradeRecommendation};
+use crate::storage::Store;
+use crate::utils::mint::MintProfile;
+use crate::utils::solana::SolanaUtils;
+use crate::utils::token_registry::{metadata_address, TokenMetadata};
+use super::Analyzer;
+
+/// Audits the mint account itself: who can still mint or freeze, whether supply has grown
+/// since the token was first seen, and whether its metadata can still be rewritten
+pub struct MintAuthorityAnalyzer {
+    solana: SolanaUtils,
+    /// Keeps the first-seen supply of each mint across restarts
+    store: Option<Arc<dyn Store>>,
+    /// Supply at the first analysis of each mint in this process, without a store
+    first_supply: Mutex<HashMap<Pubkey, u64>>,
+}
+
+impl MintAuthorityAnalyzer {
+    pub fn new(rpc_url: &str) -> Self {
+        Self {
+            solana: SolanaUtils::new(rpc_url),
+            store: None,
+            first_supply: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reads and records first-seen supplies in `store`, so growth is measured from the
+    /// first analysis ever rather than the first since the process started
+    pub fn with_store(mut self, store: Arc<dyn Store>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    async fn fetch_metadata(&self, token: &Pubkey) -> Result<Option<TokenMetadata>, CoralError> {
+        let accounts = self.solana.get_accounts_data(&[metadata_address(token)]).await?;
+        Ok(accounts.into_iter().next().flatten().and_then(|data| TokenMetadata::parse(&data)))
+    }
+
+    /// Supply when the mint was first analyzed, recording `supply` on the first call
+    async fn first_supply(&self, token: &Pubkey, supply: u64) -> Result<u64, CoralError> {
+        if let Some(store) = &self.store {
+            return store.first_supply(token, supply).await;
+        }
+        Ok(*self
+            .first_supply
+            .lock()
+            .expect("supply history lock poisoned")
+            .entry(*token)
+            .or_insert(supply))
+    }
+
+    fn collect_metrics(&self, mint: &MintProfile, first_supply: u64, metadata: Option<&TokenMetadata>) -> BTreeMap<String, f64> {
+        let flag = |set: bool| if set { 1.0 } else { 0.0 };
+        BTreeMap::from([
+            ("supply".to_string(), mint.supply as f64),
+            ("supply_growth".to_string(), supply_growth(first_supply, mint.supply)),
+            ("mint_authority_active".to_string(), flag(mint.mint_authority.is_some())),
+            ("freeze_authority_active".to_string(), flag(mint.freeze_authority.is_some())),
+            ("metadata_mutable".to_string(), flag(metadata.map_or(false, |m| m.is_mutable))),
+        ])
+    }
+}
+
+#[async_trait]
+impl Analyzer for MintAuthorityAnalyzer {
+    fn name(&self) -> &'static str {
+        "mint_authority"
+    }
+
+    fn version(&self) -> u32 {
+        2
+    }
+
+    async fn analyze(&self, token: &Pubkey) -> Result<AnalysisResult, CoralError> {
+        let mint = self.solana.get_mint_profile(token).await?;
+        let metadata = self.fetch_metadata(token).await?;
+        let first_supply = self.first_supply(token, mint.supply).await?;
+
+        let risk_factors = audit(&mint, first_supply, metadata.as_ref());
+        let risk_level = risk_factors
+            .iter()
+            .map(|factor| factor.severity)
+            .max()
+            .unwrap_or(RiskLevel::Low);
+        let penalty: f64 = risk_factors.iter().map(|factor| severity_penalty(factor.severity)).sum();
+
+        // The mint alone can rule a token out but says nothing about whether to buy it
+        let recommendation = if risk_level >= RiskLevel::High {
+            TradeRecommendation::Avoid {
+                risk_factors: risk_factors.clone(),
+            }
+        } else {
+            TradeRecommendation::Abstain
+        };
+
+        // Everything here is read straight from chain, so the findings are certain
+        Ok(AnalysisResult::new(*token, (1.0 - penalty).max(0.0), risk_level, recommendation).with_findings(
+            self.name(),
+            1.0,
+            risk_factors,
+            self.collect_metrics(&mint, first_supply, metadata.as_ref()),
+        ))
+    }
+}
+
+/// Findings about who controls the mint. Supply growth only counts while the mint
+/// authority is still set; burns are expected and never flagged.
+fn audit(mint: &MintProfile, first_supply: u64, metadata: Option<&TokenMetadata>) -> Vec<RiskFactor> {
+    let mut factors = Vec::new();
+
+    if let Some(authority) = mint.mint_authority {
+        let growth = supply_growth(first_supply, mint.supply);
+        if growth > 0.0 {
+            factors.push(
+                RiskFactor::new(RiskFactorKind::SupplyIncreased, RiskLevel::Extreme)
+                    .with_evidence("first_supply", first_supply)
+                    .with_evidence("supply", mint.supply)
+                    .with_evidence("growth", format!("{:.4}", growth)),
+            );
+        }
+        factors.push(
+            RiskFactor::new(RiskFactorKind::MintAuthorityActive, RiskLevel::High)
+                .with_evidence("mint_authority", authority),
+        );
+    }
+    if let Some(authority) = mint.freeze_authority {
+        factors.push(
+            RiskFactor::new(RiskFactorKind::FreezeAuthorityActive, RiskLevel::High)
+                .with_evidence("freeze_authority", authority),
+        );
+    }
+    if let Some(metadata) = metadata.filter(|metadata| metadata.is_mutable) {
+        factors.push(
+            RiskFactor::new(RiskFactorKind::MutableMetadata, RiskLevel::Medium)
+                .with_evidence("update_authority", metadata.update_authority),
+        );
+    }
+
+    factors
+}
+
+/// Fractional change in supply since it was first seen
+fn supply_growth(first_supply: u64, supply: u64) -> f64 {
+    if first_supply == 0 {
+        return 0.0;
+    }
+    (supply as f64 - first_supply as f64) / first_supply as f64
+}
+
+fn severity_penalty(severity: RiskLevel) -> f64 {
+    match severity {
+        RiskLevel::Low => 0.0,
+        RiskLevel::Medium => 0.1,
+        RiskLevel::High => 0.3,
+        RiskLevel::Extreme => 0.6,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::mint::TokenProgram;
+
+    fn mint(mint_authority: Option<Pubkey>, freeze_authority: Option<Pubkey>, supply: u64) -> MintProfile {
+        MintProfile {
+            program: TokenProgram::SplToken,
+            mint_authority,
+            freeze_authority,
+            supply,
+            decimals: 6,
+            extensions: Vec::new(),
+        }
+    }
+
+    fn kinds(factors: &[RiskFactor]) -> Vec<(RiskFactorKind, RiskLevel)> {
+        factors.iter().map(|factor| (factor.kind, factor.severity)).collect()
+    }
+
+    #[test]
+    fn test_audit_flags_live_authorities_and_supply_growth() {
+        let authority = Some(Pubkey::new_unique());
+        let metadata = TokenMetadata {
+            update_authority: Pubkey::new_unique(),
+            name: "Token".to_string(),
+            symbol: "TKN".to_string(),
+            is_mutable: true,
+        };
+
+        assert!(audit(&mint(None, None, 1_000), 1_000, None).is_empty());
+        assert_eq!(
+            kinds(&audit(&mint(authority, authority, 1_500), 1_000, Some(&metadata))),
+            vec![
+                (RiskFactorKind::SupplyIncreased, RiskLevel::Extreme),
+                (RiskFactorKind::MintAuthorityActive, RiskLevel::High),
+                (RiskFactorKind::FreezeAuthorityActive, RiskLevel::High),
+                (RiskFactorKind::MutableMetadata, RiskLevel::Medium),
+            ]
+        );
+        // Burns with the authority revoked are not a finding
+        assert!(audit(&mint(None, None, 500), 1_000, None).is_empty());
+    }
+}
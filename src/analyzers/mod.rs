@@ -5,13 +5,41 @@ pub mod authenticity;
 pub mod wallet;
 pub mod developer;
 pub mod market;
+pub mod mint_authority;
+pub mod consensus;
+pub mod registry;
+pub mod cache;
+pub mod clusters;
+pub mod concentration;
+pub mod pools;
+pub mod holder_history;
 
 use async_trait::async_trait;
 use solana_sdk::pubkey::Pubkey;
+use std::time::Duration;
 use crate::core::types::{AnalysisResult, CoralError};
 
+pub const DEFAULT_ANALYZER_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+
 #[async_trait]
-pub trait Analyzer {
+pub trait Analyzer: Send + Sync {
+    /// Stable identifier used in configuration, logs and cache keys
+    fn name(&self) -> &'static str;
+
+    /// Bumped whenever the scoring logic changes so stale results are not reused
+    fn version(&self) -> u32;
+
+    /// Upper bound on a single `analyze` call before the agent gives up on this analyzer
+    fn timeout(&self) -> Duration {
+        DEFAULT_ANALYZER_TIMEOUT
+    }
+
+    /// How long a result stays fresh enough to reuse; zero disables caching
+    fn cache_ttl(&self) -> Duration {
+        DEFAULT_CACHE_TTL
+    }
+
     async fn analyze(&self, token: &Pubkey) -> Result<AnalysisResult, CoralError>;
 }
 
@@ -0,0 +1,472 @@
+//! Liquidity pools of a mint, decoded from the pool accounts of each DEX
+//!
+//! Pools are found by scanning each DEX program for pool accounts with the mint on either
+//! side, so a pool is seen as soon as it exists on chain. For pools that issue an LP token
+//! the LP is split by what can happen to it: burned, locked in a known locker, held by the
+//! wallet that created the pool, or held by anyone else. LP the deployer can withdraw at
+//! will is how most rugs drain a pool.
+//!
+//! Concentrated liquidity pools (Raydium CLMM, Orca Whirlpool, Meteora DLMM) have no LP
+//! token; their positions are NFTs and are not traced.
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use std::collections::HashSet;
+use crate::utils::mint::INCINERATOR;
+
+/// Lock programs whose accounts hold LP tokens until an unlock date
+const BUILT_IN_LOCKERS: [Pubkey; 2] = [
+    // Streamflow
+    pubkey!("strmRqUCoQUgGUan5YhzUZa6KqdzwX5L6FpUxfmKg5m"),
+    // Raydium liquidity locking (Burn & Earn)
+    pubkey!("LockrWmn6K5twhz3y9w1dQERbmgSaRkfnTeTKbpofwE"),
+];
+
+/// Offset of the amount in a token account, under either token program
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+/// Meteora vault: total underlying amount and the mint of its LP token
+const METEORA_VAULT_TOTAL_AMOUNT_OFFSET: usize = 11;
+const METEORA_VAULT_LP_MINT_OFFSET: usize = 115;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LiquidityConfig {
+    /// Lock programs trusted on top of the built-in ones; LP in accounts they own counts
+    /// as locked
+    #[serde(with = "crate::core::config::pubkey_list")]
+    pub lockers: Vec<Pubkey>,
+    /// Share of a pool's LP that may be withdrawable at will before the pool is a risk
+    pub max_withdrawable_lp_share: f64,
+    /// Pools holding less of the mint's pooled supply than this are not rated
+    pub min_pool_share: f64,
+}
+
+impl Default for LiquidityConfig {
+    fn default() -> Self {
+        Self {
+            lockers: Vec::new(),
+            max_withdrawable_lp_share: 0.05,
+            min_pool_share: 0.1,
+        }
+    }
+}
+
+impl LiquidityConfig {
+    pub fn is_locker(&self, program: &Pubkey) -> bool {
+        BUILT_IN_LOCKERS.contains(program) || self.lockers.contains(program)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Dex {
+    RaydiumAmm,
+    RaydiumClmm,
+    OrcaWhirlpool,
+    MeteoraDlmm,
+    MeteoraDynamicAmm,
+}
+
+/// Byte offsets of the fields a pool account is decoded for
+struct Layout {
+    /// Exact account size, for programs without an account discriminator
+    size: Option<u64>,
+    /// Anchor account discriminator, the first 8 bytes of the account
+    discriminator: Option<[u8; 8]>,
+    mints: [usize; 2],
+    vaults: [usize; 2],
+    /// Accounts holding the pool's LP of each Meteora vault, for pools that keep their
+    /// reserves in vaults
+    vault_lps: Option<[usize; 2]>,
+    lp_mint: Option<usize>,
+    lp_issued: Option<usize>,
+}
+
+impl Dex {
+    pub const ALL: [Dex; 5] = [
+        Dex::RaydiumAmm,
+        Dex::RaydiumClmm,
+        Dex::OrcaWhirlpool,
+        Dex::MeteoraDlmm,
+        Dex::MeteoraDynamicAmm,
+    ];
+
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            Dex::RaydiumAmm => pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8"),
+            Dex::RaydiumClmm => pubkey!("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK"),
+            Dex::OrcaWhirlpool => pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc"),
+            Dex::MeteoraDlmm => pubkey!("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo"),
+            Dex::MeteoraDynamicAmm => pubkey!("Eo7WjKq67rjJQSZxS6z3YkapzY3eMj6Xy8X5EQVn5UaB"),
+        }
+    }
+
+    /// Size every pool account of this DEX has, when that is what identifies them
+    pub fn data_size(&self) -> Option<u64> {
+        self.layout().size
+    }
+
+    pub fn discriminator(&self) -> Option<[u8; 8]> {
+        self.layout().discriminator
+    }
+
+    /// Offsets of the two mints of a pool
+    pub fn mint_offsets(&self) -> [usize; 2] {
+        self.layout().mints
+    }
+
+    fn layout(&self) -> Layout {
+        match self {
+            // Raydium AMM v4 predates Anchor; its pools are the program's 752 byte accounts
+            Dex::RaydiumAmm => Layout {
+                size: Some(752),
+                discriminator: None,
+                mints: [400, 432],
+                vaults: [336, 368],
+                vault_lps: None,
+                lp_mint: Some(464),
+                lp_issued: Some(720),
+            },
+            Dex::RaydiumClmm => Layout {
+                size: None,
+                discriminator: Some([247, 237, 227, 245, 215, 195, 222, 70]),
+                mints: [73, 105],
+                vaults: [137, 169],
+                vault_lps: None,
+                lp_mint: None,
+                lp_issued: None,
+            },
+            Dex::OrcaWhirlpool => Layout {
+                size: None,
+                discriminator: Some([63, 149, 209, 12, 225, 128, 99, 9]),
+                mints: [101, 181],
+                vaults: [133, 213],
+                vault_lps: None,
+                lp_mint: None,
+                lp_issued: None,
+            },
+            Dex::MeteoraDlmm => Layout {
+                size: None,
+                discriminator: Some([33, 11, 49, 98, 181, 101, 177, 13]),
+                mints: [88, 120],
+                vaults: [152, 184],
+                vault_lps: None,
+                lp_mint: None,
+                lp_issued: None,
+            },
+            Dex::MeteoraDynamicAmm => Layout {
+                size: None,
+                discriminator: Some([241, 154, 109, 4, 17, 177, 109, 188]),
+                mints: [40, 72],
+                vaults: [104, 136],
+                vault_lps: Some([168, 200]),
+                lp_mint: Some(8),
+                lp_issued: None,
+            },
+        }
+    }
+}
+
+/// Accounts a pool's reserves are read from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReserveAccounts {
+    /// Token accounts holding each side
+    Vaults { a: Pubkey, b: Pubkey },
+    /// Meteora vaults each side is deposited in, and the accounts holding the pool's LP of
+    /// each vault; a side's reserve is the pool's share of its vault
+    MeteoraVaults {
+        vault_a: Pubkey,
+        vault_b: Pubkey,
+        vault_lp_a: Pubkey,
+        vault_lp_b: Pubkey,
+    },
+}
+
+/// A pool account as decoded, before its balances are looked up
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolAccount {
+    pub dex: Dex,
+    pub address: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub reserves: ReserveAccounts,
+    pub lp_mint: Option<Pubkey>,
+    /// LP the pool counts as outstanding, for pools that track it. LP burned through the
+    /// token program lowers the mint supply but not this.
+    pub lp_issued: Option<u64>,
+}
+
+impl PoolAccount {
+    /// `None` if `data` is not a pool account of `dex`
+    pub fn parse(dex: Dex, address: Pubkey, data: &[u8]) -> Option<Self> {
+        let layout = dex.layout();
+        if layout.size.map_or(false, |size| data.len() as u64 != size) {
+            return None;
+        }
+        if let Some(discriminator) = layout.discriminator {
+            if data.get(..8)? != discriminator {
+                return None;
+            }
+        }
+
+        let [vault_a, vault_b] = layout.vaults;
+        let reserves = match layout.vault_lps {
+            Some([vault_lp_a, vault_lp_b]) => ReserveAccounts::MeteoraVaults {
+                vault_a: read_pubkey(data, vault_a)?,
+                vault_b: read_pubkey(data, vault_b)?,
+                vault_lp_a: read_pubkey(data, vault_lp_a)?,
+                vault_lp_b: read_pubkey(data, vault_lp_b)?,
+            },
+            None => ReserveAccounts::Vaults {
+                a: read_pubkey(data, vault_a)?,
+                b: read_pubkey(data, vault_b)?,
+            },
+        };
+
+        Some(Self {
+            dex,
+            address,
+            mint_a: read_pubkey(data, layout.mints[0])?,
+            mint_b: read_pubkey(data, layout.mints[1])?,
+            reserves,
+            lp_mint: layout.lp_mint.and_then(|offset| read_pubkey(data, offset)),
+            lp_issued: layout.lp_issued.and_then(|offset| read_u64(data, offset)),
+        })
+    }
+}
+
+/// A Meteora vault, which lends out deposits and issues its own LP against them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeteoraVault {
+    pub total_amount: u64,
+    pub lp_mint: Pubkey,
+}
+
+impl MeteoraVault {
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        Some(Self {
+            total_amount: read_u64(data, METEORA_VAULT_TOTAL_AMOUNT_OFFSET)?,
+            lp_mint: read_pubkey(data, METEORA_VAULT_LP_MINT_OFFSET)?,
+        })
+    }
+
+    /// Underlying tokens `lp_amount` of the vault's LP is worth
+    pub fn underlying(&self, lp_amount: u64, lp_supply: u64) -> u64 {
+        if lp_supply == 0 {
+            return 0;
+        }
+        (self.total_amount as u128 * lp_amount as u128 / lp_supply as u128) as u64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiquidityPool {
+    pub dex: Dex,
+    pub address: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    /// `None` for concentrated liquidity pools, which have no LP token
+    pub lp: Option<LpDistribution>,
+}
+
+impl LiquidityPool {
+    /// Amount of `mint` in the pool; zero if it is on neither side
+    pub fn reserve_of(&self, mint: &Pubkey) -> u64 {
+        if self.mint_a == *mint {
+            self.reserve_a
+        } else if self.mint_b == *mint {
+            self.reserve_b
+        } else {
+            0
+        }
+    }
+}
+
+/// What holds most of a pool's LP
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LpStatus {
+    Burned,
+    Locked,
+    Unlocked,
+    DeployerHeld,
+}
+
+/// A pool's LP split by what can happen to it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LpDistribution {
+    pub mint: Pubkey,
+    /// Current supply of the LP mint
+    pub supply: u64,
+    /// Burned through the token program or sent to the incinerator
+    pub burned: u64,
+    pub locked: u64,
+    /// Wallet that created the pool; `None` when the pool's history is too deep to reach
+    /// its first transaction, in which case LP the deployer holds counts as `other`
+    pub deployer: Option<Pubkey>,
+    pub deployer_held: u64,
+    /// Held by anyone else, and so withdrawable at will
+    pub other: u64,
+}
+
+impl LpDistribution {
+    /// Splits LP held as `(owner, amount)`. `locked_owners` are the holders whose
+    /// accounts a locker controls; `issued` is the pool's own count of outstanding LP, so
+    /// LP burned through the token program is counted too.
+    pub fn classify(
+        mint: Pubkey,
+        supply: u64,
+        issued: Option<u64>,
+        holders: &[(Pubkey, u64)],
+        locked_owners: &HashSet<Pubkey>,
+        deployer: Option<Pubkey>,
+    ) -> Self {
+        let mut distribution = Self {
+            mint,
+            supply,
+            burned: issued.map_or(0, |issued| issued.saturating_sub(supply)),
+            locked: 0,
+            deployer,
+            deployer_held: 0,
+            other: 0,
+        };
+
+        for (owner, amount) in holders {
+            let bucket = if *owner == INCINERATOR {
+                &mut distribution.burned
+            } else if locked_owners.contains(owner) {
+                &mut distribution.locked
+            } else if Some(*owner) == deployer {
+                &mut distribution.deployer_held
+            } else {
+                &mut distribution.other
+            };
+            *bucket += amount;
+        }
+
+        distribution
+    }
+
+    pub fn total(&self) -> u64 {
+        self.burned + self.locked + self.deployer_held + self.other
+    }
+
+    pub fn share(&self, amount: u64) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => amount as f64 / total as f64,
+        }
+    }
+
+    /// Share of the LP its holders can redeem for the reserves at any time
+    pub fn withdrawable_share(&self) -> f64 {
+        self.share(self.deployer_held + self.other)
+    }
+
+    /// Largest part of the LP, the riskier one on a tie; `None` when no LP was issued
+    pub fn status(&self) -> Option<LpStatus> {
+        if self.total() == 0 {
+            return None;
+        }
+        [
+            (LpStatus::Burned, self.burned),
+            (LpStatus::Locked, self.locked),
+            (LpStatus::Unlocked, self.other),
+            (LpStatus::DeployerHeld, self.deployer_held),
+        ]
+        .into_iter()
+        .max_by_key(|(_, amount)| *amount)
+        .map(|(status, _)| status)
+    }
+}
+
+/// Amount held by a token account under either token program
+pub fn token_account_amount(data: &[u8]) -> Option<u64> {
+    read_u64(data, TOKEN_ACCOUNT_AMOUNT_OFFSET)
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Option<Pubkey> {
+    Pubkey::try_from(data.get(offset..offset + 32)?).ok()
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(data: &mut [u8], offset: usize, bytes: &[u8]) {
+        data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    #[test]
+    fn test_parses_pool_accounts() {
+        let (mint_a, mint_b, vault_a, vault_b, lp_mint) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        let mut amm = vec![0u8; 752];
+        write(&mut amm, 336, vault_a.as_ref());
+        write(&mut amm, 368, vault_b.as_ref());
+        write(&mut amm, 400, mint_a.as_ref());
+        write(&mut amm, 432, mint_b.as_ref());
+        write(&mut amm, 464, lp_mint.as_ref());
+        write(&mut amm, 720, &1_000u64.to_le_bytes());
+        let pool = PoolAccount::parse(Dex::RaydiumAmm, Pubkey::new_unique(), &amm).unwrap();
+        assert_eq!((pool.mint_a, pool.mint_b), (mint_a, mint_b));
+        assert_eq!(pool.reserves, ReserveAccounts::Vaults { a: vault_a, b: vault_b });
+        assert_eq!((pool.lp_mint, pool.lp_issued), (Some(lp_mint), Some(1_000)));
+        assert!(PoolAccount::parse(Dex::RaydiumAmm, Pubkey::new_unique(), &amm[..700]).is_none());
+
+        let mut whirlpool = vec![0u8; 653];
+        write(&mut whirlpool, 0, &Dex::OrcaWhirlpool.discriminator().unwrap());
+        write(&mut whirlpool, 101, mint_a.as_ref());
+        write(&mut whirlpool, 133, vault_a.as_ref());
+        write(&mut whirlpool, 181, mint_b.as_ref());
+        write(&mut whirlpool, 213, vault_b.as_ref());
+        let pool = PoolAccount::parse(Dex::OrcaWhirlpool, Pubkey::new_unique(), &whirlpool).unwrap();
+        assert_eq!((pool.mint_a, pool.mint_b, pool.lp_mint), (mint_a, mint_b, None));
+        assert_eq!(pool.reserves, ReserveAccounts::Vaults { a: vault_a, b: vault_b });
+        // Another program's account with the same fields is not a Whirlpool
+        assert!(PoolAccount::parse(Dex::MeteoraDlmm, Pubkey::new_unique(), &whirlpool).is_none());
+    }
+
+    #[test]
+    fn test_lp_distribution_classifies_holders() {
+        let (deployer, locker_escrow, trader) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let holders = vec![(INCINERATOR, 100), (locker_escrow, 200), (deployer, 600), (trader, 50)];
+
+        // 50 more was issued than the mint supply shows: burned through the token program
+        let lp = LpDistribution::classify(
+            Pubkey::new_unique(),
+            950,
+            Some(1_000),
+            &holders,
+            &HashSet::from([locker_escrow]),
+            Some(deployer),
+        );
+        assert_eq!((lp.burned, lp.locked, lp.deployer_held, lp.other), (150, 200, 600, 50));
+        assert_eq!(lp.status(), Some(LpStatus::DeployerHeld));
+        assert!((lp.withdrawable_share() - 0.65).abs() < 1e-9);
+
+        let burned = LpDistribution::classify(Pubkey::new_unique(), 0, Some(1_000), &[], &HashSet::new(), None);
+        assert_eq!(burned.status(), Some(LpStatus::Burned));
+        assert_eq!(burned.withdrawable_share(), 0.0);
+    }
+
+    #[test]
+    fn test_meteora_vault_share() {
+        let vault = MeteoraVault {
+            total_amount: 3_000,
+            lp_mint: Pubkey::new_unique(),
+        };
+        assert_eq!(vault.underlying(250, 1_000), 750);
+        assert_eq!(vault.underlying(250, 0), 0);
+    }
+}
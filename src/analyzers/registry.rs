@@ -0,0 +1,218 @@
+//! Named, weighted and switchable set of analyzers run by the agent
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::core::types::CoralError;
+use super::Analyzer;
+
+/// Per-analyzer settings, configured under `[analysis.analyzers.<name>]`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalyzerSettings {
+    pub enabled: bool,
+    /// Vote weight in the consensus. Analyzers weighted 0 do not vote, but their risk
+    /// level still counts and can veto.
+    pub weight: f64,
+    /// Overrides `Analyzer::timeout` when set
+    pub timeout_ms: Option<u64>,
+    /// Overrides `Analyzer::cache_ttl` when set; 0 disables caching
+    pub cache_ttl_ms: Option<u64>,
+}
+
+impl Default for AnalyzerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            weight: 1.0,
+            timeout_ms: None,
+            cache_ttl_ms: None,
+        }
+    }
+}
+
+pub struct RegisteredAnalyzer {
+    analyzer: Box<dyn Analyzer>,
+    settings: AnalyzerSettings,
+}
+
+impl RegisteredAnalyzer {
+    pub fn name(&self) -> &'static str {
+        self.analyzer.name()
+    }
+
+    pub fn version(&self) -> u32 {
+        self.analyzer.version()
+    }
+
+    pub fn weight(&self) -> f64 {
+        self.settings.weight
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.settings.enabled
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.settings
+            .timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or_else(|| self.analyzer.timeout())
+    }
+
+    pub fn cache_ttl(&self) -> Duration {
+        self.settings
+            .cache_ttl_ms
+            .map(Duration::from_millis)
+            .unwrap_or_else(|| self.analyzer.cache_ttl())
+    }
+
+    pub fn analyzer(&self) -> &dyn Analyzer {
+        self.analyzer.as_ref()
+    }
+}
+
+#[derive(Default)]
+pub struct AnalyzerRegistry {
+    entries: Vec<RegisteredAnalyzer>,
+}
+
+impl AnalyzerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a registry, applying `settings` by analyzer name. Settings that name no
+    /// registered analyzer are rejected so typos in the config do not go unnoticed.
+    pub fn from_analyzers(
+        analyzers: Vec<Box<dyn Analyzer>>,
+        settings: &HashMap<String, AnalyzerSettings>,
+    ) -> Result<Self, CoralError> {
+        let mut registry = Self::new();
+        for analyzer in analyzers {
+            let analyzer_settings = settings.get(analyzer.name()).cloned().unwrap_or_default();
+            registry.register(analyzer, analyzer_settings)?;
+        }
+
+        if let Some(unknown) = settings.keys().find(|name| !registry.contains(name)) {
+            return Err(CoralError::config(
+                format!("analysis.analyzers.{}", unknown),
+                "no analyzer registered under this name",
+            ));
+        }
+
+        Ok(registry)
+    }
+
+    pub fn register(&mut self, analyzer: Box<dyn Analyzer>, settings: AnalyzerSettings) -> Result<(), CoralError> {
+        if self.contains(analyzer.name()) {
+            return Err(CoralError::config(
+                format!("analysis.analyzers.{}", analyzer.name()),
+                "registered more than once",
+            ));
+        }
+
+        log::info!(
+            "Registered analyzer {} v{} (enabled: {}, weight: {})",
+            analyzer.name(),
+            analyzer.version(),
+            settings.enabled,
+            settings.weight
+        );
+        self.entries.push(RegisteredAnalyzer { analyzer, settings });
+        Ok(())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.iter().any(|entry| entry.name() == name)
+    }
+
+    pub fn enabled(&self) -> impl Iterator<Item = &RegisteredAnalyzer> {
+        self.entries.iter().filter(|entry| entry.is_enabled())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use solana_sdk::pubkey::Pubkey;
+    use crate::core::types::AnalysisResult;
+
+    struct Named(&'static str);
+
+    #[async_trait]
+    impl Analyzer for Named {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn version(&self) -> u32 {
+            1
+        }
+
+        async fn analyze(&self, _token: &Pubkey) -> Result<AnalysisResult, CoralError> {
+            Err(CoralError::ServiceUnavailable {
+                service: self.0.to_string(),
+                reason: "not used by these tests".to_string(),
+            })
+        }
+    }
+
+    fn settings(entries: &[(&str, AnalyzerSettings)]) -> HashMap<String, AnalyzerSettings> {
+        entries.iter().map(|(name, settings)| (name.to_string(), settings.clone())).collect()
+    }
+
+    #[test]
+    fn test_settings_apply_by_name() {
+        let registry = AnalyzerRegistry::from_analyzers(
+            vec![Box::new(Named("wallet")), Box::new(Named("market"))],
+            &settings(&[
+                ("wallet", AnalyzerSettings {
+                    weight: 2.5,
+                    timeout_ms: Some(1_500),
+                    cache_ttl_ms: Some(0),
+                    ..Default::default()
+                }),
+                ("market", AnalyzerSettings {
+                    enabled: false,
+                    ..Default::default()
+                }),
+            ]),
+        )
+        .unwrap();
+
+        let enabled: Vec<&RegisteredAnalyzer> = registry.enabled().collect();
+        assert_eq!(enabled.len(), 1);
+        assert_eq!(enabled[0].name(), "wallet");
+        assert_eq!(enabled[0].weight(), 2.5);
+        assert_eq!(enabled[0].timeout(), Duration::from_millis(1_500));
+        assert_eq!(enabled[0].cache_ttl(), Duration::ZERO);
+        assert!(registry.contains("market"));
+    }
+
+    #[test]
+    fn test_unconfigured_analyzer_uses_its_own_defaults() {
+        let registry = AnalyzerRegistry::from_analyzers(vec![Box::new(Named("wallet"))], &HashMap::new()).unwrap();
+        let entry = registry.enabled().next().unwrap();
+
+        assert_eq!(entry.weight(), 1.0);
+        assert_eq!(entry.timeout(), Named("wallet").timeout());
+        assert_eq!(entry.cache_ttl(), Named("wallet").cache_ttl());
+    }
+
+    #[test]
+    fn test_rejects_unknown_settings_and_duplicate_names() {
+        let result = AnalyzerRegistry::from_analyzers(
+            vec![Box::new(Named("wallet"))],
+            &settings(&[("walet", AnalyzerSettings::default())]),
+        );
+        assert!(matches!(result, Err(CoralError::Config { field, .. }) if field == "analysis.analyzers.walet"));
+
+        let result = AnalyzerRegistry::from_analyzers(
+            vec![Box::new(Named("wallet")), Box::new(Named("wallet"))],
+            &HashMap::new(),
+        );
+        assert!(matches!(result, Err(CoralError::Config { field, .. }) if field == "analysis.analyzers.wallet"));
+    }
+}
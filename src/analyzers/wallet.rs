@@ -1,62 +1,300 @@
 use async_trait::async_trait;
-use solana_sdk::pubkey::Pubkey;
-use crate::core::types::{AnalysisResult, CoralError};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::core::types::{AnalysisResult, CoralError, RiskFactor, RiskFactorKind, RiskLevel, TradeRecommendation};
+use crate::utils::mint::{MintProfile, INCINERATOR};
+use crate::utils::solana::SolanaUtils;
+use super::clusters::{self, Acquisition, ClusterConfig, ClusterKind, Funding, WalletCluster};
+use super::concentration::{ConcentrationMetrics, ConcentrationThresholds};
+use super::holder_history::SnapshotConfig;
 use super::Analyzer;
 
+/// Owners that never represent a real holder, whatever the configuration says
+const BUILT_IN_EXCLUDED_OWNERS: [Pubkey; 3] = [
+    // Tokens sent here are burned
+    INCINERATOR,
+    // Raydium AMM v4 and CPMM pool authorities, which own every pool vault
+    pubkey!("5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1"),
+    pubkey!("GpMZbSM2GgvTKHJirzeGfMFoaZ8UR2X7F4v8vHTvxFbL"),
+];
+
+/// Which token accounts count as holders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HolderConfig {
+    /// Protocol vaults, lockers and treasuries to leave out, by owner
+    #[serde(with = "crate::core::config::pubkey_list")]
+    pub excluded_owners: Vec<Pubkey>,
+    /// Leaves out accounts owned by a program address (off-curve), which covers most
+    /// pool vaults and lockers without listing them. Off by default: multisigs and
+    /// program-controlled treasuries are off-curve too, and hiding them understates
+    /// concentration.
+    pub exclude_program_owned: bool,
+    /// Largest holders whose transaction history is fetched; the rest only have a balance
+    pub history_depth: usize,
+    /// Per-holder history and funding lookups in flight at once
+    pub lookup_concurrency: usize,
+    pub concentration: ConcentrationThresholds,
+    pub clusters: ClusterConfig,
+    pub snapshots: SnapshotConfig,
+}
+
+impl Default for HolderConfig {
+    fn default() -> Self {
+        Self {
+            excluded_owners: Vec::new(),
+            exclude_program_owned: false,
+            history_depth: 100,
+            lookup_concurrency: 8,
+            concentration: ConcentrationThresholds::default(),
+            clusters: ClusterConfig::default(),
+            snapshots: SnapshotConfig::default(),
+        }
+    }
+}
+
+impl HolderConfig {
+    pub fn is_excluded(&self, owner: &Pubkey) -> bool {
+        BUILT_IN_EXCLUDED_OWNERS.contains(owner)
+            || self.excluded_owners.contains(owner)
+            || (self.exclude_program_owned && !owner.is_on_curve())
+    }
+}
+
 pub struct WalletAnalyzer {
     rpc_client: solana_client::rpc_client::RpcClient,
+    solana: Arc<SolanaUtils>,
+    config: HolderConfig,
 }
 
 impl WalletAnalyzer {
-    pub fn new(rpc_url: &str) -> Self {
+    pub fn new(rpc_url: &str, config: HolderConfig) -> Self {
         Self {
             rpc_client: solana_client::rpc_client::RpcClient::new(rpc_url.to_string()),
+            solana: Arc::new(SolanaUtils::new(rpc_url)),
+            config,
         }
     }
 
-    async fn analyze_holder_distribution(&self, token: &Pubkey) -> Result<HolderDistribution, CoralError> {
-        let holders = self.fetch_token_holders(token).await?;
-        
-        Ok(HolderDistribution {
+    /// Runs an RPC lookup on the blocking pool, since the RPC client blocks its thread.
+    /// Every call this analyzer makes goes through here, the holder scan above all.
+    async fn lookup<T, F, Fut>(&self, lookup: F) -> Result<T, CoralError>
+    where
+        T: Send + 'static,
+        F: FnOnce(Arc<SolanaUtils>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T, CoralError>>,
+    {
+        let solana = Arc::clone(&self.solana);
+        let runtime = tokio::runtime::Handle::current();
+        tokio::task::spawn_blocking(move || runtime.block_on(lookup(solana)))
+            .await
+            .map_err(|e| CoralError::network("holder lookup task failed", e))?
+    }
+
+    fn analyze_holder_distribution(&self, holders: &[HolderInfo]) -> HolderDistribution {
+        let balances: Vec<u64> = holders.iter().map(|holder| holder.balance).collect();
+        HolderDistribution {
             total_holders: holders.len(),
-            new_wallet_ratio: self.calculate_new_wallet_ratio(&holders),
-            concentration_score: self.calculate_concentration_score(&holders),
-            whale_dominance: self.calculate_whale_dominance(&holders),
-        })
+            new_wallet_ratio: self.calculate_new_wallet_ratio(holders),
+            concentration: ConcentrationMetrics::from_balances(&balances, self.config.concentration.top_n),
+        }
     }
 
-    async fn analyze_fund_sources(&self, token: &Pubkey) -> Result<FundSourceAnalysis, CoralError> {
-        let transactions = self.fetch_holder_transactions(token).await?;
-        
-        Ok(FundSourceAnalysis {
-            unique_sources: self.count_unique_sources(&transactions),
-            source_concentration: self.calculate_source_concentration(&transactions),
-            suspicious_patterns: self.detect_suspicious_patterns(&transactions),
-        })
+    /// Traces where the largest holders got their SOL and when they first received the
+    /// token, then groups them into clusters (see `clusters`)
+    async fn analyze_fund_sources(&self, token: &Pubkey, mint: &MintProfile, holders: &[HolderInfo]) -> FundSourceAnalysis {
+        let config = &self.config.clusters;
+        let traced = &holders[..holders.len().min(config.depth)];
+
+        let traces: Vec<_> = stream::iter(traced)
+            .map(|holder| async move {
+                let wallet = holder.address;
+                // Only associated token accounts are checked; tokens held elsewhere are missed
+                let token_account = mint.program.associated_token_address(&wallet, token);
+                let funding = self.lookup(move |solana| async move { solana.get_first_funding(&wallet).await }).await;
+                let acquisition = self.lookup(move |solana| async move { solana.get_first_slot(&token_account).await }).await;
+                (wallet, funding, acquisition)
+            })
+            .buffer_unordered(self.config.lookup_concurrency.max(1))
+            .collect()
+            .await;
+
+        let mut fundings = Vec::new();
+        let mut acquisitions = Vec::new();
+        for (wallet, funding, acquisition) in traces {
+            match funding {
+                Ok(Some(first)) => fundings.push(Funding {
+                    wallet,
+                    funder: first.funder,
+                    timestamp: first.block_time.unwrap_or(0),
+                    lamports: first.lamports,
+                }),
+                Ok(None) => {}
+                Err(e) => log::warn!("Funding trace for holder {} of {} failed: {}", wallet, token, e),
+            }
+            match acquisition {
+                Ok(Some(slot)) => acquisitions.push(Acquisition { wallet, slot }),
+                Ok(None) => {}
+                Err(e) => log::warn!("Acquisition trace for holder {} of {} failed: {}", wallet, token, e),
+            }
+        }
+
+        let balances: HashMap<Pubkey, u64> = traced.iter().map(|h| (h.address, h.balance)).collect();
+        let mut wallet_clusters = clusters::funding_clusters(&fundings, config, &balances, mint.supply);
+        wallet_clusters.extend(clusters::bundled_buys(&acquisitions, config, &balances, mint.supply));
+
+        let mut per_funder: HashMap<Pubkey, usize> = HashMap::new();
+        for funding in &fundings {
+            *per_funder.entry(funding.funder).or_default() += 1;
+        }
+        let largest_source = per_funder.values().copied().max().unwrap_or(0);
+
+        FundSourceAnalysis {
+            unique_sources: per_funder.len(),
+            source_concentration: if fundings.is_empty() { 0.0 } else { largest_source as f64 / fundings.len() as f64 },
+            clusters: wallet_clusters,
+        }
+    }
+
+    /// Every real holder of `token`, largest first. The history of the largest
+    /// `history_depth` holders is filled in; a failed history lookup leaves it unknown
+    /// rather than failing the analysis.
+    async fn fetch_token_holders(&self, token: &Pubkey, mint: &MintProfile) -> Result<Vec<HolderInfo>, CoralError> {
+        let (mint_address, program) = (*token, mint.program);
+        let accounts = self
+            .lookup(move |solana| async move { solana.get_mint_token_accounts(&mint_address, program).await })
+            .await?;
+        let mut holders = aggregate_holders(accounts, &self.config);
+
+        let depth = holders.len().min(self.config.history_depth);
+        let histories: Vec<_> = stream::iter(holders[..depth].iter().map(|holder| holder.address).enumerate())
+            .map(|(index, address)| async move {
+                let history = self.lookup(move |solana| async move { solana.get_address_history(&address).await }).await;
+                (index, history)
+            })
+            .buffer_unordered(self.config.lookup_concurrency.max(1))
+            .collect()
+            .await;
+
+        for (index, history) in histories {
+            let holder = &mut holders[index];
+            match history {
+                Ok(history) => {
+                    holder.first_transaction_date = history.first_seen;
+                    holder.transaction_count = Some(history.transaction_count);
+                }
+                Err(e) => log::warn!("History lookup for holder {} of {} failed: {}", holder.address, token, e),
+            }
+        }
+
+        Ok(holders)
+    }
+
+    /// Small holder sets make every distribution metric noisy
+    fn calculate_confidence(&self, distribution: &HolderDistribution) -> f64 {
+        (distribution.total_holders as f64 / 100.0).min(1.0)
+    }
+
+    fn collect_risk_factors(&self, distribution: &HolderDistribution, fund_sources: &FundSourceAnalysis) -> Vec<RiskFactor> {
+        let mut factors = Vec::new();
+
+        let concentration = &distribution.concentration;
+        let thresholds = &self.config.concentration;
+        if let Some(severity) = thresholds.control_level(concentration) {
+            factors.push(
+                RiskFactor::new(RiskFactorKind::HolderConcentration, severity)
+                    .with_evidence("gini", format!("{:.3}", concentration.gini))
+                    .with_evidence("hhi", format!("{:.4}", concentration.hhi))
+                    .with_evidence("nakamoto_coefficient", concentration.nakamoto),
+            );
+        }
+        if let Some(severity) = thresholds.top_n_level(concentration) {
+            factors.push(
+                RiskFactor::new(RiskFactorKind::WhaleDominance, severity)
+                    .with_evidence("top_n", concentration.top_n)
+                    .with_evidence("top_n_share", format!("{:.3}", concentration.top_n_share)),
+            );
+        }
+        if distribution.new_wallet_ratio > 0.5 {
+            factors.push(
+                RiskFactor::new(RiskFactorKind::NewWalletInflux, RiskLevel::Medium)
+                    .with_evidence("new_wallet_ratio", format!("{:.2}", distribution.new_wallet_ratio)),
+            );
+        }
+        for cluster in &fund_sources.clusters {
+            factors.push(cluster_risk_factor(cluster));
+        }
+
+        factors
     }
 
-    async fn fetch_token_holders(&self, token: &Pubkey) -> Result<Vec<HolderInfo>, CoralError> {
-        // Implementation for fetching token holders
-        todo!()
+    fn collect_metrics(&self, distribution: &HolderDistribution, fund_sources: &FundSourceAnalysis) -> BTreeMap<String, f64> {
+        BTreeMap::from([
+            ("total_holders".to_string(), distribution.total_holders as f64),
+            ("new_wallet_ratio".to_string(), distribution.new_wallet_ratio),
+            ("gini".to_string(), distribution.concentration.gini),
+            ("hhi".to_string(), distribution.concentration.hhi),
+            ("top_n_share".to_string(), distribution.concentration.top_n_share),
+            ("nakamoto_coefficient".to_string(), distribution.concentration.nakamoto as f64),
+            ("unique_fund_sources".to_string(), fund_sources.unique_sources as f64),
+            ("source_concentration".to_string(), fund_sources.source_concentration),
+            ("suspicious_patterns".to_string(), fund_sources.clusters.len() as f64),
+            ("clustered_supply_share".to_string(), fund_sources.clusters.iter().map(|c| c.supply_share).sum()),
+        ])
     }
 }
 
 #[async_trait]
 impl Analyzer for WalletAnalyzer {
+    fn name(&self) -> &'static str {
+        "wallet"
+    }
+
+    fn version(&self) -> u32 {
+        2
+    }
+
+    // Holder balances move with every trade
+    fn cache_ttl(&self) -> Duration {
+        Duration::from_secs(60)
+    }
+
     async fn analyze(&self, token: &Pubkey) -> Result<AnalysisResult, CoralError> {
-        let distribution = self.analyze_holder_distribution(token).await?;
-        let fund_sources = self.analyze_fund_sources(token).await?;
-        
-        let risk_level = self.calculate_overall_risk(&distribution, &fund_sources);
-        let recommendation = self.generate_recommendation(&distribution, &fund_sources);
-
-        Ok(AnalysisResult {
-            token_address: *token,
-            authenticity_score: self.calculate_authenticity_score(&distribution, &fund_sources),
+        let mint_address = *token;
+        let mint = self
+            .lookup(move |solana| async move { solana.get_mint_profile(&mint_address).await })
+            .await?;
+        let holders = self.fetch_token_holders(token, &mint).await?;
+        let distribution = self.analyze_holder_distribution(&holders);
+        let fund_sources = self.analyze_fund_sources(token, &mint, &holders).await;
+
+        let risk_factors = self.collect_risk_factors(&distribution, &fund_sources);
+        let risk_level = overall_risk(&risk_factors);
+        let recommendation = if risk_level >= RiskLevel::High {
+            TradeRecommendation::Avoid {
+                risk_factors: risk_factors.clone(),
+            }
+        } else {
+            self.generate_recommendation(&distribution, &fund_sources)
+        };
+
+        Ok(AnalysisResult::new(
+            *token,
+            self.calculate_authenticity_score(&distribution, &fund_sources),
             risk_level,
             recommendation,
-            analysis_timestamp: chrono::Utc::now().timestamp(),
-        })
+        )
+        .with_findings(
+            self.name(),
+            self.calculate_confidence(&distribution),
+            risk_factors,
+            self.collect_metrics(&distribution, &fund_sources),
+        ))
     }
 }
 
@@ -64,28 +302,138 @@ impl Analyzer for WalletAnalyzer {
 struct HolderDistribution {
     total_holders: usize,
     new_wallet_ratio: f64,
-    concentration_score: f64,
-    whale_dominance: f64,
+    concentration: ConcentrationMetrics,
 }
 
 #[derive(Debug)]
 struct FundSourceAnalysis {
     unique_sources: usize,
     source_concentration: f64,
-    suspicious_patterns: Vec<SuspiciousPattern>,
+    /// Holders likely controlled by one party
+    clusters: Vec<WalletCluster>,
 }
 
+/// One owner's combined balance across its token accounts
 #[derive(Debug)]
 struct HolderInfo {
     address: Pubkey,
     balance: u64,
-    first_transaction_date: i64,
-    transaction_count: u64,
+    /// Unknown beyond `history_depth`, and for wallets whose history is too deep to read
+    first_transaction_date: Option<i64>,
+    /// Capped at the history read; see `AddressHistory`
+    transaction_count: Option<u64>,
 }
 
-#[derive(Debug)]
-struct SuspiciousPattern {
-    pattern_type: String,
-    severity: f64,
-    affected_addresses: Vec<Pubkey>,
+/// The most severe finding sets the level: concentration past its thresholds and
+/// clusters of coordinated wallets both raise it
+fn overall_risk(factors: &[RiskFactor]) -> RiskLevel {
+    factors
+        .iter()
+        .map(|factor| factor.severity)
+        .max()
+        .unwrap_or(RiskLevel::Low)
+}
+
+/// A cluster is rated by how much of the supply it could dump at once. Bundled launch
+/// buys are never rated below High, since they point at the deployer.
+fn cluster_risk_factor(cluster: &WalletCluster) -> RiskFactor {
+    let severity = match cluster.supply_share {
+        share if share >= 0.2 => RiskLevel::Extreme,
+        share if share >= 0.05 => RiskLevel::High,
+        _ => RiskLevel::Medium,
+    };
+    let factor = match &cluster.kind {
+        ClusterKind::SharedFunder { funder } => RiskFactor::new(RiskFactorKind::SuspiciousFunding, severity)
+            .with_evidence("funder", funder),
+        ClusterKind::BundledBuy { slot } => RiskFactor::new(RiskFactorKind::BundledLaunch, severity.max(RiskLevel::High))
+            .with_evidence("slot", slot),
+    };
+    factor
+        .with_evidence("wallets", cluster.wallets.len())
+        .with_evidence("supply_share", format!("{:.4}", cluster.supply_share))
+}
+
+/// Balance per holder from raw `(owner, amount)` token accounts: accounts of one owner are
+/// summed, and empty accounts and excluded owners are dropped
+pub fn holder_balances(accounts: Vec<(Pubkey, u64)>, config: &HolderConfig) -> HashMap<Pubkey, u64> {
+    let mut balances: HashMap<Pubkey, u64> = HashMap::new();
+    for (owner, amount) in accounts {
+        if amount > 0 && !config.is_excluded(&owner) {
+            *balances.entry(owner).or_default() += amount;
+        }
+    }
+    balances
+}
+
+/// Sums token accounts per owner and drops empty and excluded owners, largest first
+fn aggregate_holders(accounts: Vec<(Pubkey, u64)>, config: &HolderConfig) -> Vec<HolderInfo> {
+    let mut holders: Vec<HolderInfo> = holder_balances(accounts, config)
+        .into_iter()
+        .map(|(address, balance)| HolderInfo {
+            address,
+            balance,
+            first_transaction_date: None,
+            transaction_count: None,
+        })
+        .collect();
+    holders.sort_by(|a, b| b.balance.cmp(&a.balance).then_with(|| a.address.cmp(&b.address)));
+    holders
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    #[test]
+    fn test_aggregate_holders_merges_accounts_and_drops_excluded_owners() {
+        let (whale, minnow, treasury) = (Keypair::new().pubkey(), Keypair::new().pubkey(), Keypair::new().pubkey());
+        let vault_authority = Pubkey::find_program_address(&[b"vault"], &Pubkey::new_unique()).0;
+        let mut config = HolderConfig {
+            excluded_owners: vec![treasury],
+            ..Default::default()
+        };
+        let accounts = vec![
+            (minnow, 10),
+            (whale, 600),
+            (whale, 400),
+            (treasury, 5_000),
+            (vault_authority, 9_000),
+            (BUILT_IN_EXCLUDED_OWNERS[0], 7_000),
+            (Keypair::new().pubkey(), 0),
+        ];
+
+        // Program-owned holders count unless asked otherwise
+        let holders = aggregate_holders(accounts.clone(), &config);
+        let balances: Vec<(Pubkey, u64)> = holders.iter().map(|h| (h.address, h.balance)).collect();
+        assert_eq!(balances, vec![(vault_authority, 9_000), (whale, 1_000), (minnow, 10)]);
+
+        config.exclude_program_owned = true;
+        let holders = aggregate_holders(accounts, &config);
+        let balances: Vec<(Pubkey, u64)> = holders.iter().map(|h| (h.address, h.balance)).collect();
+        assert_eq!(balances, vec![(whale, 1_000), (minnow, 10)]);
+    }
+
+    #[test]
+    fn test_findings_set_the_risk_level() {
+        assert_eq!(overall_risk(&[]), RiskLevel::Low);
+
+        let concentration = RiskFactor::new(RiskFactorKind::HolderConcentration, RiskLevel::Medium);
+        assert_eq!(overall_risk(&[concentration.clone()]), RiskLevel::Medium);
+
+        // A small bundled launch still rates High, and a large funding cluster Extreme
+        let bundle = cluster_risk_factor(&WalletCluster {
+            kind: ClusterKind::BundledBuy { slot: 7 },
+            wallets: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            supply_share: 0.01,
+        });
+        assert_eq!(overall_risk(&[concentration.clone(), bundle]), RiskLevel::High);
+
+        let funded = cluster_risk_factor(&WalletCluster {
+            kind: ClusterKind::SharedFunder { funder: Pubkey::new_unique() },
+            wallets: vec![Pubkey::new_unique(); 3],
+            supply_share: 0.25,
+        });
+        assert_eq!(overall_risk(&[concentration, funded]), RiskLevel::Extreme);
+    }
 }
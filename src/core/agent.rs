@@ -1,6 +1,43 @@
 use async_trait::async_trait;
-use solana_sdk::pubkey::Pubkey;
-use crate::core::types::{AnalysisResult, CoralError, TradeResult};
+use futures::future::join_all;
+use futures::stream::{self, Stream, StreamExt};
+use solana_sdk::{pubkey::Pubkey, signature::{read_keypair_file, Signer}};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinHandle;
+use crate::analyzers::{
+    Analyzer,
+    authenticity::AuthenticityAnalyzer,
+    cache::AnalysisCache,
+    consensus::{AnalyzerOutput, ConsensusEngine},
+    developer::DeveloperAnalyzer,
+    holder_history::HolderChanges,
+    market::MarketAnalyzer,
+    mint_authority::MintAuthorityAnalyzer,
+    registry::{AnalyzerRegistry, RegisteredAnalyzer},
+    wallet::{self, WalletAnalyzer},
+};
+use crate::core::amount::Price;
+use crate::core::config::AgentConfig;
+use crate::core::risk::RiskGate;
+use crate::core::types::{
+    AnalysisResult, AnalyzerFailure, AnalyzerFailureReason, CoralError, ExitTrigger, Position,
+    PositionId, TradeResult, WatchlistSummary,
+};
+use crate::notifiers::{telegram::TelegramNotifier, NotificationMessage, Notifier};
+use crate::storage::{
+    open_positions, BalanceSnapshot, HolderSnapshot, OrderEvent, OrderEventKind, OrderId,
+    PositionEvent, PositionEventKind, Reconciliation, RecoveryReport, SqliteStore, Store,
+    StoreQuery, StoredOrder,
+};
+use crate::traders::{
+    executor::TradeExecutor,
+    jupiter::{JupiterConfig, JupiterTrader},
+    RoutePreference, RouteStrategy, TradeOrder, Trader,
+};
+use crate::utils::{metrics::MetricsCollector, solana::SolanaUtils, token_registry::TokenRegistry};
 
 #[async_trait]
 pub trait Agent {
@@ -11,47 +48,1408 @@ pub trait Agent {
 
 pub struct CoralAgent {
     config: AgentConfig,
-    analyzers: Vec<Box<dyn Analyzer>>,
-    trader: Box<dyn Trader>,
+    analyzers: AnalyzerRegistry,
+    cache: AnalysisCache,
+    executor: TradeExecutor,
+    consensus: ConsensusEngine,
+    risk: RiskGate,
+    store: Option<Arc<dyn Store>>,
+    solana: SolanaUtils,
+    /// Account that signs trades; needed to reconcile in-flight orders after a restart
+    wallet: Option<Pubkey>,
+    tokens: Arc<TokenRegistry>,
+    notifiers: Vec<Box<dyn Notifier>>,
+    metrics: Arc<MetricsCollector>,
+    /// Stop signals of the positions being watched, by mint and position id
+    position_stops: Mutex<HashMap<(Pubkey, PositionId), oneshot::Sender<()>>>,
 }
 
 impl CoralAgent {
-    pub fn new(config: AgentConfig) -> Self {
-        Self {
+    /// Agent with the built-in analyzers and traders, configured from an already
+    /// validated configuration (see `AgentConfig::load`)
+    pub fn new(config: AgentConfig, metrics: Option<Arc<MetricsCollector>>) -> Result<Self, CoralError> {
+        let mut builder = Self::builder(config)
+            .with_default_analyzers()
+            .with_default_traders()
+            .with_default_notifiers();
+        if let Some(metrics) = metrics {
+            builder = builder.metrics(metrics);
+        }
+        builder.build()
+    }
+
+    pub fn builder(config: AgentConfig) -> CoralAgentBuilder {
+        CoralAgentBuilder {
             config,
-            analyzers: vec![
-                Box::new(AuthenticityAnalyzer::new()),
-                Box::new(WalletAnalyzer::new()),
-                Box::new(DeveloperAnalyzer::new()),
-            ],
-            trader: Box::new(JupiterTrader::new()),
+            metrics: None,
+            store: None,
+            wallet: None,
+            tokens: None,
+            analyzers: Vec::new(),
+            default_analyzers: false,
+            traders: Vec::new(),
+            notifiers: Vec::new(),
+        }
+    }
+
+    /// Adds a trading venue to a running agent
+    pub async fn register_trader(&self, trader: Box<dyn Trader>) {
+        self.executor.register_trader(trader).await;
+    }
+
+    /// Registers a route strategy for orders with `RoutePreference::Custom(name)`
+    pub async fn register_route_strategy(&self, name: impl Into<String>, strategy: Box<dyn RouteStrategy>) {
+        self.executor.register_strategy(name, strategy).await;
+    }
+
+    /// Analyzes many tokens with at most `analysis.batch_concurrency` in flight,
+    /// yielding each result as soon as it completes. Duplicate mints are analyzed once.
+    pub fn analyze_batch(
+        &self,
+        tokens: Vec<Pubkey>,
+    ) -> impl Stream<Item = (Pubkey, Result<AnalysisResult, CoralError>)> + '_ {
+        let mut seen = HashSet::new();
+        let tokens: Vec<Pubkey> = tokens.into_iter().filter(|token| seen.insert(*token)).collect();
+
+        stream::iter(tokens)
+            .map(move |token| async move {
+                let result = self.analyze_token(&token).await;
+                (token, result)
+            })
+            .buffer_unordered(self.config.analysis.batch_concurrency.max(1))
+    }
+
+    /// Runs a batch analysis to completion and ranks the outcome for triage
+    pub async fn analyze_watchlist(&self, tokens: Vec<Pubkey>) -> WatchlistSummary {
+        let results: Vec<_> = self.analyze_batch(tokens).collect().await;
+        WatchlistSummary::from_results(results)
+    }
+
+    /// Persistent history, when the agent was built with a store
+    pub fn store(&self) -> Option<&Arc<dyn Store>> {
+        self.store.as_ref()
+    }
+
+    /// Symbols, names and decimals of known tokens
+    pub fn tokens(&self) -> &Arc<TokenRegistry> {
+        &self.tokens
+    }
+
+    /// Restores what a previous run persisted: recent analyses for the risk gate, exposure
+    /// from recorded fills, the outcome of orders that were in flight when the process
+    /// stopped, and the positions still open. Call once before trading, then pass the
+    /// open positions to `resume_monitoring`.
+    pub async fn recover(&self) -> Result<RecoveryReport, CoralError> {
+        let store = match &self.store {
+            Some(store) => store.clone(),
+            None => return Ok(RecoveryReport::default()),
+        };
+        let mut report = RecoveryReport::default();
+
+        let oldest_analysis = chrono::Utc::now().timestamp() - self.config.risk.max_analysis_age_secs;
+        for analysis in store.analyses(&StoreQuery::all().since(oldest_analysis)).await? {
+            self.risk.record_analysis(&analysis).await;
+            report.restored_analyses += 1;
+        }
+
+        let orders: HashMap<OrderId, StoredOrder> = store
+            .orders(&StoreQuery::all())
+            .await?
+            .into_iter()
+            .map(|stored| (stored.id, stored))
+            .collect();
+        let mut claimed_signatures = HashSet::new();
+        for trade in store.trades(&StoreQuery::all()).await? {
+            claimed_signatures.insert(trade.result.transaction_signature.clone());
+            if let Some(stored) = orders.get(&trade.order_id) {
+                self.risk.replay_fill(&stored.order, &trade.result).await;
+                report.replayed_trades += 1;
+            }
+        }
+
+        for pending in store.pending_orders().await? {
+            match self.reconcile(&store, &pending, &mut claimed_signatures).await {
+                Ok(Some(outcome)) => {
+                    log::info!("Reconciled order {}: {:?}", pending.id, outcome);
+                    report.reconciled.push((pending.id, outcome));
+                }
+                Ok(None) => {
+                    log::error!("Order {} is still unresolved and needs manual review", pending.id);
+                    report.unresolved.push(pending.id);
+                }
+                Err(e) => {
+                    log::error!("Could not reconcile order {}: {}", pending.id, e);
+                    report.unresolved.push(pending.id);
+                }
+            }
+        }
+
+        report.open_positions = self.close_exited_positions(&store, &orders).await?;
+
+        Ok(report)
+    }
+
+    /// Open positions once those whose exit order filled are closed. The exit may have
+    /// landed without its `Closed` event, either because the process stopped right after
+    /// the fill or because the fill was only found by reconciliation. Fills that already
+    /// closed a position are not matched again.
+    async fn close_exited_positions(
+        &self,
+        store: &Arc<dyn Store>,
+        orders: &HashMap<OrderId, StoredOrder>,
+    ) -> Result<Vec<Position>, CoralError> {
+        let events = store.position_events(&StoreQuery::all()).await?;
+        let used_signatures: HashSet<&str> = events
+            .iter()
+            .filter_map(|event| match &event.kind {
+                PositionEventKind::Closed { transaction_signature } => Some(transaction_signature.as_str()),
+                _ => None,
+            })
+            .collect();
+        let mut trades: Vec<_> = store
+            .trades(&StoreQuery::all())
+            .await?
+            .into_iter()
+            .filter(|trade| !used_signatures.contains(trade.result.transaction_signature.as_str()))
+            .collect();
+        let mut positions = open_positions(&events);
+
+        let mut still_open = Vec::with_capacity(positions.len());
+        for position in positions.drain(..) {
+            let exit = self.exit_order(&position);
+            let filled_exit = trades.iter().position(|trade| {
+                orders.get(&trade.order_id).map_or(false, |stored| {
+                    stored.recorded_at >= position.opened_at
+                        && stored.order.from_token == exit.from_token
+                        && stored.order.to_token == exit.to_token
+                        && stored.order.amount == exit.amount
+                })
+            });
+            match filled_exit {
+                Some(index) => {
+                    // One fill closes one position
+                    let trade = trades.swap_remove(index);
+                    log::info!(
+                        "Position in {} was closed by {} before the restart",
+                        self.tokens.label(&position.token_address),
+                        trade.result.transaction_signature
+                    );
+                    self.record_position_event(PositionEvent::now(
+                        &position,
+                        PositionEventKind::Closed {
+                            transaction_signature: trade.result.transaction_signature,
+                        },
+                    ))
+                    .await;
+                }
+                None => still_open.push(position),
+            }
+        }
+        Ok(still_open)
+    }
+
+    /// Watches each recovered position again on its own task. Unlike `monitor_position`
+    /// this does not record the positions as newly opened.
+    pub fn resume_monitoring(self: &Arc<Self>, positions: Vec<Position>) -> Vec<JoinHandle<Result<(), CoralError>>> {
+        positions
+            .into_iter()
+            .map(|position| {
+                let agent = Arc::clone(self);
+                tokio::spawn(async move { agent.watch_position(position).await })
+            })
+            .collect()
+    }
+
+    /// Records a snapshot of `token`'s holders and compares it with the mint's previous
+    /// one, sending a risk alert for every change past the `analysis.holders.snapshots`
+    /// thresholds. `None` for the first snapshot of a mint.
+    pub async fn snapshot_holders(&self, token: &Pubkey) -> Result<Option<HolderChanges>, CoralError> {
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| CoralError::config("storage.path", "holder snapshots need a store"))?;
+        let holders = &self.config.analysis.holders;
+
+        let mint = self.solana.get_mint_profile(token).await?;
+        let accounts = self.solana.get_mint_token_accounts(token, mint.program).await?;
+        let balances = wallet::holder_balances(accounts, holders).into_iter().collect();
+        let snapshot = HolderSnapshot::now(*token, balances, holders.snapshots.tracked_holders);
+
+        let previous = store.latest_holder_snapshot(token).await?;
+        store.record_holder_snapshot(&snapshot).await?;
+        let changes = match previous {
+            Some(previous) => HolderChanges::between(&previous, &snapshot, &holders.snapshots),
+            None => return Ok(None),
+        };
+
+        for alert in changes.alerts(&self.tokens.label(token), &holders.snapshots) {
+            self.notify(&alert).await;
+        }
+        Ok(Some(changes))
+    }
+
+    /// Snapshots the holders of each of `tokens` every
+    /// `analysis.holders.snapshots.interval_secs` until the task is aborted. A failed
+    /// snapshot is logged and retried on the next tick.
+    pub fn watch_holders(self: &Arc<Self>, tokens: Vec<Pubkey>) -> JoinHandle<()> {
+        let agent = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(
+                agent.config.analysis.holders.snapshots.interval_secs,
+            ));
+            loop {
+                interval.tick().await;
+                for token in &tokens {
+                    if let Err(e) = agent.snapshot_holders(token).await {
+                        log::warn!("Holder snapshot of {} failed: {}", agent.tokens.label(token), e);
+                    }
+                }
+            }
+        })
+    }
+
+    /// Drops cached analyzer results for `token`, forcing the next analysis to hit RPC
+    pub async fn invalidate_analysis(&self, token: &Pubkey) {
+        self.cache.invalidate_token(token).await;
+    }
+
+    /// Drops every cached result of the named analyzer
+    pub async fn invalidate_analyzer(&self, analyzer: &str) {
+        self.cache.invalidate_analyzer(analyzer).await;
+    }
+
+    /// Serves a fresh cached result or runs the analyzer within its timeout
+    async fn run_analyzer(&self, entry: &RegisteredAnalyzer, token: &Pubkey) -> Result<AnalysisResult, AnalyzerFailureReason> {
+        let ttl = entry.cache_ttl();
+        if !ttl.is_zero() {
+            if let Some(cached) = self.cache.get(token, entry.name(), entry.version()).await {
+                return Ok(cached);
+            }
         }
+
+        let timeout = entry.timeout();
+        let result = match tokio::time::timeout(timeout, entry.analyzer().analyze(token)).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => return Err(AnalyzerFailureReason::Failed(e.to_string())),
+            Err(_) => {
+                return Err(AnalyzerFailureReason::TimedOut {
+                    after_ms: timeout.as_millis() as u64,
+                })
+            }
+        };
+
+        self.cache
+            .insert(token, entry.name(), entry.version(), result.clone(), ttl)
+            .await;
+
+        Ok(result)
     }
 
+    /// Runs every enabled analyzer concurrently, each bounded by its own timeout.
+    /// Analyzers that fail or time out are recorded on the combined result instead of
+    /// aborting the whole analysis.
     async fn aggregate_analysis(&self, token: &Pubkey) -> Result<AnalysisResult, CoralError> {
-        let mut results = Vec::new();
-        
-        for analyzer in &self.analyzers {
-            let result = analyzer.analyze(token).await?;
-            results.push(result);
+        let runs = self
+            .analyzers
+            .enabled()
+            .map(|entry| async move { (entry, self.run_analyzer(entry, token).await) });
+
+        let mut outputs = Vec::new();
+        let mut failures = Vec::new();
+
+        for (entry, outcome) in join_all(runs).await {
+            match outcome {
+                Ok(result) => outputs.push(AnalyzerOutput {
+                    analyzer: entry.name().to_string(),
+                    weight: entry.weight(),
+                    result,
+                }),
+                Err(reason) => {
+                    log::warn!(
+                        "Analyzer {} v{} did not complete for {}: {:?}",
+                        entry.name(),
+                        entry.version(),
+                        token,
+                        reason
+                    );
+                    failures.push(AnalyzerFailure {
+                        analyzer: entry.name().to_string(),
+                        reason,
+                    });
+                }
+            }
+        }
+
+        if outputs.is_empty() {
+            return Err(CoralError::AnalysisFailed {
+                token: *token,
+                reason: format!("all {} analyzers failed", failures.len()),
+            });
+        }
+
+        let mut combined = self.combine_analysis_results(token, outputs)?;
+        for failure in &failures {
+            combined.explanation.push(format!(
+                "{} did not contribute: {:?}",
+                failure.analyzer, failure.reason
+            ));
+        }
+        combined.analyzer_failures = failures;
+
+        Ok(combined)
+    }
+
+    /// Folds weighted analyzer results into one verdict and logs how it was reached
+    fn combine_analysis_results(
+        &self,
+        token: &Pubkey,
+        outputs: Vec<AnalyzerOutput>,
+    ) -> Result<AnalysisResult, CoralError> {
+        let result = self.consensus.combine(token, outputs)?;
+        for step in &result.explanation {
+            log::info!("Consensus for {}: {}", token, step);
         }
 
-        self.combine_analysis_results(results)
+        Ok(result)
+    }
+
+    /// Current price of the position's token in quote units per token unit.
+    async fn current_price(&self, position: &Position) -> Result<Price, CoralError> {
+        let quote = self
+            .executor
+            .get_best_quote(&position.token_address, &position.quote_token, position.size.raw())
+            .await?;
+
+        Price::from_raw(quote.input_amount, quote.output_amount).ok_or_else(|| CoralError::InvalidPrice {
+            reason: "price quote with zero input amount".to_string(),
+        })
+    }
+
+    /// Settles an order whose outcome was lost, first from the transaction it was sent
+    /// in, if that is known, or the wallet's transactions since the order was journaled,
+    /// then from its balances. `None` means the chain does not say either way.
+    async fn reconcile(
+        &self,
+        store: &Arc<dyn Store>,
+        pending: &StoredOrder,
+        claimed_signatures: &mut HashSet<String>,
+    ) -> Result<Option<Reconciliation>, CoralError> {
+        let order = &pending.order;
+        let events: Vec<OrderEvent> = store
+            .order_events(&StoreQuery::all().mint(order.to_token).since(pending.recorded_at))
+            .await?
+            .into_iter()
+            .filter(|event| event.order_id == pending.id)
+            .collect();
+
+        let mut candidates: Vec<String> = events
+            .iter()
+            .filter_map(|event| match &event.kind {
+                OrderEventKind::Sent { transaction_signature } => Some(transaction_signature.clone()),
+                _ => None,
+            })
+            .collect();
+        if let Some(wallet) = &self.wallet {
+            candidates.extend(self.solana.get_signatures_since(wallet, pending.recorded_at).await?);
+        }
+
+        let mut outcome = None;
+        for signature in candidates {
+            if claimed_signatures.contains(&signature) {
+                continue;
+            }
+            // Only a swap that spent this order's input counts as its fill
+            let fill = self.solana.get_fill_details(&signature, &order.from_token, &order.to_token).await?;
+            if fill.output_amount == 0 || !matches_order_input(order, fill.input_amount) {
+                continue;
+            }
+
+            // The quote is gone with the process, so the fill stands as its own quote
+            let price = fill_price(&self.config.trading.quote_token, order, fill.output_amount);
+            let result = TradeResult {
+                transaction_signature: signature.clone(),
+                amount: order.amount,
+                output_amount: fill.output_amount,
+                quoted_price: price,
+                executed_price: price,
+                slippage_bps: 0,
+                route: Vec::new(),
+                slot: fill.slot,
+                confirmation_status: fill.confirmation_status,
+                fee: fill.fee,
+                priority_fee: fill.priority_fee,
+                timestamp: fill.block_time.unwrap_or_else(|| chrono::Utc::now().timestamp()),
+            };
+            store.record_trade(pending.id, &result).await?;
+            self.risk.replay_fill(order, &result).await;
+            claimed_signatures.insert(signature.clone());
+
+            outcome = Some(Reconciliation::Filled {
+                transaction_signature: signature,
+                output_amount: fill.output_amount,
+            });
+            break;
+        }
+
+        // Nothing delivered the output; the order did not land if its input is untouched
+        if let (None, Some(wallet)) = (&outcome, &self.wallet) {
+            let snapshot = events.iter().find_map(|event| match event.kind {
+                OrderEventKind::Submitted { balances } => balances,
+                _ => None,
+            });
+            if let Some(snapshot) = snapshot {
+                let from_balance = self.solana.get_owner_balance(wallet, &order.from_token).await?;
+                if from_balance >= snapshot.from_balance {
+                    outcome = Some(Reconciliation::NotExecuted);
+                }
+            }
+        }
+
+        if let Some(outcome) = &outcome {
+            store
+                .record_order_event(&OrderEvent::now(pending.id, OrderEventKind::Reconciled {
+                    outcome: outcome.clone(),
+                }))
+                .await?;
+        }
+
+        Ok(outcome)
+    }
+
+    /// Records the order and the wallet's balances before it is handed to a trader, so
+    /// a restart can tell whether it landed
+    async fn journal_order(&self, order: &TradeOrder) -> Option<OrderId> {
+        let store = self.store.as_ref()?;
+        let order_id = store
+            .record_order(order)
+            .await
+            .map_err(|e| log::error!("Failed to record order for {}: {}", order.to_token, e))
+            .ok()?;
+
+        let balances = self.balance_snapshot(order).await;
+        self.record_order_event(OrderEvent::now(order_id, OrderEventKind::Submitted { balances }))
+            .await;
+
+        Some(order_id)
+    }
+
+    async fn balance_snapshot(&self, order: &TradeOrder) -> Option<BalanceSnapshot> {
+        let wallet = self.wallet?;
+        let from_balance = self.solana.get_owner_balance(&wallet, &order.from_token).await;
+        let to_balance = self.solana.get_owner_balance(&wallet, &order.to_token).await;
+
+        match (from_balance, to_balance) {
+            (Ok(from_balance), Ok(to_balance)) => Some(BalanceSnapshot { from_balance, to_balance }),
+            (Err(e), _) | (_, Err(e)) => {
+                log::warn!("Could not snapshot balances before trading {}: {}", order.to_token, e);
+                None
+            }
+        }
+    }
+
+    /// Stops watching `position` without closing it; `recover` finds it open on the next
+    /// start. `false` when the position was not being watched.
+    pub async fn stop_monitoring(&self, position: &Position) -> bool {
+        let key = (position.token_address, position.id.clone());
+        match self.position_stops.lock().await.remove(&key) {
+            Some(stop) => stop.send(()).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Polls the price of `position` until it exits or `stop_monitoring` is called.
+    /// Watching the same position again replaces, and so stops, the earlier watcher.
+    /// Only an exit that fails with a non-retryable error ends the watch early.
+    async fn watch_position(&self, position: Position) -> Result<(), CoralError> {
+        let (stop, stopped) = oneshot::channel();
+        self.position_stops
+            .lock()
+            .await
+            .insert((position.token_address, position.id.clone()), stop);
+
+        let result = self.watch_until_exit(&position, stopped).await;
+        self.position_stops.lock().await.retain(|_, stop| !stop.is_closed());
+        result
+    }
+
+    async fn watch_until_exit(&self, position: &Position, mut stopped: oneshot::Receiver<()>) -> Result<(), CoralError> {
+        let mut interval = tokio::time::interval(Duration::from_millis(
+            self.config.trading.position_poll_interval_ms,
+        ));
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = &mut stopped => {
+                    log::info!(
+                        "Stopped watching position in {}; it stays open",
+                        self.tokens.label(&position.token_address)
+                    );
+                    return Ok(());
+                }
+            }
+
+            // Price lookups are retried on the next tick rather than abandoning the position
+            let price = match self.current_price(position).await {
+                Ok(price) => price,
+                Err(e) => {
+                    log::warn!("Price check failed for {}: {}", self.tokens.label(&position.token_address), e);
+                    continue;
+                }
+            };
+
+            let trigger = match position.exit_trigger(price) {
+                Some(trigger) => trigger,
+                None => continue,
+            };
+
+            let threshold = match trigger {
+                ExitTrigger::StopLoss => position.stop_loss,
+                ExitTrigger::TakeProfit => position.take_profit,
+            };
+            log::info!(
+                "{:?} hit for {} at {} (threshold {}, entry {})",
+                trigger,
+                self.tokens.pair(&position.token_address, &position.quote_token),
+                price,
+                threshold,
+                position.entry_price
+            );
+            self.record_position_event(PositionEvent::now(
+                position,
+                PositionEventKind::ExitTriggered { trigger, price },
+            ))
+            .await;
+
+            // A retryable failure leaves the position open and the exit is tried again on
+            // the next tick if the price is still past the threshold
+            let result = match self
+                .execute_order(self.exit_order(position), Some(&position.quote_token))
+                .await
+            {
+                Ok(result) => result,
+                Err(e) if e.is_retryable() => {
+                    log::warn!(
+                        "Exit of {} failed, retrying on the next tick: {}",
+                        self.tokens.label(&position.token_address),
+                        e
+                    );
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            self.record_position_event(PositionEvent::now(
+                position,
+                PositionEventKind::Closed {
+                    transaction_signature: result.transaction_signature.clone(),
+                },
+            ))
+            .await;
+            log::info!(
+                "Closed position in {}. Signature: {}",
+                self.tokens.label(&position.token_address),
+                result.transaction_signature
+            );
+
+            return Ok(());
+        }
+    }
+
+    /// History is best effort: a storage failure is logged but never fails the trade or
+    /// analysis it describes
+    async fn record_order_event(&self, event: OrderEvent) {
+        if let Some(store) = &self.store {
+            if let Err(e) = store.record_order_event(&event).await {
+                log::error!("Failed to record event for order {}: {}", event.order_id, e);
+            }
+        }
+    }
+
+    /// Sends `message` through every notifier if it meets `alerts.min_priority`. Like
+    /// history, delivery is best effort.
+    async fn notify(&self, message: &NotificationMessage) {
+        if message.priority < self.config.alerts.min_priority {
+            return;
+        }
+        for notifier in &self.notifiers {
+            if let Err(e) = notifier.send_notification(message).await {
+                log::error!("Failed to send notification \"{}\": {}", message.title, e);
+            }
+        }
+    }
+
+    async fn record_position_event(&self, event: PositionEvent) {
+        if let Some(store) = &self.store {
+            if let Err(e) = store.record_position_event(&event).await {
+                log::error!("Failed to record position event for {}: {}", event.token_address, e);
+            }
+        }
+    }
+
+    /// Reads metadata for any of `mints` the registry has not seen, so the labels and
+    /// amounts logged for them carry symbols and decimals. Best effort: a mint that cannot
+    /// be read is shown by its address.
+    async fn warm_tokens(&self, mints: &[Pubkey]) {
+        for mint in mints {
+            if let Err(e) = self.tokens.fetch(mint).await {
+                log::debug!("No metadata for {}: {}", mint, e);
+            }
+        }
+    }
+
+    /// `execute_trade`, with `exit_quote` set when the order closes a position held against
+    /// that quote token so the risk gate lets it through
+    async fn execute_order(&self, order: TradeOrder, exit_quote: Option<&Pubkey>) -> Result<TradeResult, CoralError> {
+        self.warm_tokens(&[order.from_token, order.to_token]).await;
+        let description = format!(
+            "{} order of {}",
+            self.tokens.pair(&order.from_token, &order.to_token),
+            self.tokens.format_amount(&order.from_token, order.amount)
+        );
+        let checked = match exit_quote {
+            Some(quote_token) => self.risk.check_exit(order, quote_token).await,
+            None => self.risk.check(order).await,
+        };
+        let order = checked.map_err(|rejection| {
+            log::warn!("Risk gate rejected {}: {}", description, rejection);
+            CoralError::from(rejection)
+        })?;
+
+        let order_id = self.journal_order(&order).await;
+
+        let result = match self.executor.execute_trade(order.clone()).await {
+            Ok(result) => result,
+            Err(e) => {
+                // Network and execution errors can come after the transaction was sent, so
+                // those orders stay pending until `recover` settles them from the chain, and
+                // keep their risk reservation since they may still have filled
+                if !matches!(e, CoralError::Network { .. } | CoralError::ExecutionFailed { .. }) {
+                    self.risk.release(&order).await;
+                    if let Some(order_id) = order_id {
+                        self.record_order_event(OrderEvent::now(order_id, OrderEventKind::Failed {
+                            reason: e.to_string(),
+                        }))
+                        .await;
+                    }
+                }
+                return Err(e);
+            }
+        };
+
+        // The swap landed but what it filled is unknown; exposure and P&L wait until
+        // `recover` reads the fill from the chain, and the reservation holds until then
+        if !result.fill_known() {
+            log::warn!(
+                "Fill of {} in {} could not be read back; it stays pending",
+                description,
+                result.transaction_signature
+            );
+            if let Some(order_id) = order_id {
+                self.record_order_event(OrderEvent::now(order_id, OrderEventKind::Sent {
+                    transaction_signature: result.transaction_signature.clone(),
+                }))
+                .await;
+            }
+            return Ok(result);
+        }
+        self.risk.record_fill(&order, &result).await;
+
+        // A fill the store could not take is logged in full so it can be restored by hand
+        let unrecorded = match (&self.store, order_id) {
+            (Some(store), Some(order_id)) => store.record_trade(order_id, &result).await.err(),
+            (Some(_), None) => Some(CoralError::Database {
+                message: "its order was never recorded".to_string(),
+                source: None,
+            }),
+            (None, _) => None,
+        };
+        if let Some(e) = unrecorded {
+            log::error!(
+                "Failed to record trade {}: {}; fill: {}",
+                result.transaction_signature,
+                e,
+                serde_json::to_string(&result).unwrap_or_else(|_| format!("{:?}", result))
+            );
+        }
+
+        Ok(result)
+    }
+
+    fn exit_order(&self, position: &Position) -> TradeOrder {
+        TradeOrder {
+            from_token: position.token_address,
+            to_token: position.quote_token,
+            amount: position.size.raw(),
+            slippage_tolerance: self.config.trading.exit_slippage,
+            minimum_received: None,
+            route_preference: RoutePreference::BestPrice,
+        }
     }
 }
 
 #[async_trait]
 impl Agent for CoralAgent {
     async fn analyze_token(&self, token: &Pubkey) -> Result<AnalysisResult, CoralError> {
-        self.aggregate_analysis(token).await
+        let start_time = std::time::Instant::now();
+        self.warm_tokens(&[*token]).await;
+        let result = self.aggregate_analysis(token).await;
+
+        self.metrics.increment_analysis_count();
+        self.metrics.record_analysis_time(start_time.elapsed().as_millis() as u64).await;
+        match &result {
+            Ok(analysis) => {
+                self.risk.record_analysis(analysis).await;
+                if let Some(store) = &self.store {
+                    if let Err(e) = store.record_analysis(analysis).await {
+                        log::error!("Failed to record analysis of {}: {}", token, e);
+                    }
+                }
+            }
+            Err(_) => self.metrics.record_error(),
+        }
+
+        result
     }
 
+    /// Passes the order through the risk gate, which may reject or shrink it, then
+    /// routes it through the executor, which compares venues, validates the route and
+    /// records the trade in the logs and metrics
     async fn execute_trade(&self, order: TradeOrder) -> Result<TradeResult, CoralError> {
-        self.trader.execute(order).await
+        self.execute_order(order, None).await
     }
 
     async fn monitor_position(&self, position: Position) -> Result<(), CoralError> {
-        // Implementation for position monitoring
-        todo!()
+        self.record_position_event(PositionEvent::now(
+            &position,
+            PositionEventKind::Opened { position: position.clone() },
+        ))
+        .await;
+
+        self.watch_position(position).await
+    }
+}
+
+/// Assembles a `CoralAgent` from configuration plus any number of analyzers, traders
+/// and notifiers. Each analyzer is registered under `Analyzer::name` and picks up its
+/// `[analysis.analyzers.<name>]` settings.
+pub struct CoralAgentBuilder {
+    config: AgentConfig,
+    metrics: Option<Arc<MetricsCollector>>,
+    store: Option<Arc<dyn Store>>,
+    wallet: Option<Pubkey>,
+    tokens: Option<Arc<TokenRegistry>>,
+    analyzers: Vec<Box<dyn Analyzer>>,
+    /// Built-in analyzers are created in `build`, once the store is known
+    default_analyzers: bool,
+    traders: Vec<Box<dyn Trader>>,
+    notifiers: Vec<Box<dyn Notifier>>,
+}
+
+impl CoralAgentBuilder {
+    pub fn metrics(mut self, metrics: Arc<MetricsCollector>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Persists history to `store` instead of the database in `storage.path`
+    pub fn store(mut self, store: Arc<dyn Store>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Account that signs trades, instead of the one in `security.keypair_path`
+    pub fn wallet(mut self, wallet: Pubkey) -> Self {
+        self.wallet = Some(wallet);
+        self
+    }
+
+    /// Shares `tokens` instead of building a registry from `tokens.list_path`
+    pub fn tokens(mut self, tokens: Arc<TokenRegistry>) -> Self {
+        self.tokens = Some(tokens);
+        self
+    }
+
+    pub fn analyzer(mut self, analyzer: Box<dyn Analyzer>) -> Self {
+        self.analyzers.push(analyzer);
+        self
+    }
+
+    /// Registers the authenticity, wallet, developer, market and mint authority analyzers
+    pub fn with_default_analyzers(mut self) -> Self {
+        self.default_analyzers = true;
+        self
+    }
+
+    pub fn trader(mut self, trader: Box<dyn Trader>) -> Self {
+        self.traders.push(trader);
+        self
+    }
+
+    /// Registers Jupiter, quoting against the configured quote token
+    pub fn with_default_traders(self) -> Self {
+        let jupiter_config = JupiterConfig {
+            quote_token: self.config.trading.quote_token,
+            ..Default::default()
+        };
+        let api_key = self.config.security.jupiter_api_key.clone().unwrap_or_default();
+        let rpc_url = self.config.rpc.primary_endpoint.clone();
+
+        self.trader(Box::new(JupiterTrader::new(&api_key, &rpc_url, jupiter_config)))
+    }
+
+    pub fn notifier(mut self, notifier: Box<dyn Notifier>) -> Self {
+        self.notifiers.push(notifier);
+        self
+    }
+
+    /// Registers Telegram when `alerts.telegram_enabled` is set
+    pub fn with_default_notifiers(self) -> Self {
+        let alerts = &self.config.alerts;
+        match (alerts.telegram_enabled, alerts.telegram_bot_token.clone(), alerts.telegram_chat_id) {
+            (true, Some(token), Some(chat_id)) => self.notifier(Box::new(TelegramNotifier::new(token, chat_id))),
+            _ => self,
+        }
+    }
+
+    pub fn build(mut self) -> Result<CoralAgent, CoralError> {
+        let store = match (self.store, &self.config.storage.path) {
+            (Some(store), _) => Some(store),
+            (None, Some(path)) => Some(Arc::new(SqliteStore::open(path)?) as Arc<dyn Store>),
+            (None, None) => None,
+        };
+
+        if self.default_analyzers {
+            self.analyzers.extend(default_analyzers(&self.config, store.clone()));
+        }
+        let analyzers = AnalyzerRegistry::from_analyzers(self.analyzers, &self.config.analysis.analyzers)?;
+        if analyzers.enabled().next().is_none() {
+            return Err(CoralError::config(
+                "analysis.analyzers",
+                "at least one analyzer must be enabled",
+            ));
+        }
+        if analyzers.enabled().all(|entry| entry.weight() <= 0.0) {
+            return Err(CoralError::config(
+                "analysis.analyzers",
+                "at least one enabled analyzer must have a weight above zero",
+            ));
+        }
+
+        let wallet = match (self.wallet, &self.config.security.keypair_path) {
+            (Some(wallet), _) => Some(wallet),
+            (None, Some(path)) => {
+                let keypair = read_keypair_file(path).map_err(|e| CoralError::Initialization {
+                    message: format!("cannot read keypair {}", path),
+                    source: Some(e.to_string().into()),
+                })?;
+                Some(keypair.pubkey())
+            }
+            (None, None) => None,
+        };
+        let tokens = match self.tokens {
+            Some(tokens) => tokens,
+            None => {
+                let tokens = TokenRegistry::new().with_rpc(&self.config.rpc.primary_endpoint);
+                if let Some(path) = &self.config.tokens.list_path {
+                    tokens.load_token_list(path)?;
+                }
+                Arc::new(tokens)
+            }
+        };
+
+        let metrics = self.metrics.unwrap_or_else(|| Arc::new(MetricsCollector::new()));
+        let executor = TradeExecutor::with_traders(metrics.clone(), self.traders);
+
+        Ok(CoralAgent {
+            analyzers,
+            cache: AnalysisCache::new(metrics.clone()),
+            executor,
+            consensus: ConsensusEngine::new(self.config.analysis.consensus.clone()),
+            risk: RiskGate::new(self.config.risk.clone(), self.config.trading.quote_token),
+            store,
+            solana: SolanaUtils::new(&self.config.rpc.primary_endpoint),
+            wallet,
+            tokens,
+            notifiers: self.notifiers,
+            config: self.config,
+            metrics,
+            position_stops: Mutex::new(HashMap::new()),
+        })
+    }
+}
+
+/// The authenticity, wallet, developer, market and mint authority analyzers. The mint
+/// authority analyzer keeps first-seen supplies in `store` when there is one.
+fn default_analyzers(config: &AgentConfig, store: Option<Arc<dyn Store>>) -> Vec<Box<dyn Analyzer>> {
+    let rpc_url = &config.rpc.primary_endpoint;
+    let jupiter_api_key = config.security.jupiter_api_key.clone().unwrap_or_default();
+
+    let mut mint_authority = MintAuthorityAnalyzer::new(rpc_url);
+    if let Some(store) = store {
+        mint_authority = mint_authority.with_store(store);
+    }
+    vec![
+        Box::new(AuthenticityAnalyzer::new(rpc_url)),
+        Box::new(WalletAnalyzer::new(rpc_url, config.analysis.holders.clone())),
+        Box::new(DeveloperAnalyzer::new(rpc_url, config.security.github_token.clone())),
+        Box::new(MarketAnalyzer::new(rpc_url, &jupiter_api_key, config.analysis.liquidity.clone())),
+        Box::new(mint_authority),
+    ]
+}
+
+/// Lamports a swap may spend on top of a native SOL input: rent for the temporary
+/// wrapped SOL account
+const WRAPPED_SOL_RENT: u64 = 2_039_280;
+
+/// Whether a transaction that spent `input_amount` of `order.from_token` could be the
+/// order's own fill, within 1% or the rent of a wrapped SOL account
+fn matches_order_input(order: &TradeOrder, input_amount: u64) -> bool {
+    let mut tolerance = order.amount / 100;
+    if order.from_token == spl_token::native_mint::id() {
+        tolerance = tolerance.max(WRAPPED_SOL_RENT);
+    }
+    input_amount.abs_diff(order.amount) <= tolerance
+}
+
+/// Quote units per unit of the traded token, whichever side of `order` the quote token is on
+fn fill_price(quote_token: &Pubkey, order: &TradeOrder, output: u64) -> Price {
+    let price = if order.from_token == *quote_token {
+        Price::from_raw(output, order.amount)
+    } else {
+        Price::from_raw(order.amount, output)
+    };
+    price.unwrap_or(Price::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::registry::AnalyzerSettings;
+    use crate::core::amount::TokenAmount;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+    use crate::core::types::{ConfirmationStatus, RiskLevel, TradeRecommendation};
+    use crate::traders::{PriceQuote, TradeRoute};
+
+    struct IdleAnalyzer;
+
+    #[async_trait]
+    impl Analyzer for IdleAnalyzer {
+        fn name(&self) -> &'static str {
+            "idle"
+        }
+
+        fn version(&self) -> u32 {
+            1
+        }
+
+        async fn analyze(&self, _token: &Pubkey) -> Result<AnalysisResult, CoralError> {
+            Err(CoralError::ServiceUnavailable {
+                service: "idle".to_string(),
+                reason: "not used by these tests".to_string(),
+            })
+        }
+    }
+
+    /// Scores the tokens it was given and fails for any other, counting its runs
+    struct ScriptedAnalyzer {
+        scores: HashMap<Pubkey, f64>,
+        runs: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Analyzer for ScriptedAnalyzer {
+        fn name(&self) -> &'static str {
+            "scripted"
+        }
+
+        fn version(&self) -> u32 {
+            1
+        }
+
+        async fn analyze(&self, token: &Pubkey) -> Result<AnalysisResult, CoralError> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            let score = self.scores.get(token).ok_or_else(|| CoralError::MarketNotFound {
+                market: token.to_string(),
+            })?;
+            Ok(AnalysisResult::new(*token, *score, RiskLevel::Low, TradeRecommendation::Hold {
+                duration: "1h".to_string(),
+                reevaluation_price: Price::ZERO,
+            }))
+        }
+    }
+
+    /// Quotes and fills a position at whatever output `output` holds, and remembers the
+    /// quote tokens it was asked to price in. The first `failures` fills fail with a
+    /// network error.
+    #[derive(Clone, Default)]
+    struct StubTrader {
+        output: Arc<AtomicU64>,
+        quoted_in: Arc<std::sync::Mutex<Vec<Pubkey>>>,
+        failures: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Trader for StubTrader {
+        async fn execute(&self, order: TradeOrder) -> Result<TradeResult, CoralError> {
+            if self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| left.checked_sub(1))
+                .is_ok()
+            {
+                return Err(CoralError::Network {
+                    message: "connection reset".to_string(),
+                    source: None,
+                });
+            }
+            Ok(fill("exit", order.amount, self.output.load(Ordering::SeqCst)))
+        }
+
+        async fn get_price_quote(&self, _token: &Pubkey, quote: &Pubkey, amount: u64) -> Result<PriceQuote, CoralError> {
+            self.quoted_in.lock().unwrap().push(*quote);
+            let route = self.get_available_routes(&Pubkey::default(), quote, amount).await?.remove(0);
+            Ok(PriceQuote {
+                input_amount: amount,
+                output_amount: route.expected_output,
+                price_impact: 0.0,
+                fee_amount: 0,
+                route,
+            })
+        }
+
+        async fn get_available_routes(&self, _from: &Pubkey, _to: &Pubkey, _amount: u64) -> Result<Vec<TradeRoute>, CoralError> {
+            Ok(vec![TradeRoute {
+                hops: Vec::new(),
+                total_fee: 0,
+                expected_output: self.output.load(Ordering::SeqCst),
+                price_impact: 0.0,
+            }])
+        }
+    }
+
+    fn monitoring_agent(store: Arc<dyn Store>, trader: StubTrader) -> Arc<CoralAgent> {
+        let mut config = AgentConfig::default();
+        config.trading.position_poll_interval_ms = 1;
+        let agent = CoralAgent::builder(config)
+            .store(store)
+            .tokens(Arc::new(TokenRegistry::new()))
+            .analyzer(Box::new(IdleAnalyzer))
+            .trader(Box::new(trader))
+            .build()
+            .unwrap();
+        Arc::new(agent)
+    }
+
+    /// 1 token bought at 1.0, with a stop at 0.9 and a target at 1.5
+    fn position_in(quote_token: Pubkey) -> Position {
+        let token = Pubkey::new_unique();
+        Position {
+            id: PositionId::generate(&token),
+            token_address: token,
+            quote_token,
+            size: TokenAmount::new(1_000_000, 6),
+            entry_price: Price::from_raw(1_000_000, 1_000_000).unwrap(),
+            stop_loss: Price::from_raw(1_000_000, 900_000).unwrap(),
+            take_profit: Price::from_raw(1_000_000, 1_500_000).unwrap(),
+            opened_at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    fn agent_with_store(store: Arc<dyn Store>) -> CoralAgent {
+        CoralAgent::builder(AgentConfig::default())
+            .store(store)
+            .tokens(Arc::new(TokenRegistry::new()))
+            .analyzer(Box::new(IdleAnalyzer))
+            .build()
+            .unwrap()
+    }
+
+    fn fill(signature: &str, amount: u64, output_amount: u64) -> TradeResult {
+        let price = Price::from_raw(amount, output_amount).unwrap();
+        TradeResult {
+            transaction_signature: signature.to_string(),
+            amount,
+            output_amount,
+            quoted_price: price,
+            executed_price: price,
+            slippage_bps: 0,
+            route: Vec::new(),
+            slot: 1,
+            confirmation_status: ConfirmationStatus::Finalized,
+            fee: 5_000,
+            priority_fee: 0,
+            timestamp: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    #[test]
+    fn test_build_rejects_analyzers_that_all_weigh_nothing() {
+        let mut config = AgentConfig::default();
+        config.analysis.analyzers.insert("idle".to_string(), AnalyzerSettings {
+            weight: 0.0,
+            ..Default::default()
+        });
+
+        let result = CoralAgent::builder(config)
+            .tokens(Arc::new(TokenRegistry::new()))
+            .analyzer(Box::new(IdleAnalyzer))
+            .build();
+        assert!(matches!(result, Err(CoralError::Config { field, .. }) if field == "analysis.analyzers"));
+    }
+
+    #[tokio::test]
+    async fn test_batch_analyzes_each_mint_once_and_survives_failures() {
+        let (weak, strong, broken) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let runs = Arc::new(AtomicUsize::new(0));
+        let mut config = AgentConfig::default();
+        // Uncached, so every run reaches the analyzer
+        config.analysis.analyzers.insert("scripted".to_string(), AnalyzerSettings {
+            cache_ttl_ms: Some(0),
+            ..Default::default()
+        });
+        let agent = CoralAgent::builder(config)
+            .tokens(Arc::new(TokenRegistry::new()))
+            .analyzer(Box::new(ScriptedAnalyzer {
+                scores: HashMap::from([(weak, 0.4), (strong, 0.9)]),
+                runs: runs.clone(),
+            }))
+            .build()
+            .unwrap();
+
+        let results: HashMap<Pubkey, Result<AnalysisResult, CoralError>> = agent
+            .analyze_batch(vec![weak, strong, weak, broken, strong])
+            .collect()
+            .await;
+        assert_eq!(results.len(), 3);
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        assert!(results[&weak].is_ok() && results[&strong].is_ok());
+        assert!(matches!(results[&broken], Err(CoralError::AnalysisFailed { token, .. }) if token == broken));
+
+        let summary = agent.analyze_watchlist(vec![broken, weak, strong]).await;
+        let ranked: Vec<Pubkey> = summary.ranked.iter().map(|entry| entry.token_address).collect();
+        assert_eq!(ranked, vec![strong, weak]);
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.failed[0].token_address, broken);
+    }
+
+    #[tokio::test]
+    async fn test_recover_closes_position_whose_exit_filled() {
+        let store: Arc<dyn Store> = Arc::new(SqliteStore::open_in_memory().unwrap());
+        let agent = agent_with_store(store.clone());
+
+        let token = Pubkey::new_unique();
+        let held = Position {
+            id: PositionId::generate(&token),
+            token_address: token,
+            quote_token: agent.config.trading.quote_token,
+            size: TokenAmount::new(5_000_000, 6),
+            entry_price: Price::from_raw(1_000, 2_000).unwrap(),
+            stop_loss: Price::from_raw(1_000, 1_000).unwrap(),
+            take_profit: Price::from_raw(1_000, 4_000).unwrap(),
+            opened_at: chrono::Utc::now().timestamp() - 60,
+        };
+        let exited = Position {
+            id: PositionId::generate(&token),
+            size: TokenAmount::new(2_000_000, 6),
+            ..held.clone()
+        };
+        for position in [&held, &exited] {
+            store
+                .record_position_event(&PositionEvent::now(
+                    position,
+                    PositionEventKind::Opened { position: position.clone() },
+                ))
+                .await
+                .unwrap();
+        }
+
+        // The exit filled, but the process stopped before recording the close
+        let exit_order = agent.exit_order(&exited);
+        let order_id = store.record_order(&exit_order).await.unwrap();
+        store.record_trade(order_id, &fill("exit", exit_order.amount, 9_000_000)).await.unwrap();
+
+        let report = agent.recover().await.unwrap();
+        assert_eq!(report.replayed_trades, 1);
+        assert!(report.unresolved.is_empty());
+        assert_eq!(report.open_positions.len(), 1);
+        assert_eq!(report.open_positions[0].id, held.id);
+
+        let events = store.position_events(&StoreQuery::all()).await.unwrap();
+        assert!(events.iter().any(|event| matches!(
+            &event.kind,
+            PositionEventKind::Closed { transaction_signature } if transaction_signature == "exit"
+        )));
+        // A second restart finds the close recorded and does not close again
+        let report = agent.recover().await.unwrap();
+        assert_eq!(report.open_positions.len(), 1);
+        let closes = store
+            .position_events(&StoreQuery::all())
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|event| matches!(event.kind, PositionEventKind::Closed { .. }))
+            .count();
+        assert_eq!(closes, 1);
+    }
+
+    #[tokio::test]
+    async fn test_monitor_exits_position_when_stop_loss_is_hit() {
+        let store: Arc<dyn Store> = Arc::new(SqliteStore::open_in_memory().unwrap());
+        let trader = StubTrader::default();
+        trader.output.store(800_000, Ordering::SeqCst);
+        let agent = monitoring_agent(store.clone(), trader);
+        let position = position_in(agent.config.trading.quote_token);
+
+        agent.monitor_position(position.clone()).await.unwrap();
+
+        let kinds: Vec<PositionEventKind> = store
+            .position_events(&StoreQuery::all())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|event| event.kind)
+            .collect();
+        assert_eq!(kinds.len(), 3);
+        assert!(matches!(&kinds[0], PositionEventKind::Opened { position: opened } if opened.id == position.id));
+        assert!(matches!(kinds[1], PositionEventKind::ExitTriggered { trigger: ExitTrigger::StopLoss, .. }));
+        assert!(matches!(&kinds[2], PositionEventKind::Closed { transaction_signature } if transaction_signature == "exit"));
+        assert!(!agent.stop_monitoring(&position).await);
+    }
+
+    #[tokio::test]
+    async fn test_monitor_retries_a_failed_exit() {
+        let store: Arc<dyn Store> = Arc::new(SqliteStore::open_in_memory().unwrap());
+        let trader = StubTrader::default();
+        trader.output.store(800_000, Ordering::SeqCst);
+        trader.failures.store(2, Ordering::SeqCst);
+        let agent = monitoring_agent(store.clone(), trader.clone());
+        let position = position_in(agent.config.trading.quote_token);
+
+        agent.monitor_position(position.clone()).await.unwrap();
+
+        assert_eq!(trader.failures.load(Ordering::SeqCst), 0);
+        let events = store.position_events(&StoreQuery::all()).await.unwrap();
+        assert!(matches!(
+            &events.last().unwrap().kind,
+            PositionEventKind::Closed { transaction_signature } if transaction_signature == "exit"
+        ));
+        assert!(open_positions(&events).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stop_loss_exits_position_held_against_another_quote_token() {
+        let store: Arc<dyn Store> = Arc::new(SqliteStore::open_in_memory().unwrap());
+        let trader = StubTrader::default();
+        trader.output.store(800_000, Ordering::SeqCst);
+        let agent = monitoring_agent(store.clone(), trader);
+        let position = position_in(Pubkey::new_unique());
+        assert_ne!(position.quote_token, agent.config.trading.quote_token);
+
+        // No analysis of either token exists, so only an exit gets past the risk gate
+        agent.monitor_position(position.clone()).await.unwrap();
+
+        let events = store.position_events(&StoreQuery::all()).await.unwrap();
+        assert!(matches!(
+            &events.last().unwrap().kind,
+            PositionEventKind::Closed { transaction_signature } if transaction_signature == "exit"
+        ));
+        assert!(open_positions(&events).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stopped_monitor_leaves_position_open() {
+        let store: Arc<dyn Store> = Arc::new(SqliteStore::open_in_memory().unwrap());
+        let trader = StubTrader::default();
+        trader.output.store(1_000_000, Ordering::SeqCst);
+        let agent = monitoring_agent(store.clone(), trader.clone());
+        // Priced in the position's own quote token, not the configured one
+        let quote_token = Pubkey::new_unique();
+        let position = position_in(quote_token);
+
+        let watcher = {
+            let (agent, position) = (Arc::clone(&agent), position.clone());
+            tokio::spawn(async move { agent.monitor_position(position).await })
+        };
+        while trader.quoted_in.lock().unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        assert!(agent.stop_monitoring(&position).await);
+        watcher.await.unwrap().unwrap();
+        assert!(trader.quoted_in.lock().unwrap().iter().all(|quote| *quote == quote_token));
+
+        let events = store.position_events(&StoreQuery::all()).await.unwrap();
+        assert_eq!(open_positions(&events).len(), 1);
+        assert!(agent.position_stops.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_recover_does_not_reuse_an_exit_that_closed_another_position() {
+        let store: Arc<dyn Store> = Arc::new(SqliteStore::open_in_memory().unwrap());
+        let agent = agent_with_store(store.clone());
+
+        let first = Position {
+            opened_at: chrono::Utc::now().timestamp() - 120,
+            ..position_in(agent.config.trading.quote_token)
+        };
+        // Same token and size, opened after the first
+        let second = Position {
+            id: PositionId::generate(&first.token_address),
+            opened_at: first.opened_at + 60,
+            ..first.clone()
+        };
+        for position in [&first, &second] {
+            store
+                .record_position_event(&PositionEvent::now(
+                    position,
+                    PositionEventKind::Opened { position: position.clone() },
+                ))
+                .await
+                .unwrap();
+        }
+
+        // The first position exits normally after the second one opened
+        let exit_order = agent.exit_order(&first);
+        let order_id = store.record_order(&exit_order).await.unwrap();
+        store.record_trade(order_id, &fill("exit", exit_order.amount, 900_000)).await.unwrap();
+        store
+            .record_position_event(&PositionEvent::now(&first, PositionEventKind::Closed {
+                transaction_signature: "exit".to_string(),
+            }))
+            .await
+            .unwrap();
+
+        let report = agent.recover().await.unwrap();
+        assert_eq!(report.open_positions.len(), 1);
+        assert_eq!(report.open_positions[0].id, second.id);
+        let closes = store
+            .position_events(&StoreQuery::all())
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|event| matches!(event.kind, PositionEventKind::Closed { .. }))
+            .count();
+        assert_eq!(closes, 1);
+    }
+
+    #[test]
+    fn test_reconciled_fill_must_spend_the_order_input() {
+        let order = TradeOrder {
+            from_token: Pubkey::new_unique(),
+            to_token: Pubkey::new_unique(),
+            amount: 1_000_000,
+            slippage_tolerance: 0.01,
+            minimum_received: None,
+            route_preference: RoutePreference::BestPrice,
+        };
+        assert!(matches_order_input(&order, 1_000_000));
+        assert!(matches_order_input(&order, 995_000));
+        assert!(!matches_order_input(&order, 0));
+        assert!(!matches_order_input(&order, 500_000));
+
+        let sol_order = TradeOrder {
+            from_token: spl_token::native_mint::id(),
+            ..order
+        };
+        assert!(matches_order_input(&sol_order, 1_000_000 + WRAPPED_SOL_RENT));
     }
 }
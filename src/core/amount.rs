@@ -0,0 +1,371 @@
+//! Fixed-point token amounts and prices
+//!
+//! Amounts are kept as the raw base units stored on chain together with the mint's
+//! decimals, so converting to and from `u64` never loses precision. Arithmetic is
+//! checked; amounts with different decimals are rescaled to the finer precision first.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use thiserror::Error;
+
+/// Fractional digits carried by `Price`
+pub const PRICE_DECIMALS: u32 = 18;
+const PRICE_SCALE: u128 = 10u128.pow(PRICE_DECIMALS);
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AmountError {
+    #[error("amount overflow")]
+    Overflow,
+
+    #[error("invalid amount {0:?}")]
+    InvalidFormat(String),
+
+    #[error("{value:?} has more than {decimals} decimal places")]
+    TooPrecise { value: String, decimals: u8 },
+}
+
+/// Unsigned amount of a token in its base units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TokenAmount {
+    raw: u64,
+    decimals: u8,
+}
+
+impl TokenAmount {
+    pub const fn new(raw: u64, decimals: u8) -> Self {
+        Self { raw, decimals }
+    }
+
+    pub const fn zero(decimals: u8) -> Self {
+        Self::new(0, decimals)
+    }
+
+    /// Parses a UI amount such as `"12.5"`, rejecting more fractional digits than `decimals`
+    pub fn parse(value: &str, decimals: u8) -> Result<Self, AmountError> {
+        let raw = parse_fixed(value, decimals as u32, false)?;
+        u64::try_from(raw)
+            .map(|raw| Self::new(raw, decimals))
+            .map_err(|_| AmountError::Overflow)
+    }
+
+    pub const fn raw(&self) -> u64 {
+        self.raw
+    }
+
+    pub const fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    pub const fn is_zero(&self) -> bool {
+        self.raw == 0
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let decimals = self.decimals.max(other.decimals);
+        let raw = self.rescaled(decimals)?.checked_add(other.rescaled(decimals)?)?;
+        Some(Self::new(raw, decimals))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        let decimals = self.decimals.max(other.decimals);
+        let raw = self.rescaled(decimals)?.checked_sub(other.rescaled(decimals)?)?;
+        Some(Self::new(raw, decimals))
+    }
+
+    /// `self * numerator / denominator`, rounded down
+    pub fn checked_mul_ratio(self, numerator: u64, denominator: u64) -> Option<Self> {
+        if denominator == 0 {
+            return None;
+        }
+        let raw = self.raw as u128 * numerator as u128 / denominator as u128;
+        Some(Self::new(u64::try_from(raw).ok()?, self.decimals))
+    }
+
+    /// Signed difference `self - other`, for P&L
+    pub fn signed_sub(self, other: Self) -> Option<SignedAmount> {
+        SignedAmount::from(self).checked_sub(SignedAmount::from(other))
+    }
+
+    fn rescaled(&self, decimals: u8) -> Option<u64> {
+        let factor = 10u64.checked_pow((decimals - self.decimals) as u32)?;
+        self.raw.checked_mul(factor)
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_fixed(self.raw as u128, self.decimals as u32, false))
+    }
+}
+
+/// Signed amount in base units, used for profit and loss
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SignedAmount {
+    raw: i128,
+    decimals: u8,
+}
+
+impl SignedAmount {
+    pub const fn new(raw: i128, decimals: u8) -> Self {
+        Self { raw, decimals }
+    }
+
+    pub const fn zero(decimals: u8) -> Self {
+        Self::new(0, decimals)
+    }
+
+    pub const fn raw(&self) -> i128 {
+        self.raw
+    }
+
+    pub const fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    pub const fn is_negative(&self) -> bool {
+        self.raw < 0
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let decimals = self.decimals.max(other.decimals);
+        let raw = self.rescaled(decimals)?.checked_add(other.rescaled(decimals)?)?;
+        Some(Self::new(raw, decimals))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        let decimals = self.decimals.max(other.decimals);
+        let raw = self.rescaled(decimals)?.checked_sub(other.rescaled(decimals)?)?;
+        Some(Self::new(raw, decimals))
+    }
+
+    /// Magnitude as an unsigned amount, if it fits
+    pub fn abs(&self) -> Option<TokenAmount> {
+        u64::try_from(self.raw.unsigned_abs())
+            .ok()
+            .map(|raw| TokenAmount::new(raw, self.decimals))
+    }
+
+    fn rescaled(&self, decimals: u8) -> Option<i128> {
+        let factor = 10i128.checked_pow((decimals - self.decimals) as u32)?;
+        self.raw.checked_mul(factor)
+    }
+}
+
+impl From<TokenAmount> for SignedAmount {
+    fn from(amount: TokenAmount) -> Self {
+        Self::new(amount.raw as i128, amount.decimals)
+    }
+}
+
+impl fmt::Display for SignedAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_fixed(self.raw.unsigned_abs(), self.decimals as u32, self.raw < 0))
+    }
+}
+
+/// Quote token base units paid per base unit of the traded token, with
+/// `PRICE_DECIMALS` of fixed-point precision. Working in base units keeps the price
+/// independent of either mint's decimals; `to_ui_string` renders the human price.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Price {
+    value: u128,
+}
+
+impl Price {
+    pub const ZERO: Price = Price { value: 0 };
+
+    /// Price implied by swapping `base_raw` units for `quote_raw` units
+    pub fn from_raw(base_raw: u64, quote_raw: u64) -> Option<Self> {
+        if base_raw == 0 {
+            return None;
+        }
+        Some(Self {
+            value: quote_raw as u128 * PRICE_SCALE / base_raw as u128,
+        })
+    }
+
+    pub fn from_amounts(base: TokenAmount, quote: TokenAmount) -> Option<Self> {
+        Self::from_raw(base.raw, quote.raw)
+    }
+
+    /// Quote units worth `base_raw` units at this price, rounded down
+    pub fn quote_for(&self, base_raw: u64) -> Option<u64> {
+        u64::try_from(mul_div(base_raw as u128, self.value, PRICE_SCALE)?).ok()
+    }
+
+    /// Base units that `quote_raw` quote units buy at this price, rounded down
+    pub fn base_for(&self, quote_raw: u64) -> Option<u64> {
+        if self.value == 0 {
+            return None;
+        }
+        u64::try_from(mul_div(quote_raw as u128, PRICE_SCALE, self.value)?).ok()
+    }
+
+    /// Value of `amount` in the quote token
+    pub fn value_of(&self, amount: TokenAmount, quote_decimals: u8) -> Option<TokenAmount> {
+        self.quote_for(amount.raw).map(|raw| TokenAmount::new(raw, quote_decimals))
+    }
+
+    /// `self * numerator / denominator`, e.g. a stop loss 10% below entry is `scaled(9, 10)`
+    pub fn scaled(&self, numerator: u64, denominator: u64) -> Option<Self> {
+        if denominator == 0 {
+            return None;
+        }
+        mul_div(self.value, numerator as u128, denominator as u128).map(|value| Self { value })
+    }
+
+    /// Whole quote tokens per whole base token, if it fits
+    pub fn to_ui_string(&self, base_decimals: u8, quote_decimals: u8) -> Option<String> {
+        let shift = base_decimals as i32 - quote_decimals as i32;
+        let (value, decimals) = if shift >= 0 {
+            (self.value.checked_mul(10u128.checked_pow(shift as u32)?)?, PRICE_DECIMALS)
+        } else {
+            (self.value, PRICE_DECIMALS + shift.unsigned_abs())
+        };
+        Some(trim_fraction(format_fixed(value, decimals, false)))
+    }
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&trim_fraction(format_fixed(self.value, PRICE_DECIMALS, false)))
+    }
+}
+
+impl From<Price> for String {
+    fn from(price: Price) -> Self {
+        price.to_string()
+    }
+}
+
+impl TryFrom<String> for Price {
+    type Error = AmountError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Ok(Self {
+            value: parse_fixed(&value, PRICE_DECIMALS, true)?,
+        })
+    }
+}
+
+/// `a * b / c` without intermediate overflow when `a * (b % c)` fits
+fn mul_div(a: u128, b: u128, c: u128) -> Option<u128> {
+    if c == 0 {
+        return None;
+    }
+    let whole = a.checked_mul(b / c)?;
+    let fraction = a.checked_mul(b % c)? / c;
+    whole.checked_add(fraction)
+}
+
+fn format_fixed(value: u128, decimals: u32, negative: bool) -> String {
+    let sign = if negative && value != 0 { "-" } else { "" };
+    if decimals == 0 {
+        return format!("{}{}", sign, value);
+    }
+    let scale = match 10u128.checked_pow(decimals) {
+        Some(scale) => scale,
+        // Past 38 decimals the scale exceeds any u128, so the whole part is zero
+        None => return format!("{}0.{:0width$}", sign, value, width = decimals as usize),
+    };
+    format!(
+        "{}{}.{:0width$}",
+        sign,
+        value / scale,
+        value % scale,
+        width = decimals as usize
+    )
+}
+
+fn trim_fraction(formatted: String) -> String {
+    if !formatted.contains('.') {
+        return formatted;
+    }
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Parses a non-negative decimal into `decimals` fixed-point digits. With `truncate`,
+/// extra fractional digits are dropped instead of rejected.
+fn parse_fixed(value: &str, decimals: u32, truncate: bool) -> Result<u128, AmountError> {
+    let invalid = || AmountError::InvalidFormat(value.to_string());
+    let (whole, fraction) = value.trim().split_once('.').unwrap_or((value.trim(), ""));
+
+    let digits_only = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !digits_only(whole) || !digits_only(fraction) {
+        return Err(invalid());
+    }
+
+    let fraction = match fraction.len().cmp(&(decimals as usize)) {
+        Ordering::Greater if truncate => &fraction[..decimals as usize],
+        Ordering::Greater => {
+            return Err(AmountError::TooPrecise {
+                value: value.to_string(),
+                decimals: decimals as u8,
+            })
+        }
+        _ => fraction,
+    };
+
+    let whole: u128 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| AmountError::Overflow)? };
+    let fraction_value: u128 = if fraction.is_empty() { 0 } else { fraction.parse().map_err(|_| invalid())? };
+    let fraction_scale = 10u128.checked_pow(decimals - fraction.len() as u32).ok_or(AmountError::Overflow)?;
+    let scale = 10u128.checked_pow(decimals).ok_or(AmountError::Overflow)?;
+
+    whole
+        .checked_mul(scale)
+        .and_then(|w| w.checked_add(fraction_value.checked_mul(fraction_scale)?))
+        .ok_or(AmountError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amounts_round_trip_exactly() {
+        let amount = TokenAmount::parse("1234.000001", 6).unwrap();
+        assert_eq!(amount.raw(), 1_234_000_001);
+        assert_eq!(amount.to_string(), "1234.000001");
+        assert_eq!(TokenAmount::new(u64::MAX, 9).to_string(), "18446744073.709551615");
+
+        assert!(matches!(TokenAmount::parse("0.0000001", 6), Err(AmountError::TooPrecise { .. })));
+        assert!(matches!(TokenAmount::parse("1e6", 6), Err(AmountError::InvalidFormat(_))));
+
+        // Decimals whose scale does not fit a u128
+        assert_eq!(TokenAmount::new(5, 40).to_string(), format!("0.{}5", "0".repeat(39)));
+        assert!(matches!(TokenAmount::parse("1", 40), Err(AmountError::Overflow)));
+    }
+
+    #[test]
+    fn test_checked_arithmetic_rescales_and_detects_overflow() {
+        let a = TokenAmount::new(1_500_000, 6);
+        let b = TokenAmount::new(250_000_000, 9);
+        assert_eq!(a.checked_add(b), Some(TokenAmount::new(1_750_000_000, 9)));
+        assert_eq!(TokenAmount::new(u64::MAX, 0).checked_add(TokenAmount::new(1, 0)), None);
+
+        let pnl = TokenAmount::new(900_000, 6).signed_sub(TokenAmount::new(1_000_000, 6)).unwrap();
+        assert!(pnl.is_negative());
+        assert_eq!(pnl.to_string(), "-0.100000");
+    }
+
+    #[test]
+    fn test_price_conversions() {
+        // 2_000_000 base units bought for 3_000_000 quote units
+        let price = Price::from_raw(2_000_000, 3_000_000).unwrap();
+        assert_eq!(price.to_string(), "1.5");
+        assert_eq!(price.quote_for(10), Some(15));
+        assert_eq!(price.base_for(15), Some(10));
+
+        // 1 token with 9 decimals at 1.5 USDC (6 decimals)
+        let ui = Price::from_raw(1_000_000_000, 1_500_000).unwrap();
+        assert_eq!(ui.to_ui_string(9, 6).as_deref(), Some("1.5"));
+        assert_eq!(ui.to_ui_string(200, 0), None);
+
+        let stop = price.scaled(9, 10).unwrap();
+        assert!(stop < price);
+        assert_eq!(Price::try_from(price.to_string()).unwrap(), price);
+        assert_eq!(Price::from_raw(0, 1), None);
+    }
+}
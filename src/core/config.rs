@@ -0,0 +1,579 @@
+//! Agent configuration loaded from TOML
+//!
+//! `${VAR}` placeholders in string values are replaced from the environment. Afterwards any
+//! `CORAL_<SECTION>__<KEY>` variable overrides the matching key, e.g.
+//! `CORAL_TRADING__DEFAULT_SLIPPAGE=0.005` or `CORAL_RPC__PRIMARY_ENDPOINT=...`.
+//! The merged configuration is validated before it is handed out. Syntax errors are
+//! reported by line, wrong types and invalid values by their key. Unknown keys, from the
+//! file or an override, are rejected rather than silently falling back to the default.
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::path::Path;
+use crate::analyzers::{
+    consensus::ConsensusConfig, pools::LiquidityConfig, registry::AnalyzerSettings, wallet::HolderConfig,
+};
+use crate::core::risk::RiskConfig;
+use crate::core::types::CoralError;
+use crate::notifiers::NotificationPriority;
+use crate::utils::token_registry::USDC_MINT;
+
+const ENV_OVERRIDE_PREFIX: &str = "CORAL_";
+const ENV_OVERRIDE_SEPARATOR: &str = "__";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AgentConfig {
+    pub rpc: RpcConfig,
+    pub trading: TradingConfig,
+    pub analysis: AnalysisConfig,
+    pub risk: RiskConfig,
+    pub storage: StorageConfig,
+    pub tokens: TokensConfig,
+    pub security: SecurityConfig,
+    pub alerts: AlertsConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcConfig {
+    pub primary_endpoint: String,
+    pub backup_endpoints: Vec<String>,
+    pub max_retries: u32,
+    pub timeout_ms: u64,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            primary_endpoint: "https://api.mainnet-beta.solana.com".to_string(),
+            backup_endpoints: Vec::new(),
+            max_retries: 3,
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TradingConfig {
+    /// Token positions are entered from and exited back into
+    #[serde(with = "pubkey_string")]
+    pub quote_token: Pubkey,
+    pub max_position_size: u64,
+    pub default_slippage: f64,
+    pub exit_slippage: f64,
+    pub position_poll_interval_ms: u64,
+}
+
+impl Default for TradingConfig {
+    fn default() -> Self {
+        Self {
+            quote_token: USDC_MINT,
+            max_position_size: 100_000_000_000,
+            default_slippage: 0.001,
+            exit_slippage: 0.01,
+            position_poll_interval_ms: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalysisConfig {
+    pub consensus: ConsensusConfig,
+    /// Settings per analyzer, keyed by `Analyzer::name`
+    pub analyzers: HashMap<String, AnalyzerSettings>,
+    /// Tokens analyzed at once by the batch and watchlist APIs
+    pub batch_concurrency: usize,
+    pub holders: HolderConfig,
+    pub liquidity: LiquidityConfig,
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        Self {
+            consensus: ConsensusConfig::default(),
+            analyzers: HashMap::new(),
+            batch_concurrency: 8,
+            holders: HolderConfig::default(),
+            liquidity: LiquidityConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// SQLite database holding analysis, order and trade history; nothing is persisted
+    /// when unset
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TokensConfig {
+    /// Token list JSON (Solana token-list format) whose symbols become resolvable
+    /// alongside the built-in SOL, USDC and USDT
+    pub list_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecurityConfig {
+    pub jupiter_api_key: Option<String>,
+    pub github_token: Option<String>,
+    pub keypair_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertsConfig {
+    pub telegram_enabled: bool,
+    pub telegram_bot_token: Option<String>,
+    pub telegram_chat_id: Option<i64>,
+    pub min_priority: NotificationPriority,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            telegram_enabled: false,
+            telegram_bot_token: None,
+            telegram_chat_id: None,
+            min_priority: NotificationPriority::Medium,
+        }
+    }
+}
+
+impl AgentConfig {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CoralError> {
+        let path = path.as_ref();
+        let raw = std::fs::read_to_string(path)
+            .map_err(|e| CoralError::Initialization {
+                message: format!("cannot read config file {}", path.display()),
+                source: Some(e.into()),
+            })?;
+
+        Self::from_toml_str(&raw, std::env::vars())
+    }
+
+    /// Parses `raw` with `env` as the source for placeholders and overrides
+    pub fn from_toml_str(
+        raw: &str,
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, CoralError> {
+        let env: HashMap<String, String> = env.into_iter().collect();
+
+        let mut value: toml::Value = toml::from_str(raw).map_err(|e| {
+            // Syntax errors have no key yet, so they are named by line
+            let line = e.span().map_or(1, |span| raw[..span.start].matches('\n').count() + 1);
+            CoralError::config(format!("line {}", line), e.message())
+        })?;
+        substitute_placeholders(&mut value, &env)?;
+        apply_env_overrides(&mut value, &env)?;
+
+        let config: AgentConfig = serde_path_to_error::deserialize(value).map_err(|e| {
+            let field = match e.path().to_string() {
+                path if path == "." => "config".to_string(),
+                path => path,
+            };
+            CoralError::config(field, e.into_inner().message())
+        })?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), CoralError> {
+        validate_endpoint("rpc.primary_endpoint", &self.rpc.primary_endpoint)?;
+        for (i, endpoint) in self.rpc.backup_endpoints.iter().enumerate() {
+            validate_endpoint(&format!("rpc.backup_endpoints[{}]", i), endpoint)?;
+        }
+        if self.rpc.timeout_ms == 0 {
+            return Err(invalid("rpc.timeout_ms", "must be greater than zero"));
+        }
+
+        if self.trading.max_position_size == 0 {
+            return Err(invalid("trading.max_position_size", "must be greater than zero"));
+        }
+        validate_fraction("trading.default_slippage", self.trading.default_slippage)?;
+        validate_fraction("trading.exit_slippage", self.trading.exit_slippage)?;
+        if self.trading.position_poll_interval_ms == 0 {
+            return Err(invalid("trading.position_poll_interval_ms", "must be greater than zero"));
+        }
+
+        if self.analysis.batch_concurrency == 0 {
+            return Err(invalid("analysis.batch_concurrency", "must be greater than zero"));
+        }
+        for (analyzer, settings) in &self.analysis.analyzers {
+            validate_weight(&format!("analysis.analyzers.{}.weight", analyzer), settings.weight)?;
+            if settings.timeout_ms == Some(0) {
+                return Err(invalid(
+                    &format!("analysis.analyzers.{}.timeout_ms", analyzer),
+                    "must be greater than zero",
+                ));
+            }
+        }
+
+        if self.risk.reduce_at_risk_level > self.risk.max_risk_level {
+            return Err(invalid("risk.reduce_at_risk_level", "must not be above risk.max_risk_level"));
+        }
+        if !(self.risk.reduced_size_factor > 0.0 && self.risk.reduced_size_factor <= 1.0) {
+            return Err(invalid("risk.reduced_size_factor", "must be greater than 0 and at most 1"));
+        }
+        if self.risk.max_analysis_age_secs <= 0 {
+            return Err(invalid("risk.max_analysis_age_secs", "must be greater than zero"));
+        }
+        if self.risk.daily_loss_limit == 0 {
+            return Err(invalid("risk.daily_loss_limit", "must be greater than zero"));
+        }
+        if self.risk.max_token_exposure > self.risk.max_total_exposure {
+            return Err(invalid("risk.max_token_exposure", "must not exceed risk.max_total_exposure"));
+        }
+
+        if self.analysis.holders.lookup_concurrency == 0 {
+            return Err(invalid("analysis.holders.lookup_concurrency", "must be greater than zero"));
+        }
+        let concentration = &self.analysis.holders.concentration;
+        if concentration.top_n == 0 {
+            return Err(invalid("analysis.holders.concentration.top_n", "must be greater than zero"));
+        }
+        for (field, bands) in [
+            ("gini", concentration.gini),
+            ("hhi", concentration.hhi),
+            ("top_n_share", concentration.top_n_share),
+        ] {
+            if !(0.0..=1.0).contains(&bands.medium) || !(bands.medium..=1.0).contains(&bands.high) {
+                return Err(invalid(
+                    &format!("analysis.holders.concentration.{}", field),
+                    "bands must satisfy 0 <= medium <= high <= 1",
+                ));
+            }
+        }
+        if concentration.nakamoto.high > concentration.nakamoto.medium {
+            return Err(invalid(
+                "analysis.holders.concentration.nakamoto",
+                "high must not exceed medium; fewer holders in control is riskier",
+            ));
+        }
+
+        let snapshots = &self.analysis.holders.snapshots;
+        if snapshots.interval_secs == 0 {
+            return Err(invalid("analysis.holders.snapshots.interval_secs", "must be greater than zero"));
+        }
+        if snapshots.tracked_holders < 10 {
+            return Err(invalid(
+                "analysis.holders.snapshots.tracked_holders",
+                "must be at least 10 to measure the top-10 share",
+            ));
+        }
+        for (field, value) in [
+            ("holder_growth", snapshots.holder_growth),
+            ("top_10_drift", snapshots.top_10_drift),
+            ("large_wallet_share", snapshots.large_wallet_share),
+            ("whale_share", snapshots.whale_share),
+            ("whale_exit_fraction", snapshots.whale_exit_fraction),
+        ] {
+            if !(value > 0.0 && value <= 1.0) {
+                return Err(invalid(
+                    &format!("analysis.holders.snapshots.{}", field),
+                    "must be greater than 0 and at most 1",
+                ));
+            }
+        }
+
+        let liquidity = &self.analysis.liquidity;
+        validate_fraction("analysis.liquidity.max_withdrawable_lp_share", liquidity.max_withdrawable_lp_share)?;
+        validate_fraction("analysis.liquidity.min_pool_share", liquidity.min_pool_share)?;
+
+        if self.storage.path.as_deref() == Some("") {
+            return Err(invalid("storage.path", "must not be empty; omit it to disable persistence"));
+        }
+        if self.tokens.list_path.as_deref() == Some("") {
+            return Err(invalid("tokens.list_path", "must not be empty; omit it to use the built-in tokens only"));
+        }
+
+        if self.alerts.telegram_enabled {
+            if self.alerts.telegram_bot_token.as_deref().unwrap_or("").is_empty() {
+                return Err(invalid("alerts.telegram_bot_token", "required when telegram_enabled is set"));
+            }
+            if self.alerts.telegram_chat_id.is_none() {
+                return Err(invalid("alerts.telegram_chat_id", "required when telegram_enabled is set"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn invalid(field: &str, reason: &str) -> CoralError {
+    CoralError::config(field, reason)
+}
+
+fn validate_endpoint(field: &str, endpoint: &str) -> Result<(), CoralError> {
+    if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+        Ok(())
+    } else {
+        Err(invalid(field, "must be an http(s) URL"))
+    }
+}
+
+fn validate_fraction(field: &str, value: f64) -> Result<(), CoralError> {
+    if value > 0.0 && value < 1.0 {
+        Ok(())
+    } else {
+        Err(invalid(field, "must be between 0 and 1 (exclusive)"))
+    }
+}
+
+fn validate_weight(field: &str, value: f64) -> Result<(), CoralError> {
+    if value.is_finite() && value >= 0.0 {
+        Ok(())
+    } else {
+        Err(invalid(field, "must be a non-negative number"))
+    }
+}
+
+/// Replaces placeholders in every string value; keys and comments are left alone
+fn substitute_placeholders(value: &mut toml::Value, env: &HashMap<String, String>) -> Result<(), CoralError> {
+    match value {
+        toml::Value::String(raw) => *raw = substitute_env(raw, env)?,
+        toml::Value::Array(items) => {
+            for item in items {
+                substitute_placeholders(item, env)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (_, item) in table.iter_mut() {
+                substitute_placeholders(item, env)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn substitute_env(raw: &str, env: &HashMap<String, String>) -> Result<String, CoralError> {
+    let mut output = String::with_capacity(raw.len());
+    let mut rest = raw;
+
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| CoralError::config("${", "unterminated placeholder"))?;
+        let name = &after[..end];
+        let value = env
+            .get(name)
+            .ok_or_else(|| CoralError::config(format!("${{{}}}", name), "environment variable is not set"))?;
+        output.push_str(value);
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+
+    Ok(output)
+}
+
+/// Applies `CORAL_<SECTION>__<KEY>` variables on top of the parsed file
+fn apply_env_overrides(value: &mut toml::Value, env: &HashMap<String, String>) -> Result<(), CoralError> {
+    let mut overrides: Vec<(&String, &String)> = env
+        .iter()
+        .filter(|(key, _)| key.starts_with(ENV_OVERRIDE_PREFIX) && key.contains(ENV_OVERRIDE_SEPARATOR))
+        .collect();
+    overrides.sort();
+
+    for (key, raw) in overrides {
+        let path: Vec<String> = key[ENV_OVERRIDE_PREFIX.len()..]
+            .split(ENV_OVERRIDE_SEPARATOR)
+            .map(|segment| segment.to_lowercase())
+            .collect();
+        let field = path.join(".");
+
+        let mut table = value
+            .as_table_mut()
+            .ok_or_else(|| invalid(&field, "configuration root is not a table"))?;
+        for segment in &path[..path.len() - 1] {
+            table = table
+                .entry(segment.clone())
+                .or_insert_with(|| toml::Value::Table(toml::map::Map::new()))
+                .as_table_mut()
+                .ok_or_else(|| invalid(&field, &format!("{} is not a section", segment)))?;
+        }
+        table.insert(path[path.len() - 1].clone(), parse_override(raw));
+    }
+
+    Ok(())
+}
+
+/// Reads an override as a TOML literal where possible so numbers and booleans keep
+/// their type, and as a plain string otherwise.
+fn parse_override(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+mod pubkey_string {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use solana_sdk::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(key: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&key.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        Pubkey::from_str(&raw).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Base58 strings in TOML for a list of keys
+pub(crate) mod pubkey_list {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use solana_sdk::pubkey::Pubkey;
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(keys: &[Pubkey], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(keys.iter().map(Pubkey::to_string))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Pubkey>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|raw| Pubkey::from_str(raw).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+        [rpc]
+        primary_endpoint = "${SOLANA_RPC_URL}"
+
+        [trading]
+        default_slippage = 0.002
+
+        [analysis.analyzers.wallet]
+        weight = 2.0
+    "#;
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_substitutes_placeholders_and_applies_overrides() {
+        let config = AgentConfig::from_toml_str(SAMPLE, env(&[
+            ("SOLANA_RPC_URL", "https://api.testnet.solana.com"),
+            ("CORAL_TRADING__DEFAULT_SLIPPAGE", "0.005"),
+            ("CORAL_SECURITY__GITHUB_TOKEN", "ghp_test"),
+        ]))
+        .unwrap();
+
+        assert_eq!(config.rpc.primary_endpoint, "https://api.testnet.solana.com");
+        assert_eq!(config.trading.default_slippage, 0.005);
+        assert_eq!(config.security.github_token.as_deref(), Some("ghp_test"));
+        assert_eq!(config.analysis.analyzers["wallet"].weight, 2.0);
+        assert!(config.analysis.analyzers["wallet"].enabled);
+    }
+
+    #[test]
+    fn test_missing_placeholder_is_config_error() {
+        let result = AgentConfig::from_toml_str(SAMPLE, env(&[]));
+        assert!(matches!(result, Err(CoralError::Config { field, .. }) if field == "${SOLANA_RPC_URL}"));
+    }
+
+    #[test]
+    fn test_placeholders_outside_string_values_are_ignored() {
+        let raw = format!("# ${{UNSET_IN_COMMENT}} is not read\n{}", SAMPLE);
+        let config = AgentConfig::from_toml_str(&raw, env(&[("SOLANA_RPC_URL", "https://api.testnet.solana.com")])).unwrap();
+        assert_eq!(config.rpc.primary_endpoint, "https://api.testnet.solana.com");
+    }
+
+    #[test]
+    fn test_shipped_production_config_loads() {
+        let raw = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/config/production.toml")).unwrap();
+        let config = AgentConfig::from_toml_str(&raw, env(&[
+            ("SOLANA_RPC_URL", "https://api.mainnet-beta.solana.com"),
+            ("JUPITER_API_KEY", "test"),
+        ]))
+        .unwrap();
+        assert_eq!(config.rpc.primary_endpoint, "https://api.mainnet-beta.solana.com");
+    }
+
+    #[test]
+    fn test_validation_names_offending_field() {
+        let result = AgentConfig::from_toml_str(SAMPLE, env(&[
+            ("SOLANA_RPC_URL", "https://api.testnet.solana.com"),
+            ("CORAL_TRADING__EXIT_SLIPPAGE", "1.5"),
+        ]));
+        assert!(matches!(result, Err(CoralError::Config { field, .. }) if field == "trading.exit_slippage"));
+
+        let result = AgentConfig::from_toml_str(SAMPLE, env(&[
+            ("SOLANA_RPC_URL", "https://api.testnet.solana.com"),
+            ("CORAL_RISK__DAILY_LOSS_LIMIT", "0"),
+        ]));
+        assert!(matches!(result, Err(CoralError::Config { field, .. }) if field == "risk.daily_loss_limit"));
+    }
+
+    #[test]
+    fn test_parse_and_type_errors_name_the_key() {
+        let vars = env(&[("SOLANA_RPC_URL", "https://api.testnet.solana.com")]);
+
+        let raw = format!("{}\n[analysis.holders]\nhistory_depth = \"deep\"\n", SAMPLE);
+        let result = AgentConfig::from_toml_str(&raw, vars.clone());
+        assert!(matches!(result, Err(CoralError::Config { field, .. }) if field == "analysis.holders.history_depth"));
+
+        let result = AgentConfig::from_toml_str("[rpc]\ntimeout_ms = = 5\n", vars.clone());
+        assert!(matches!(result, Err(CoralError::Config { field, .. }) if field == "line 2"));
+
+        let result = AgentConfig::from_toml_str(SAMPLE, env(&[
+            ("SOLANA_RPC_URL", "https://api.testnet.solana.com"),
+            ("CORAL_RPC__TIMEOUT_MS", "soon"),
+        ]));
+        assert!(matches!(result, Err(CoralError::Config { field, .. }) if field == "rpc.timeout_ms"));
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        let vars = env(&[("SOLANA_RPC_URL", "https://api.testnet.solana.com")]);
+
+        let raw = format!("{}\n[risk]\ndaily_loss_limt = 1\n", SAMPLE);
+        let result = AgentConfig::from_toml_str(&raw, vars.clone());
+        assert!(matches!(
+            result,
+            Err(CoralError::Config { field, reason }) if field.starts_with("risk") && reason.contains("daily_loss_limt")
+        ));
+
+        let mut overridden = vars;
+        overridden.push(("CORAL_TRADING__EXIT_SLIPAGE".to_string(), "0.02".to_string()));
+        let result = AgentConfig::from_toml_str(SAMPLE, overridden);
+        assert!(matches!(
+            result,
+            Err(CoralError::Config { field, reason }) if field.starts_with("trading") && reason.contains("exit_slipage")
+        ));
+    }
+
+    #[test]
+    fn test_concentration_thresholds_are_validated() {
+        let raw = format!(
+            "{}\n[analysis.holders.concentration]\nnakamoto = {{ medium = 3, high = 10 }}\n",
+            SAMPLE
+        );
+        let vars = env(&[("SOLANA_RPC_URL", "https://api.testnet.solana.com")]);
+
+        let result = AgentConfig::from_toml_str(&raw, vars.clone());
+        assert!(matches!(result, Err(CoralError::Config { field, .. }) if field == "analysis.holders.concentration.nakamoto"));
+
+        let config = AgentConfig::from_toml_str(SAMPLE, vars).unwrap();
+        assert_eq!(config.analysis.holders.concentration.top_n, 10);
+    }
+}
@@ -0,0 +1,621 @@
+//! Pre-trade risk policy
+//!
+//! Every order passes through `RiskGate::check` before it reaches a trader. Entries
+//! (orders spending the quote token) are rejected or shrunk based on the token's most
+//! recent analysis, per-token and total exposure caps, and the daily loss limit.
+//! Exits back into the quote token only reduce risk and are always let through; so are
+//! exits of positions held against another quote token (see `check_exit`).
+//! Exposure and P&L are tracked in quote token base units.
+//!
+//! An approved entry reserves its amount against the caps until the caller settles it
+//! with `record_fill` or hands it back with `release`, so orders checked concurrently
+//! cannot together overshoot a cap.
+
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use thiserror::Error;
+use tokio::sync::RwLock;
+use crate::core::types::{AnalysisResult, RiskFactorKind, RiskLevel, TradeRecommendation, TradeResult};
+use crate::traders::TradeOrder;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RiskConfig {
+    /// Tokens rated above this level are not traded
+    pub max_risk_level: RiskLevel,
+    /// Tokens rated at this level or above (but within `max_risk_level`) trade at reduced size
+    pub reduce_at_risk_level: RiskLevel,
+    pub reduced_size_factor: f64,
+    /// Analyses older than this do not count as recent
+    pub max_analysis_age_secs: i64,
+    pub max_token_exposure: u64,
+    pub max_total_exposure: u64,
+    /// Realized loss within a UTC day at which new entries stop; must be above zero
+    pub daily_loss_limit: u64,
+    /// Orders shrunk below this size are rejected instead
+    pub min_order_size: u64,
+    /// Tokens whose latest analysis reports any of these factors are not traded
+    pub blocked_risk_factors: Vec<RiskFactorKind>,
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        Self {
+            max_risk_level: RiskLevel::High,
+            reduce_at_risk_level: RiskLevel::High,
+            reduced_size_factor: 0.5,
+            max_analysis_age_secs: 900,
+            max_token_exposure: 10_000_000_000,
+            max_total_exposure: 50_000_000_000,
+            daily_loss_limit: 5_000_000_000,
+            min_order_size: 1_000_000,
+            blocked_risk_factors: Vec::new(),
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RiskRejection {
+    #[error("no analysis of {token} within the last {max_age_secs}s")]
+    NoRecentAnalysis { token: Pubkey, max_age_secs: i64 },
+
+    #[error("latest analysis of {token} rates it {risk_level:?} and recommends {recommendation}")]
+    UnfavorableAnalysis {
+        token: Pubkey,
+        risk_level: RiskLevel,
+        recommendation: String,
+    },
+
+    #[error("latest analysis of {token} reports blocked risk factor {kind:?} ({severity:?})")]
+    BlockedRiskFactor {
+        token: Pubkey,
+        kind: RiskFactorKind,
+        severity: RiskLevel,
+    },
+
+    #[error("exposure to {token} would exceed {limit} (currently {current})")]
+    PositionLimitExceeded { token: Pubkey, limit: u64, current: u64 },
+
+    #[error("total exposure would exceed {limit} (currently {current})")]
+    TotalExposureExceeded { limit: u64, current: u64 },
+
+    #[error("order of {amount} after risk adjustments is below the minimum of {minimum}")]
+    OrderTooSmall { amount: u64, minimum: u64 },
+
+    #[error("daily loss limit of {limit} reached (realized loss {realized_loss})")]
+    DailyLossLimitReached { limit: u64, realized_loss: u64 },
+}
+
+impl RiskRejection {
+    /// 2005 for exposure caps, 2001 for orders shrunk too far, 2006 for other policy rejections
+    pub fn code(&self) -> u32 {
+        match self {
+            Self::PositionLimitExceeded { .. } | Self::TotalExposureExceeded { .. } => 2005,
+            Self::OrderTooSmall { .. } => 2001,
+            Self::NoRecentAnalysis { .. }
+            | Self::UnfavorableAnalysis { .. }
+            | Self::BlockedRiskFactor { .. }
+            | Self::DailyLossLimitReached { .. } => 2006,
+        }
+    }
+
+    pub fn details(&self) -> serde_json::Value {
+        match self {
+            Self::NoRecentAnalysis { token, max_age_secs } => json!({
+                "rule": "no_recent_analysis",
+                "token": token.to_string(),
+                "max_age_secs": max_age_secs,
+            }),
+            Self::UnfavorableAnalysis { token, risk_level, recommendation } => json!({
+                "rule": "unfavorable_analysis",
+                "token": token.to_string(),
+                "risk_level": risk_level,
+                "recommendation": recommendation,
+            }),
+            Self::BlockedRiskFactor { token, kind, severity } => json!({
+                "rule": "blocked_risk_factor",
+                "token": token.to_string(),
+                "kind": kind,
+                "severity": severity,
+            }),
+            Self::PositionLimitExceeded { token, limit, current } => json!({
+                "rule": "position_limit",
+                "token": token.to_string(),
+                "limit": limit,
+                "current": current,
+            }),
+            Self::TotalExposureExceeded { limit, current } => json!({
+                "rule": "total_exposure",
+                "limit": limit,
+                "current": current,
+            }),
+            Self::OrderTooSmall { amount, minimum } => json!({
+                "rule": "min_order_size",
+                "amount": amount,
+                "minimum": minimum,
+            }),
+            Self::DailyLossLimitReached { limit, realized_loss } => json!({
+                "rule": "daily_loss_limit",
+                "limit": limit,
+                "realized_loss": realized_loss,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Holding {
+    /// Quote units spent on the units still held
+    cost: u64,
+    units: u64,
+}
+
+#[derive(Debug)]
+struct ExposureBook {
+    holdings: HashMap<Pubkey, Holding>,
+    /// Quote units approved for entries that have not filled or failed yet
+    reserved: HashMap<Pubkey, u64>,
+    day: NaiveDate,
+    realized_pnl: i128,
+}
+
+impl ExposureBook {
+    fn exposure(&self, token: &Pubkey) -> u64 {
+        self.holdings.get(token).map_or(0, |h| h.cost) + self.reserved.get(token).copied().unwrap_or(0)
+    }
+
+    fn total(&self) -> u64 {
+        self.holdings.values().map(|h| h.cost).sum::<u64>() + self.reserved.values().sum::<u64>()
+    }
+
+    fn unreserve(&mut self, token: &Pubkey, amount: u64) {
+        if let Some(reserved) = self.reserved.get_mut(token) {
+            *reserved = reserved.saturating_sub(amount);
+            if *reserved == 0 {
+                self.reserved.remove(token);
+            }
+        }
+    }
+
+    /// Realized P&L resets at the start of every UTC day
+    fn roll_day(&mut self) {
+        let today = Utc::now().date_naive();
+        if today != self.day {
+            self.day = today;
+            self.realized_pnl = 0;
+        }
+    }
+}
+
+pub struct RiskGate {
+    config: RiskConfig,
+    quote_token: Pubkey,
+    latest_analyses: RwLock<HashMap<Pubkey, AnalysisResult>>,
+    book: RwLock<ExposureBook>,
+}
+
+impl RiskGate {
+    pub fn new(config: RiskConfig, quote_token: Pubkey) -> Self {
+        Self {
+            config,
+            quote_token,
+            latest_analyses: RwLock::new(HashMap::new()),
+            book: RwLock::new(ExposureBook {
+                holdings: HashMap::new(),
+                reserved: HashMap::new(),
+                day: Utc::now().date_naive(),
+                realized_pnl: 0,
+            }),
+        }
+    }
+
+    pub async fn record_analysis(&self, analysis: &AnalysisResult) {
+        self.latest_analyses
+            .write()
+            .await
+            .insert(analysis.token_address, analysis.clone());
+    }
+
+    /// Approves `order`, possibly with a smaller amount, or explains why it may not trade.
+    /// An approved entry stays reserved until `record_fill` or `release`.
+    pub async fn check(&self, mut order: TradeOrder) -> Result<TradeOrder, RiskRejection> {
+        if self.is_exit(&order) {
+            return Ok(order);
+        }
+        let token = order.to_token;
+
+        let mut book = self.book.write().await;
+        book.roll_day();
+        let realized_loss = u64::try_from(book.realized_pnl.min(0).unsigned_abs()).unwrap_or(u64::MAX);
+        if realized_loss >= self.config.daily_loss_limit {
+            return Err(RiskRejection::DailyLossLimitReached {
+                limit: self.config.daily_loss_limit,
+                realized_loss,
+            });
+        }
+
+        let mut allowed = order.amount;
+        let analysis = self.recent_analysis(&token).await?;
+        if matches!(analysis.recommendation, TradeRecommendation::Avoid { .. })
+            || analysis.risk_level > self.config.max_risk_level
+        {
+            return Err(RiskRejection::UnfavorableAnalysis {
+                token,
+                risk_level: analysis.risk_level,
+                recommendation: recommendation_name(&analysis.recommendation).to_string(),
+            });
+        }
+        if let Some(factor) = analysis
+            .risk_factors()
+            .find(|factor| self.config.blocked_risk_factors.contains(&factor.kind))
+        {
+            return Err(RiskRejection::BlockedRiskFactor {
+                token,
+                kind: factor.kind,
+                severity: factor.severity,
+            });
+        }
+        if analysis.risk_level >= self.config.reduce_at_risk_level {
+            allowed = (allowed as f64 * self.config.reduced_size_factor) as u64;
+        }
+        if let TradeRecommendation::Buy { suggested_amount, .. } = analysis.recommendation {
+            allowed = allowed.min(suggested_amount);
+        }
+
+        // Only spending the quote token adds measurable exposure
+        if order.from_token == self.quote_token {
+            let current = book.exposure(&token);
+            let token_room = self.config.max_token_exposure.saturating_sub(current);
+            let total = book.total();
+            let total_room = self.config.max_total_exposure.saturating_sub(total);
+
+            if allowed.min(token_room) < self.config.min_order_size {
+                return Err(RiskRejection::PositionLimitExceeded {
+                    token,
+                    limit: self.config.max_token_exposure,
+                    current,
+                });
+            }
+            if allowed.min(total_room) < self.config.min_order_size {
+                return Err(RiskRejection::TotalExposureExceeded {
+                    limit: self.config.max_total_exposure,
+                    current: total,
+                });
+            }
+            allowed = allowed.min(token_room).min(total_room);
+        }
+
+        if allowed < self.config.min_order_size {
+            return Err(RiskRejection::OrderTooSmall {
+                amount: allowed,
+                minimum: self.config.min_order_size,
+            });
+        }
+
+        if allowed < order.amount {
+            log::warn!(
+                "Risk gate reduced order for {} from {} to {}",
+                token,
+                order.amount,
+                allowed
+            );
+            order.minimum_received = order
+                .minimum_received
+                .map(|min| (min as u128 * allowed as u128 / order.amount as u128) as u64);
+            order.amount = allowed;
+        }
+
+        if self.reserves(&order) {
+            *book.reserved.entry(token).or_default() += order.amount;
+        }
+        Ok(order)
+    }
+
+    /// `check` for an order closing a position held against `quote_token`, which need not
+    /// be the token exposure is measured in. Selling into it is an exit and let through.
+    pub async fn check_exit(&self, order: TradeOrder, quote_token: &Pubkey) -> Result<TradeOrder, RiskRejection> {
+        if sells_into(&order, quote_token) {
+            return Ok(order);
+        }
+        self.check(order).await
+    }
+
+    /// Updates exposure and realized P&L once an approved order has filled, settling
+    /// its reservation
+    pub async fn record_fill(&self, order: &TradeOrder, result: &TradeResult) {
+        if self.reserves(order) {
+            self.book.write().await.unreserve(&order.to_token, order.amount);
+        }
+        self.apply_fill(order, result, true).await;
+    }
+
+    /// Hands back the reservation of an approved order that is known not to have filled
+    pub async fn release(&self, order: &TradeOrder) {
+        if self.reserves(order) {
+            self.book.write().await.unreserve(&order.to_token, order.amount);
+        }
+    }
+
+    /// Rebuilds exposure from a fill recorded by an earlier run. Only fills from the
+    /// current UTC day count towards the daily loss limit.
+    pub async fn replay_fill(&self, order: &TradeOrder, result: &TradeResult) {
+        let filled_today = chrono::DateTime::from_timestamp(result.timestamp, 0)
+            .map_or(false, |filled| filled.date_naive() == Utc::now().date_naive());
+        self.apply_fill(order, result, filled_today).await;
+    }
+
+    async fn apply_fill(&self, order: &TradeOrder, result: &TradeResult, count_pnl: bool) {
+        let mut book = self.book.write().await;
+        book.roll_day();
+
+        if self.is_exit(order) {
+            // Selling units that were never tracked changes nothing
+            let holding = match book.holdings.get_mut(&order.from_token) {
+                Some(holding) if holding.units > 0 => holding,
+                _ => return,
+            };
+            let sold = order.amount.min(holding.units);
+
+            let released = (holding.cost as u128 * sold as u128 / holding.units as u128) as u64;
+            // Only the part of the order that closes tracked units counts towards P&L
+            let proceeds = (result.output_amount as u128 * sold as u128 / order.amount.max(1) as u128) as u64;
+            holding.cost -= released;
+            holding.units -= sold;
+            if holding.units == 0 {
+                book.holdings.remove(&order.from_token);
+            }
+            if count_pnl {
+                book.realized_pnl += proceeds as i128 - released as i128;
+            }
+        } else if order.from_token == self.quote_token {
+            let holding = book.holdings.entry(order.to_token).or_default();
+            holding.cost += order.amount;
+            holding.units += result.output_amount;
+        }
+    }
+
+    fn is_exit(&self, order: &TradeOrder) -> bool {
+        sells_into(order, &self.quote_token)
+    }
+
+    /// Entries spending the quote token hold a reservation between check and fill
+    fn reserves(&self, order: &TradeOrder) -> bool {
+        order.from_token == self.quote_token && order.to_token != self.quote_token
+    }
+
+    async fn recent_analysis(&self, token: &Pubkey) -> Result<AnalysisResult, RiskRejection> {
+        let oldest_allowed = Utc::now().timestamp() - self.config.max_analysis_age_secs;
+
+        self.latest_analyses
+            .read()
+            .await
+            .get(token)
+            .filter(|analysis| analysis.analysis_timestamp >= oldest_allowed)
+            .cloned()
+            .ok_or(RiskRejection::NoRecentAnalysis {
+                token: *token,
+                max_age_secs: self.config.max_analysis_age_secs,
+            })
+    }
+}
+
+fn sells_into(order: &TradeOrder, quote_token: &Pubkey) -> bool {
+    order.to_token == *quote_token && order.from_token != *quote_token
+}
+
+fn recommendation_name(recommendation: &TradeRecommendation) -> &'static str {
+    match recommendation {
+        TradeRecommendation::Buy { .. } => "Buy",
+        TradeRecommendation::Sell { .. } => "Sell",
+        TradeRecommendation::Hold { .. } => "Hold",
+        TradeRecommendation::Avoid { .. } => "Avoid",
+        TradeRecommendation::Abstain => "Abstain",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::amount::Price;
+    use crate::core::types::{ConfirmationStatus, RiskFactor};
+    use crate::traders::RoutePreference;
+
+    fn buy_order(quote: Pubkey, token: Pubkey, amount: u64) -> TradeOrder {
+        TradeOrder {
+            from_token: quote,
+            to_token: token,
+            amount,
+            slippage_tolerance: 0.01,
+            minimum_received: None,
+            route_preference: RoutePreference::BestPrice,
+        }
+    }
+
+    fn analysis(token: Pubkey, risk_level: RiskLevel, recommendation: TradeRecommendation) -> AnalysisResult {
+        AnalysisResult::new(token, 0.8, risk_level, recommendation)
+    }
+
+    fn buy_recommendation(suggested_amount: u64) -> TradeRecommendation {
+        TradeRecommendation::Buy {
+            confidence: 0.8,
+            suggested_amount,
+            entry_price: Price::from_raw(1, 1).unwrap(),
+            stop_loss: Price::from_raw(5, 4).unwrap(),
+            take_profit: Price::from_raw(2, 3).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejects_without_recent_analysis_or_on_avoid() {
+        let quote = Pubkey::new_unique();
+        let token = Pubkey::new_unique();
+        let gate = RiskGate::new(RiskConfig::default(), quote);
+
+        let result = gate.check(buy_order(quote, token, 5_000_000)).await;
+        assert!(matches!(result, Err(RiskRejection::NoRecentAnalysis { .. })));
+
+        gate.record_analysis(&analysis(token, RiskLevel::Medium, TradeRecommendation::Avoid {
+            risk_factors: vec![],
+        }))
+        .await;
+        let result = gate.check(buy_order(quote, token, 5_000_000)).await;
+        assert!(matches!(result, Err(RiskRejection::UnfavorableAnalysis { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_blocked_risk_factor() {
+        let quote = Pubkey::new_unique();
+        let token = Pubkey::new_unique();
+        let config = RiskConfig {
+            blocked_risk_factors: vec![RiskFactorKind::WhaleDominance],
+            ..Default::default()
+        };
+        let gate = RiskGate::new(config, quote);
+        let findings = vec![RiskFactor::new(RiskFactorKind::WhaleDominance, RiskLevel::Medium)
+            .with_evidence("whale_share", "0.45")];
+        gate.record_analysis(
+            &analysis(token, RiskLevel::Low, buy_recommendation(u64::MAX))
+                .with_findings("wallet", 0.9, findings, Default::default()),
+        )
+        .await;
+
+        let result = gate.check(buy_order(quote, token, 5_000_000)).await;
+        assert!(matches!(
+            result,
+            Err(RiskRejection::BlockedRiskFactor { kind: RiskFactorKind::WhaleDominance, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_shrinks_to_exposure_cap_then_rejects() {
+        let quote = Pubkey::new_unique();
+        let token = Pubkey::new_unique();
+        let config = RiskConfig {
+            max_token_exposure: 10_000_000,
+            ..Default::default()
+        };
+        let gate = RiskGate::new(config, quote);
+        gate.record_analysis(&analysis(token, RiskLevel::Low, buy_recommendation(u64::MAX))).await;
+
+        let approved = gate.check(buy_order(quote, token, 25_000_000)).await.unwrap();
+        assert_eq!(approved.amount, 10_000_000);
+
+        gate.record_fill(&approved, &fill(&approved, 10_000_000)).await;
+        let result = gate.check(buy_order(quote, token, 5_000_000)).await;
+        assert!(matches!(result, Err(RiskRejection::PositionLimitExceeded { current: 10_000_000, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_daily_loss_limit_blocks_entries_but_not_exits() {
+        let quote = Pubkey::new_unique();
+        let token = Pubkey::new_unique();
+        let config = RiskConfig {
+            daily_loss_limit: 2_000_000,
+            ..Default::default()
+        };
+        let gate = RiskGate::new(config, quote);
+        gate.record_analysis(&analysis(token, RiskLevel::Low, buy_recommendation(u64::MAX))).await;
+
+        let entry = gate.check(buy_order(quote, token, 5_000_000)).await.unwrap();
+        gate.record_fill(&entry, &fill(&entry, 5_000_000)).await;
+
+        // Sell everything at half the entry price
+        let exit = TradeOrder {
+            from_token: token,
+            to_token: quote,
+            ..buy_order(quote, token, 5_000_000)
+        };
+        let exit = gate.check(exit).await.unwrap();
+        gate.record_fill(&exit, &fill(&exit, 2_500_000)).await;
+
+        let result = gate.check(buy_order(quote, token, 5_000_000)).await;
+        assert!(matches!(result, Err(RiskRejection::DailyLossLimitReached { realized_loss: 2_500_000, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_replayed_losses_from_earlier_days_do_not_count() {
+        let quote = Pubkey::new_unique();
+        let token = Pubkey::new_unique();
+        let config = RiskConfig {
+            daily_loss_limit: 2_000_000,
+            ..Default::default()
+        };
+        let gate = RiskGate::new(config, quote);
+        let two_days_ago = Utc::now().timestamp() - 2 * 86_400;
+
+        let entry = buy_order(quote, token, 5_000_000);
+        let exit = TradeOrder {
+            from_token: token,
+            to_token: quote,
+            ..buy_order(quote, token, 5_000_000)
+        };
+        for (order, output) in [(&entry, 5_000_000), (&exit, 2_500_000)] {
+            let mut result = fill(order, output);
+            result.timestamp = two_days_ago;
+            gate.replay_fill(order, &result).await;
+        }
+
+        gate.record_analysis(&analysis(token, RiskLevel::Low, buy_recommendation(u64::MAX))).await;
+        assert!(gate.check(buy_order(quote, token, 5_000_000)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_approved_entries_reserve_exposure_until_filled_or_released() {
+        let quote = Pubkey::new_unique();
+        let token = Pubkey::new_unique();
+        let config = RiskConfig {
+            max_token_exposure: 10_000_000,
+            ..Default::default()
+        };
+        let gate = RiskGate::new(config, quote);
+        gate.record_analysis(&analysis(token, RiskLevel::Low, buy_recommendation(u64::MAX))).await;
+
+        // Both approved before either fills; the second only gets what the first left
+        let first = gate.check(buy_order(quote, token, 6_000_000)).await.unwrap();
+        let second = gate.check(buy_order(quote, token, 6_000_000)).await.unwrap();
+        assert_eq!(second.amount, 4_000_000);
+        let result = gate.check(buy_order(quote, token, 6_000_000)).await;
+        assert!(matches!(result, Err(RiskRejection::PositionLimitExceeded { current: 10_000_000, .. })));
+
+        // Filling settles the reservation without counting it twice, failing hands it back
+        gate.record_fill(&first, &fill(&first, 6_000_000)).await;
+        gate.release(&second).await;
+        let third = gate.check(buy_order(quote, token, 6_000_000)).await.unwrap();
+        assert_eq!(third.amount, 4_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_exit_of_untracked_token_leaves_no_holding() {
+        let quote = Pubkey::new_unique();
+        let token = Pubkey::new_unique();
+        let gate = RiskGate::new(RiskConfig::default(), quote);
+
+        let exit = TradeOrder {
+            from_token: token,
+            to_token: quote,
+            ..buy_order(quote, token, 5_000_000)
+        };
+        gate.record_fill(&exit, &fill(&exit, 5_000_000)).await;
+        assert!(gate.book.read().await.holdings.is_empty());
+    }
+
+    fn fill(order: &TradeOrder, output_amount: u64) -> TradeResult {
+        TradeResult {
+            transaction_signature: "sig".to_string(),
+            amount: order.amount,
+            output_amount,
+            quoted_price: Price::ZERO,
+            executed_price: Price::ZERO,
+            slippage_bps: 0,
+            route: vec![],
+            slot: 1,
+            confirmation_status: ConfirmationStatus::Confirmed,
+            fee: 0,
+            priority_fee: 0,
+            timestamp: Utc::now().timestamp(),
+        }
+    }
+}
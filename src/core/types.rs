@@ -1,6 +1,13 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::json;
 use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
 use thiserror::Error;
+use crate::core::amount::{Price, TokenAmount};
+use crate::core::risk::RiskRejection;
+use crate::traders::RouteHop;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalysisResult {
@@ -9,9 +16,165 @@ pub struct AnalysisResult {
     pub risk_level: RiskLevel,
     pub recommendation: TradeRecommendation,
     pub analysis_timestamp: i64,
+    /// How much the verdict can be trusted, from 0.0 to 1.0
+    #[serde(default)]
+    pub confidence: f64,
+    /// One entry per analyzer that contributed to this result
+    #[serde(default)]
+    pub breakdown: Vec<AnalyzerBreakdown>,
+    /// Ordered trail of the steps that produced the verdict
+    #[serde(default)]
+    pub explanation: Vec<String>,
+    #[serde(default)]
+    pub analyzer_failures: Vec<AnalyzerFailure>,
+}
+
+impl AnalysisResult {
+    pub fn new(
+        token_address: Pubkey,
+        authenticity_score: f64,
+        risk_level: RiskLevel,
+        recommendation: TradeRecommendation,
+    ) -> Self {
+        Self {
+            token_address,
+            authenticity_score,
+            risk_level,
+            recommendation,
+            analysis_timestamp: chrono::Utc::now().timestamp(),
+            confidence: 0.0,
+            breakdown: Vec::new(),
+            explanation: Vec::new(),
+            analyzer_failures: Vec::new(),
+        }
+    }
+
+    /// Records what a single analyzer found behind its score
+    pub fn with_findings(
+        mut self,
+        analyzer: &str,
+        confidence: f64,
+        risk_factors: Vec<RiskFactor>,
+        metrics: BTreeMap<String, f64>,
+    ) -> Self {
+        self.confidence = confidence;
+        self.breakdown.push(AnalyzerBreakdown {
+            analyzer: analyzer.to_string(),
+            weight: 1.0,
+            score: self.authenticity_score,
+            risk_level: self.risk_level,
+            confidence,
+            risk_factors,
+            metrics,
+        });
+        self
+    }
+
+    /// Every risk factor behind this result: the analyzers' findings and any attached
+    /// to the recommendation itself
+    pub fn risk_factors(&self) -> impl Iterator<Item = &RiskFactor> {
+        let recommended: &[RiskFactor] = match &self.recommendation {
+            TradeRecommendation::Sell { risk_factors, .. } | TradeRecommendation::Avoid { risk_factors } => risk_factors,
+            _ => &[],
+        };
+        self.breakdown
+            .iter()
+            .flat_map(|entry| entry.risk_factors.iter())
+            .chain(recommended)
+    }
+}
+
+/// What one analyzer contributed to a result and the evidence behind it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyzerBreakdown {
+    pub analyzer: String,
+    pub weight: f64,
+    pub score: f64,
+    pub risk_level: RiskLevel,
+    pub confidence: f64,
+    pub risk_factors: Vec<RiskFactor>,
+    /// Raw measurements, e.g. `gini`, `liquidity_depth`, `commit_frequency`
+    pub metrics: BTreeMap<String, f64>,
+}
+
+/// Ranked triage view over a batch of analyses
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WatchlistSummary {
+    /// Lowest risk first, then highest score, then highest confidence
+    pub ranked: Vec<WatchlistEntry>,
+    pub failed: Vec<WatchlistFailure>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchlistEntry {
+    pub rank: usize,
+    pub token_address: Pubkey,
+    pub authenticity_score: f64,
+    pub risk_level: RiskLevel,
+    pub confidence: f64,
+    pub recommendation: TradeRecommendation,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchlistFailure {
+    pub token_address: Pubkey,
+    pub error: String,
+}
+
+impl WatchlistSummary {
+    pub fn from_results(results: impl IntoIterator<Item = (Pubkey, Result<AnalysisResult, CoralError>)>) -> Self {
+        let mut analyses = Vec::new();
+        let mut failed = Vec::new();
+
+        for (token_address, result) in results {
+            match result {
+                Ok(analysis) => analyses.push(analysis),
+                Err(e) => failed.push(WatchlistFailure {
+                    token_address,
+                    error: e.to_string(),
+                }),
+            }
+        }
+
+        analyses.sort_by(|a, b| {
+            a.risk_level
+                .cmp(&b.risk_level)
+                .then_with(|| b.authenticity_score.total_cmp(&a.authenticity_score))
+                .then_with(|| b.confidence.total_cmp(&a.confidence))
+        });
+
+        let ranked = analyses
+            .into_iter()
+            .enumerate()
+            .map(|(i, analysis)| WatchlistEntry {
+                rank: i + 1,
+                token_address: analysis.token_address,
+                authenticity_score: analysis.authenticity_score,
+                risk_level: analysis.risk_level,
+                confidence: analysis.confidence,
+                recommendation: analysis.recommendation,
+            })
+            .collect();
+
+        Self { ranked, failed }
+    }
+}
+
+/// An analyzer that did not contribute to a combined result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyzerFailure {
+    pub analyzer: String,
+    pub reason: AnalyzerFailureReason,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AnalyzerFailureReason {
+    TimedOut { after_ms: u64 },
+    Failed(String),
+}
+
+/// Ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum RiskLevel {
     Low,
     Medium,
@@ -23,49 +186,533 @@ pub enum RiskLevel {
 pub enum TradeRecommendation {
     Buy {
         confidence: f64,
+        /// Quote token base units to spend
         suggested_amount: u64,
-        entry_price: f64,
-        stop_loss: f64,
-        take_profit: f64,
+        entry_price: Price,
+        stop_loss: Price,
+        take_profit: Price,
     },
     Sell {
         confidence: f64,
         percentage: f64,
-        reason: String,
+        risk_factors: Vec<RiskFactor>,
     },
     Hold {
         duration: String,
-        reevaluation_price: f64,
+        reevaluation_price: Price,
     },
     Avoid {
-        reasons: Vec<String>,
-        risk_factors: Vec<String>,
+        risk_factors: Vec<RiskFactor>,
     },
+    /// No view either way, e.g. from an audit that found nothing ruling the token out.
+    /// Left out of the consensus vote.
+    Abstain,
 }
 
+/// A single finding behind a risk rating. `kind` is a stable identifier that policy
+/// rules, notifications and dashboards can key off; `evidence` holds the measurements
+/// that triggered it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskFactor {
+    pub kind: RiskFactorKind,
+    pub severity: RiskLevel,
+    #[serde(default)]
+    pub evidence: BTreeMap<String, String>,
+}
+
+impl RiskFactor {
+    pub fn new(kind: RiskFactorKind, severity: RiskLevel) -> Self {
+        Self {
+            kind,
+            severity,
+            evidence: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_evidence(mut self, key: &str, value: impl ToString) -> Self {
+        self.evidence.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+impl std::fmt::Display for RiskFactor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({:?})", self.kind.description(), self.severity)?;
+        if !self.evidence.is_empty() {
+            let evidence: Vec<String> = self.evidence.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+            write!(f, ": {}", evidence.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskFactorKind {
+    // Authenticity
+    ImitatesExistingProject,
+    UnverifiedContract,
+    NoVerifiedOfficialChannels,
+    // Holders
+    HolderConcentration,
+    WhaleDominance,
+    NewWalletInflux,
+    SuspiciousFunding,
+    BundledLaunch,
+    // Developers
+    NoActiveDevelopers,
+    LowCommitActivity,
+    UnverifiedDevelopers,
+    // Liquidity
+    NewLiquidityPool,
+    ConcentratedLiquidity,
+    ImpermanentLossRisk,
+    UnlockedLiquidity,
+    // Mint
+    MintAuthorityActive,
+    FreezeAuthorityActive,
+    SupplyIncreased,
+    MutableMetadata,
+    TransferFee,
+    PermanentDelegate,
+    TransferHook,
+    NonTransferable,
+    FrozenByDefault,
+    ConfidentialTransfers,
+}
+
+impl RiskFactorKind {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::ImitatesExistingProject => "Resembles existing projects",
+            Self::UnverifiedContract => "Contract not verified",
+            Self::NoVerifiedOfficialChannels => "No verified official channels",
+            Self::HolderConcentration => "Supply concentrated among few holders",
+            Self::WhaleDominance => "Whales hold a large share of the supply",
+            Self::NewWalletInflux => "Many holders are new wallets",
+            Self::SuspiciousFunding => "Suspicious funding pattern across holders",
+            Self::BundledLaunch => "Holders bought in the same slot at launch",
+            Self::NoActiveDevelopers => "No active developers",
+            Self::LowCommitActivity => "Low commit activity",
+            Self::UnverifiedDevelopers => "No verified developer identities",
+            Self::NewLiquidityPool => "Liquidity pools are less than a day old",
+            Self::ConcentratedLiquidity => "Liquidity concentrated in a single pool",
+            Self::ImpermanentLossRisk => "High impermanent loss risk",
+            Self::UnlockedLiquidity => "LP tokens can be withdrawn at will",
+            Self::MintAuthorityActive => "Mint authority can still create tokens",
+            Self::FreezeAuthorityActive => "Freeze authority can still freeze holders",
+            Self::SupplyIncreased => "Supply has grown since the token was first seen",
+            Self::MutableMetadata => "Name, symbol and URI can still be changed",
+            Self::TransferFee => "Transfers are charged a fee",
+            Self::PermanentDelegate => "A permanent delegate can move any holder's tokens",
+            Self::TransferHook => "A program can approve or refuse every transfer",
+            Self::NonTransferable => "Tokens cannot be transferred",
+            Self::FrozenByDefault => "New token accounts start frozen",
+            Self::ConfidentialTransfers => "Balances and transfers can be hidden",
+        }
+    }
+}
+
+/// Underlying error kept as the `source` of a `CoralError`
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Every error carries a stable numeric code (see `code`), grouped by range:
+/// 1000-1999 system, 2000-2999 trading, 3000-3999 analysis.
 #[derive(Error, Debug)]
 pub enum CoralError {
-    #[error("Analysis failed: {0}")]
-    AnalysisFailed(String),
+    #[error("System initialization failed: {message}")]
+    Initialization {
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    #[error("Invalid configuration: {field}: {reason}")]
+    Config { field: String, reason: String },
+
+    #[error("Database error: {message}")]
+    Database {
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    #[error("Network error: {message}")]
+    Network {
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
 
-    #[error("Trading execution failed: {0}")]
-    TradingFailed(String),
+    #[error("Service unavailable: {service}: {reason}")]
+    ServiceUnavailable { service: String, reason: String },
 
-    #[error("Network error: {0}")]
-    NetworkError(String),
+    #[error("Authentication failed for {service}: {reason}")]
+    Authentication { service: String, reason: String },
 
-    #[error("Invalid configuration: {0}")]
-    ConfigError(String),
+    #[error("Insufficient balance: {required} required, {available} available")]
+    InsufficientBalance { required: u64, available: u64 },
 
-    #[error("Authentication error: {0}")]
-    AuthenticationError(String),
+    #[error("Invalid order size {amount}: {reason}")]
+    InvalidOrderSize { amount: u64, reason: String },
+
+    #[error("Invalid price: {reason}")]
+    InvalidPrice { reason: String },
+
+    #[error("Market not found: {market}")]
+    MarketNotFound { market: String },
+
+    #[error("Order not found: {signature}")]
+    OrderNotFound { signature: String },
+
+    #[error("Order rejected by risk policy: {0}")]
+    RiskRejected(#[from] RiskRejection),
+
+    #[error("Trade execution failed: {message}")]
+    ExecutionFailed {
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    #[error("Token not found: {query}: {reason}")]
+    TokenNotFound { query: String, reason: String },
+
+    #[error("Position not found: {token}")]
+    PositionNotFound { token: Pubkey },
+
+    #[error("Analysis of {token} failed: {reason}")]
+    AnalysisFailed { token: Pubkey, reason: String },
+}
+
+impl CoralError {
+    pub fn config(field: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self::Config {
+            field: field.into(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn network(message: impl Into<String>, source: impl Into<BoxError>) -> Self {
+        Self::Network {
+            message: message.into(),
+            source: Some(source.into()),
+        }
+    }
+
+    pub fn database(message: impl Into<String>, source: impl Into<BoxError>) -> Self {
+        Self::Database {
+            message: message.into(),
+            source: Some(source.into()),
+        }
+    }
+
+    pub fn execution(message: impl Into<String>) -> Self {
+        Self::ExecutionFailed {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Numeric code as documented in the README's error reference
+    pub fn code(&self) -> u32 {
+        match self {
+            Self::Initialization { .. } => 1000,
+            Self::Config { .. } => 1001,
+            Self::Database { .. } => 1002,
+            Self::Network { .. } => 1004,
+            Self::ServiceUnavailable { .. } => 1005,
+            Self::Authentication { .. } => 1006,
+            Self::InsufficientBalance { .. } => 2000,
+            Self::InvalidOrderSize { .. } => 2001,
+            Self::InvalidPrice { .. } => 2002,
+            Self::MarketNotFound { .. } => 2003,
+            Self::OrderNotFound { .. } => 2004,
+            Self::RiskRejected(rejection) => rejection.code(),
+            Self::ExecutionFailed { .. } => 2007,
+            Self::TokenNotFound { .. } => 2008,
+            Self::PositionNotFound { .. } => 2100,
+            Self::AnalysisFailed { .. } => 3000,
+        }
+    }
+
+    /// Whether the same call may succeed if attempted again unchanged. Failed
+    /// executions are not retryable: the transaction may still have landed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::Database { .. }
+                | Self::Network { .. }
+                | Self::ServiceUnavailable { .. }
+                | Self::InvalidPrice { .. }
+                | Self::AnalysisFailed { .. }
+        )
+    }
+
+    /// Structured fields of the error, plus the source chain when there is one
+    pub fn details(&self) -> serde_json::Value {
+        let mut details = match self {
+            Self::Config { field, reason } => json!({ "field": field, "reason": reason }),
+            Self::ServiceUnavailable { service, reason } | Self::Authentication { service, reason } => {
+                json!({ "service": service, "reason": reason })
+            }
+            Self::InsufficientBalance { required, available } => {
+                json!({ "required": required, "available": available })
+            }
+            Self::InvalidOrderSize { amount, reason } => json!({ "amount": amount, "reason": reason }),
+            Self::InvalidPrice { reason } => json!({ "reason": reason }),
+            Self::MarketNotFound { market } => json!({ "market": market }),
+            Self::OrderNotFound { signature } => json!({ "signature": signature }),
+            Self::RiskRejected(rejection) => rejection.details(),
+            Self::TokenNotFound { query, reason } => json!({ "query": query, "reason": reason }),
+            Self::PositionNotFound { token } => json!({ "token": token.to_string() }),
+            Self::AnalysisFailed { token, reason } => json!({ "token": token.to_string(), "reason": reason }),
+            Self::Initialization { .. }
+            | Self::Database { .. }
+            | Self::Network { .. }
+            | Self::ExecutionFailed { .. } => json!({}),
+        };
+
+        let mut causes = Vec::new();
+        let mut source = std::error::Error::source(self);
+        while let Some(cause) = source {
+            causes.push(cause.to_string());
+            source = cause.source();
+        }
+        if !causes.is_empty() {
+            details["caused_by"] = json!(causes);
+        }
+
+        details
+    }
+}
+
+/// Serializes as `{"error": {"code", "message", "details"}}`
+impl Serialize for CoralError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        json!({
+            "error": {
+                "code": self.code(),
+                "message": self.to_string(),
+                "details": self.details(),
+            }
+        })
+        .serialize(serializer)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeResult {
     pub transaction_signature: String,
-    pub executed_price: f64,
+    /// Input amount in base units of the order's `from_token`
     pub amount: u64,
+    /// Output received, in base units of the order's `to_token`; 0 when the fill could
+    /// not be read back
+    pub output_amount: u64,
+    /// Prices are quote token units per unit of the traded token
+    pub quoted_price: Price,
+    pub executed_price: Price,
+    /// Shortfall of the received output against the quote; negative when the fill beat it
+    pub slippage_bps: i64,
+    pub route: Vec<RouteHop>,
+    /// 0 when the fill could not be read back
+    pub slot: u64,
+    pub confirmation_status: ConfirmationStatus,
+    /// Total network fee in lamports, including `priority_fee`
     pub fee: u64,
+    pub priority_fee: u64,
     pub timestamp: i64,
 }
+
+impl TradeResult {
+    /// Whether the output, prices and fees were read from the landed transaction. Until
+    /// they are, the trade must not count towards exposure or P&L.
+    pub fn fill_known(&self) -> bool {
+        self.confirmation_status != ConfirmationStatus::Unknown
+    }
+
+    pub fn slippage_bps(expected_output: u64, output_amount: u64) -> i64 {
+        if expected_output == 0 {
+            return 0;
+        }
+        let shortfall = expected_output as i128 - output_amount as i128;
+        (shortfall * 10_000 / expected_output as i128) as i64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConfirmationStatus {
+    Processed,
+    Confirmed,
+    Finalized,
+    /// The transaction was sent and confirmed, but its fill could not be read back
+    Unknown,
+}
+
+/// Identifies one position across restarts, so positions in the same token stay apart
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PositionId(pub String);
+
+impl PositionId {
+    pub fn generate(token_address: &Pubkey) -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(format!(
+            "{}-{}-{}-{}",
+            token_address,
+            chrono::Utc::now().timestamp_millis(),
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ))
+    }
+}
+
+impl fmt::Display for PositionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Position {
+    /// Empty for positions recorded before positions had ids
+    #[serde(default)]
+    pub id: PositionId,
+    pub token_address: Pubkey,
+    pub quote_token: Pubkey,
+    pub size: TokenAmount,
+    pub entry_price: Price,
+    pub stop_loss: Price,
+    pub take_profit: Price,
+    pub opened_at: i64,
+}
+
+impl Position {
+    /// Opens a position from a `Buy` recommendation; other recommendations yield `None`.
+    pub fn from_recommendation(
+        token_address: Pubkey,
+        quote_token: Pubkey,
+        size: TokenAmount,
+        recommendation: &TradeRecommendation,
+    ) -> Option<Self> {
+        match recommendation {
+            TradeRecommendation::Buy {
+                entry_price,
+                stop_loss,
+                take_profit,
+                ..
+            } => Some(Self {
+                id: PositionId::generate(&token_address),
+                token_address,
+                quote_token,
+                size,
+                entry_price: *entry_price,
+                stop_loss: *stop_loss,
+                take_profit: *take_profit,
+                opened_at: chrono::Utc::now().timestamp(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn exit_trigger(&self, current_price: Price) -> Option<ExitTrigger> {
+        if current_price <= self.stop_loss {
+            Some(ExitTrigger::StopLoss)
+        } else if current_price >= self.take_profit {
+            Some(ExitTrigger::TakeProfit)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExitTrigger {
+    StopLoss,
+    TakeProfit,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_serializes_to_documented_shape() {
+        let error = CoralError::config("rpc.primary_endpoint", "must be an http(s) URL");
+        let value = serde_json::to_value(&error).unwrap();
+
+        assert_eq!(value["error"]["code"], 1001);
+        assert_eq!(value["error"]["details"]["field"], "rpc.primary_endpoint");
+        assert_eq!(value["error"]["details"]["reason"], "must be an http(s) URL");
+        assert!(!error.is_retryable());
+    }
+
+    #[test]
+    fn test_network_error_keeps_source_and_is_retryable() {
+        let io = std::io::Error::new(std::io::ErrorKind::TimedOut, "connection timed out");
+        let error = CoralError::network("get_account failed", io);
+
+        assert_eq!(error.code(), 1004);
+        assert!(error.is_retryable());
+        assert_eq!(std::error::Error::source(&error).unwrap().to_string(), "connection timed out");
+        assert_eq!(error.details()["caused_by"][0], "connection timed out");
+    }
+
+    #[test]
+    fn test_watchlist_ranks_by_risk_then_score_then_confidence() {
+        let analysis = |risk, score, confidence| {
+            let mut analysis = AnalysisResult::new(Pubkey::new_unique(), score, risk, TradeRecommendation::Avoid {
+                risk_factors: vec![],
+            });
+            analysis.confidence = confidence;
+            analysis
+        };
+        let risky = analysis(RiskLevel::High, 0.95, 0.9);
+        let strong = analysis(RiskLevel::Low, 0.9, 0.5);
+        let sure = analysis(RiskLevel::Low, 0.6, 0.9);
+        let unsure = analysis(RiskLevel::Low, 0.6, 0.3);
+        let broken = Pubkey::new_unique();
+
+        let summary = WatchlistSummary::from_results(vec![
+            (unsure.token_address, Ok(unsure.clone())),
+            (risky.token_address, Ok(risky.clone())),
+            (broken, Err(CoralError::MarketNotFound { market: "none".to_string() })),
+            (sure.token_address, Ok(sure.clone())),
+            (strong.token_address, Ok(strong.clone())),
+        ]);
+
+        let order: Vec<(usize, Pubkey)> = summary.ranked.iter().map(|entry| (entry.rank, entry.token_address)).collect();
+        assert_eq!(order, vec![
+            (1, strong.token_address),
+            (2, sure.token_address),
+            (3, unsure.token_address),
+            (4, risky.token_address),
+        ]);
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.failed[0].token_address, broken);
+    }
+
+    #[test]
+    fn test_exit_trigger_at_and_beyond_the_thresholds() {
+        let token = Pubkey::new_unique();
+        let position = Position {
+            id: PositionId::generate(&token),
+            token_address: token,
+            quote_token: Pubkey::new_unique(),
+            size: TokenAmount::new(1_000_000, 6),
+            entry_price: Price::from_raw(100, 100).unwrap(),
+            stop_loss: Price::from_raw(100, 90).unwrap(),
+            take_profit: Price::from_raw(100, 150).unwrap(),
+            opened_at: 0,
+        };
+        let price = |quote| Price::from_raw(100, quote).unwrap();
+
+        assert_eq!(position.exit_trigger(price(100)), None);
+        assert_eq!(position.exit_trigger(price(91)), None);
+        assert_eq!(position.exit_trigger(price(90)), Some(ExitTrigger::StopLoss));
+        assert_eq!(position.exit_trigger(price(10)), Some(ExitTrigger::StopLoss));
+        assert_eq!(position.exit_trigger(price(149)), None);
+        assert_eq!(position.exit_trigger(price(150)), Some(ExitTrigger::TakeProfit));
+        assert_eq!(position.exit_trigger(price(400)), Some(ExitTrigger::TakeProfit));
+    }
+}
@@ -1,11 +1,9 @@
 use coral::{
-    core::{CoralAgent, types::*},
+    core::{CoralAgent, config::AgentConfig, types::*},
     utils::{CoralLogger, MetricsCollector},
 };
 use log::Level;
 use std::sync::Arc;
-use std::str::FromStr;
-use solana_sdk::pubkey::Pubkey;
 
 #[tokio::main]
 async fn main() -> Result<(), CoralError> {
@@ -15,19 +13,33 @@ async fn main() -> Result<(), CoralError> {
     // Initialize metrics collector
     let metrics = Arc::new(MetricsCollector::new());
 
+    // Load and validate configuration
+    let config_path = std::env::var("CORAL_CONFIG")
+        .unwrap_or_else(|_| "config/production.toml".to_string());
+    let config = AgentConfig::load(&config_path)?;
+
     // Create Coral agent
-    let agent = CoralAgent::new(
-        std::env::var("SOLANA_RPC_URL")
-            .unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string()),
-        Some(metrics.clone()),
+    let agent = Arc::new(CoralAgent::new(config, Some(metrics.clone()))?);
+
+    // Settle orders a previous run left in flight and pick its open positions back up
+    let recovery = agent.recover().await?;
+    log::info!(
+        "Recovered {} analyses, {} trades, {} reconciled orders, {} open positions",
+        recovery.restored_analyses,
+        recovery.replayed_trades,
+        recovery.reconciled.len(),
+        recovery.open_positions.len()
     );
+    if !recovery.unresolved.is_empty() {
+        log::warn!("Orders needing manual review: {:?}", recovery.unresolved);
+    }
+    let _monitors = agent.resume_monitoring(recovery.open_positions);
 
-    // Example token address (USDC on Solana mainnet)
-    let token_address = Pubkey::from_str("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v")
-        .expect("Failed to parse token address");
+    // Example token, by symbol or mint address
+    let token_address = agent.tokens().resolve("USDC")?;
 
     // Analyze token
-    log::info!("Starting analysis for token: {}", token_address);
+    log::info!("Starting analysis for token: {} ({})", agent.tokens().label(&token_address), token_address);
     let analysis = agent.analyze_token(&token_address).await?;
 
     // Print analysis results
@@ -0,0 +1,329 @@
+//! Persistent, append-only history of analyses, orders, trades and position lifecycles
+//!
+//! Records are never updated or deleted; state such as "is this position open" or "did
+//! this order land" is derived by replaying events. Every record carries the mint(s) it
+//! concerns and a unix timestamp so history can be queried by token and by time range.
+
+pub mod sqlite;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use crate::core::amount::Price;
+use crate::core::types::{AnalysisResult, CoralError, ExitTrigger, Position, PositionId, TradeResult};
+use crate::traders::TradeOrder;
+
+pub use sqlite::SqliteStore;
+
+/// Identifier assigned to an order when it is recorded
+pub type OrderId = i64;
+
+#[async_trait]
+pub trait Store: Send + Sync {
+    async fn record_analysis(&self, analysis: &AnalysisResult) -> Result<(), CoralError>;
+
+    /// Records an order approved for execution and returns the id its fill is recorded under
+    async fn record_order(&self, order: &TradeOrder) -> Result<OrderId, CoralError>;
+
+    async fn record_trade(&self, order_id: OrderId, result: &TradeResult) -> Result<(), CoralError>;
+
+    async fn record_position_event(&self, event: &PositionEvent) -> Result<(), CoralError>;
+
+    async fn record_order_event(&self, event: &OrderEvent) -> Result<(), CoralError>;
+
+    async fn record_holder_snapshot(&self, snapshot: &HolderSnapshot) -> Result<(), CoralError>;
+
+    async fn analyses(&self, query: &StoreQuery) -> Result<Vec<AnalysisResult>, CoralError>;
+
+    async fn orders(&self, query: &StoreQuery) -> Result<Vec<StoredOrder>, CoralError>;
+
+    async fn trades(&self, query: &StoreQuery) -> Result<Vec<StoredTrade>, CoralError>;
+
+    async fn position_events(&self, query: &StoreQuery) -> Result<Vec<PositionEvent>, CoralError>;
+
+    async fn order_events(&self, query: &StoreQuery) -> Result<Vec<OrderEvent>, CoralError>;
+
+    async fn holder_snapshots(&self, query: &StoreQuery) -> Result<Vec<HolderSnapshot>, CoralError>;
+
+    /// Supply of `mint` when it was first seen. A mint seen for the first time is
+    /// recorded with `supply`, which is then returned.
+    async fn first_supply(&self, mint: &Pubkey, supply: u64) -> Result<u64, CoralError>;
+
+    /// Most recent snapshot of `mint`'s holders
+    async fn latest_holder_snapshot(&self, mint: &Pubkey) -> Result<Option<HolderSnapshot>, CoralError>;
+
+    /// Orders with neither a recorded trade nor a terminal event: their outcome is
+    /// unknown, typically because the process stopped while they were in flight
+    async fn pending_orders(&self) -> Result<Vec<StoredOrder>, CoralError>;
+}
+
+/// Filter shared by every history query. Orders and trades match a mint on either side
+/// of the swap. Time bounds are inclusive unix timestamps; results come oldest first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StoreQuery {
+    pub mint: Option<Pubkey>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub limit: Option<usize>,
+}
+
+impl StoreQuery {
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    pub fn mint(mut self, mint: Pubkey) -> Self {
+        self.mint = Some(mint);
+        self
+    }
+
+    pub fn between(mut self, from: i64, to: i64) -> Self {
+        self.from = Some(from);
+        self.to = Some(to);
+        self
+    }
+
+    pub fn since(mut self, from: i64) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredOrder {
+    pub id: OrderId,
+    pub recorded_at: i64,
+    pub order: TradeOrder,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredTrade {
+    pub order_id: OrderId,
+    pub result: TradeResult,
+}
+
+/// One step in a position's life, from opening to close
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionEvent {
+    pub token_address: Pubkey,
+    /// Empty for events recorded before positions had ids
+    #[serde(default)]
+    pub position_id: PositionId,
+    pub timestamp: i64,
+    pub kind: PositionEventKind,
+}
+
+impl PositionEvent {
+    pub fn now(position: &Position, kind: PositionEventKind) -> Self {
+        Self {
+            token_address: position.token_address,
+            position_id: position.id.clone(),
+            timestamp: chrono::Utc::now().timestamp(),
+            kind,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PositionEventKind {
+    Opened { position: Position },
+    ExitTriggered { trigger: ExitTrigger, price: Price },
+    Closed { transaction_signature: String },
+}
+
+/// Positions opened and not yet closed, in the order they were opened. Events recorded
+/// before positions had ids fall back to one position per mint.
+pub fn open_positions(events: &[PositionEvent]) -> Vec<Position> {
+    let mut open: HashMap<(Pubkey, PositionId), Position> = HashMap::new();
+    for event in events {
+        let key = (event.token_address, event.position_id.clone());
+        match &event.kind {
+            PositionEventKind::Opened { position } => {
+                open.insert(key, position.clone());
+            }
+            PositionEventKind::Closed { .. } => {
+                open.remove(&key);
+            }
+            PositionEventKind::ExitTriggered { .. } => {}
+        }
+    }
+
+    let mut positions: Vec<Position> = open.into_values().collect();
+    positions.sort_by_key(|position| position.opened_at);
+    positions
+}
+
+/// One step in an order's life after it was recorded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderEvent {
+    pub order_id: OrderId,
+    pub timestamp: i64,
+    pub kind: OrderEventKind,
+}
+
+impl OrderEvent {
+    pub fn now(order_id: OrderId, kind: OrderEventKind) -> Self {
+        Self {
+            order_id,
+            timestamp: chrono::Utc::now().timestamp(),
+            kind,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OrderEventKind {
+    /// Journaled right before the order is handed to a trader
+    Submitted { balances: Option<BalanceSnapshot> },
+    /// Landed on-chain, but what it filled could not be read back yet
+    Sent { transaction_signature: String },
+    /// Rejected before anything was sent on-chain
+    Failed { reason: String },
+    /// Outcome of an in-flight order established after a restart
+    Reconciled { outcome: Reconciliation },
+}
+
+impl OrderEventKind {
+    /// Whether the order's outcome is settled after this event
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, Self::Submitted { .. } | Self::Sent { .. })
+    }
+}
+
+/// The wallet's balances of both sides of an order just before submission
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BalanceSnapshot {
+    pub from_balance: u64,
+    pub to_balance: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Reconciliation {
+    /// A confirmed transaction from the wallet delivered the order's output
+    Filled { transaction_signature: String, output_amount: u64 },
+    /// No matching transaction and the input balance was never spent
+    NotExecuted,
+}
+
+/// A mint's holder distribution at one point in time. Only the largest holders are kept;
+/// shares are relative to `total_held`, the supply held by all counted holders.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HolderSnapshot {
+    pub mint: Pubkey,
+    pub timestamp: i64,
+    pub holder_count: usize,
+    pub total_held: u64,
+    /// Largest first
+    pub top_holders: Vec<HolderBalance>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HolderBalance {
+    pub owner: Pubkey,
+    pub balance: u64,
+}
+
+impl HolderSnapshot {
+    /// Snapshot taken now from every holder's balance, keeping the `tracked` largest
+    pub fn now(mint: Pubkey, mut balances: Vec<(Pubkey, u64)>, tracked: usize) -> Self {
+        balances.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Self {
+            mint,
+            timestamp: chrono::Utc::now().timestamp(),
+            holder_count: balances.len(),
+            total_held: balances.iter().map(|(_, balance)| balance).sum(),
+            top_holders: balances
+                .into_iter()
+                .take(tracked)
+                .map(|(owner, balance)| HolderBalance { owner, balance })
+                .collect(),
+        }
+    }
+
+    pub fn share(&self, balance: u64) -> f64 {
+        if self.total_held == 0 {
+            return 0.0;
+        }
+        balance as f64 / self.total_held as f64
+    }
+
+    /// Combined share of the `n` largest holders
+    pub fn top_share(&self, n: usize) -> f64 {
+        self.share(self.top_holders.iter().take(n).map(|holder| holder.balance).sum())
+    }
+
+    pub fn balance_of(&self, owner: &Pubkey) -> Option<u64> {
+        self.top_holders.iter().find(|holder| holder.owner == *owner).map(|holder| holder.balance)
+    }
+}
+
+/// What `CoralAgent::recover` restored from the previous run
+#[derive(Debug, Clone, Default)]
+pub struct RecoveryReport {
+    /// Analyses recent enough to count for the risk gate again
+    pub restored_analyses: usize,
+    /// Recorded fills replayed into the risk gate's exposure
+    pub replayed_trades: usize,
+    /// In-flight orders whose outcome was settled from the chain
+    pub reconciled: Vec<(OrderId, Reconciliation)>,
+    /// In-flight orders the chain could not settle; these need manual review
+    pub unresolved: Vec<OrderId>,
+    pub open_positions: Vec<Position>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::amount::TokenAmount;
+
+    fn opened(token: Pubkey, opened_at: i64) -> PositionEvent {
+        let id = PositionId::generate(&token);
+        PositionEvent {
+            token_address: token,
+            position_id: id.clone(),
+            timestamp: opened_at,
+            kind: PositionEventKind::Opened {
+                position: Position {
+                    id,
+                    token_address: token,
+                    quote_token: Pubkey::new_unique(),
+                    size: TokenAmount::new(1_000_000, 6),
+                    entry_price: Price::from_raw(1, 1).unwrap(),
+                    stop_loss: Price::from_raw(10, 9).unwrap(),
+                    take_profit: Price::from_raw(10, 12).unwrap(),
+                    opened_at,
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn test_open_positions_replays_lifecycle() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let first_a = opened(a, 100);
+        let events = vec![
+            first_a.clone(),
+            opened(b, 200),
+            opened(a, 250),
+            PositionEvent {
+                token_address: a,
+                position_id: first_a.position_id.clone(),
+                timestamp: 300,
+                kind: PositionEventKind::Closed {
+                    transaction_signature: "sig".to_string(),
+                },
+            },
+            opened(c, 150),
+        ];
+
+        // The second position in `a` stays open after the first one closes
+        let open: Vec<(Pubkey, i64)> = open_positions(&events).iter().map(|p| (p.token_address, p.opened_at)).collect();
+        assert_eq!(open, vec![(c, 150), (b, 200), (a, 250)]);
+    }
+}
@@ -0,0 +1,663 @@
+//! SQLite-backed `Store`
+//!
+//! Each record is kept as its JSON encoding next to the columns it is queried by.
+//! Triggers reject UPDATE and DELETE so history stays append-only. The schema version
+//! lives in `PRAGMA user_version`; `open` applies any pending `MIGRATIONS` in order.
+//! Queries run on tokio's blocking pool so a slow disk never stalls the runtime.
+
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{de::DeserializeOwned, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use crate::core::types::{AnalysisResult, CoralError, TradeResult};
+use crate::traders::TradeOrder;
+use super::{HolderSnapshot, OrderEvent, OrderId, PositionEvent, Store, StoreQuery, StoredOrder, StoredTrade};
+
+/// Schema changes, applied in order. Append new steps; never edit a released one.
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    "
+    CREATE TABLE analyses (
+        id INTEGER PRIMARY KEY,
+        mint TEXT NOT NULL,
+        recorded_at INTEGER NOT NULL,
+        payload TEXT NOT NULL
+    );
+    CREATE INDEX analyses_mint_time ON analyses (mint, recorded_at);
+    CREATE INDEX analyses_time ON analyses (recorded_at);
+
+    CREATE TABLE orders (
+        id INTEGER PRIMARY KEY,
+        from_mint TEXT NOT NULL,
+        to_mint TEXT NOT NULL,
+        recorded_at INTEGER NOT NULL,
+        payload TEXT NOT NULL
+    );
+    CREATE INDEX orders_from_time ON orders (from_mint, recorded_at);
+    CREATE INDEX orders_to_time ON orders (to_mint, recorded_at);
+    CREATE INDEX orders_time ON orders (recorded_at);
+
+    CREATE TABLE trades (
+        id INTEGER PRIMARY KEY,
+        order_id INTEGER NOT NULL REFERENCES orders (id),
+        from_mint TEXT NOT NULL,
+        to_mint TEXT NOT NULL,
+        signature TEXT NOT NULL,
+        recorded_at INTEGER NOT NULL,
+        payload TEXT NOT NULL
+    );
+    CREATE INDEX trades_order ON trades (order_id);
+    CREATE INDEX trades_from_time ON trades (from_mint, recorded_at);
+    CREATE INDEX trades_to_time ON trades (to_mint, recorded_at);
+    CREATE INDEX trades_time ON trades (recorded_at);
+
+    CREATE TABLE position_events (
+        id INTEGER PRIMARY KEY,
+        mint TEXT NOT NULL,
+        recorded_at INTEGER NOT NULL,
+        payload TEXT NOT NULL
+    );
+    CREATE INDEX position_events_mint_time ON position_events (mint, recorded_at);
+    CREATE INDEX position_events_time ON position_events (recorded_at);
+
+    CREATE TRIGGER analyses_no_update BEFORE UPDATE ON analyses
+        BEGIN SELECT RAISE(ABORT, 'analyses are append-only'); END;
+    CREATE TRIGGER analyses_no_delete BEFORE DELETE ON analyses
+        BEGIN SELECT RAISE(ABORT, 'analyses are append-only'); END;
+    CREATE TRIGGER orders_no_update BEFORE UPDATE ON orders
+        BEGIN SELECT RAISE(ABORT, 'orders are append-only'); END;
+    CREATE TRIGGER orders_no_delete BEFORE DELETE ON orders
+        BEGIN SELECT RAISE(ABORT, 'orders are append-only'); END;
+    CREATE TRIGGER trades_no_update BEFORE UPDATE ON trades
+        BEGIN SELECT RAISE(ABORT, 'trades are append-only'); END;
+    CREATE TRIGGER trades_no_delete BEFORE DELETE ON trades
+        BEGIN SELECT RAISE(ABORT, 'trades are append-only'); END;
+    CREATE TRIGGER position_events_no_update BEFORE UPDATE ON position_events
+        BEGIN SELECT RAISE(ABORT, 'position events are append-only'); END;
+    CREATE TRIGGER position_events_no_delete BEFORE DELETE ON position_events
+        BEGIN SELECT RAISE(ABORT, 'position events are append-only'); END;
+    ",
+    // 2: order lifecycle journal for crash recovery
+    "
+    CREATE TABLE order_events (
+        id INTEGER PRIMARY KEY,
+        order_id INTEGER NOT NULL REFERENCES orders (id),
+        from_mint TEXT NOT NULL,
+        to_mint TEXT NOT NULL,
+        terminal INTEGER NOT NULL,
+        recorded_at INTEGER NOT NULL,
+        payload TEXT NOT NULL
+    );
+    CREATE INDEX order_events_order ON order_events (order_id, terminal);
+    CREATE INDEX order_events_time ON order_events (recorded_at);
+
+    CREATE TRIGGER order_events_no_update BEFORE UPDATE ON order_events
+        BEGIN SELECT RAISE(ABORT, 'order events are append-only'); END;
+    CREATE TRIGGER order_events_no_delete BEFORE DELETE ON order_events
+        BEGIN SELECT RAISE(ABORT, 'order events are append-only'); END;
+    ",
+    // 3: periodic holder distribution snapshots
+    "
+    CREATE TABLE holder_snapshots (
+        id INTEGER PRIMARY KEY,
+        mint TEXT NOT NULL,
+        recorded_at INTEGER NOT NULL,
+        payload TEXT NOT NULL
+    );
+    CREATE INDEX holder_snapshots_mint_time ON holder_snapshots (mint, recorded_at);
+    CREATE INDEX holder_snapshots_time ON holder_snapshots (recorded_at);
+
+    CREATE TRIGGER holder_snapshots_no_update BEFORE UPDATE ON holder_snapshots
+        BEGIN SELECT RAISE(ABORT, 'holder snapshots are append-only'); END;
+    CREATE TRIGGER holder_snapshots_no_delete BEFORE DELETE ON holder_snapshots
+        BEGIN SELECT RAISE(ABORT, 'holder snapshots are append-only'); END;
+    ",
+    // 4: supply of each mint when first seen
+    "
+    CREATE TABLE mint_supplies (
+        mint TEXT PRIMARY KEY,
+        recorded_at INTEGER NOT NULL,
+        payload TEXT NOT NULL
+    );
+
+    CREATE TRIGGER mint_supplies_no_update BEFORE UPDATE ON mint_supplies
+        BEGIN SELECT RAISE(ABORT, 'mint supplies are append-only'); END;
+    CREATE TRIGGER mint_supplies_no_delete BEFORE DELETE ON mint_supplies
+        BEGIN SELECT RAISE(ABORT, 'mint supplies are append-only'); END;
+    ",
+];
+
+pub struct SqliteStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStore {
+    /// Opens or creates the database at `path` and brings its schema up to date
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CoralError> {
+        let path = path.as_ref();
+        let conn = Connection::open(path)
+            .map_err(|e| CoralError::database(format!("cannot open {}", path.display()), e))?;
+        Self::with_connection(conn)
+    }
+
+    pub fn open_in_memory() -> Result<Self, CoralError> {
+        let conn = Connection::open_in_memory().map_err(|e| CoralError::database("cannot open in-memory store", e))?;
+        Self::with_connection(conn)
+    }
+
+    fn with_connection(mut conn: Connection) -> Result<Self, CoralError> {
+        conn.pragma_update(None, "foreign_keys", true)
+            .map_err(|e| CoralError::database("cannot enable foreign keys", e))?;
+        migrate(&mut conn)?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Runs `work` against the connection on the blocking pool, since rusqlite blocks
+    /// its thread for the whole query
+    async fn with_conn<T, F>(&self, work: F) -> Result<T, CoralError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, CoralError> + Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().map_err(|_| CoralError::Database {
+                message: "connection lock poisoned".to_string(),
+                source: None,
+            })?;
+            work(&conn)
+        })
+        .await
+        .map_err(|e| CoralError::database("storage task failed", e))?
+    }
+
+    /// Runs the shared `StoreQuery` filter against `table`, returning
+    /// `(id, recorded_at, payload)` rows oldest first. A mint matches if it equals any
+    /// of `mint_columns`.
+    async fn select(
+        &self,
+        table: &'static str,
+        id_column: &str,
+        mint_columns: &[&str],
+        query: &StoreQuery,
+    ) -> Result<Vec<(i64, i64, String)>, CoralError> {
+        let mint_filter: Vec<String> = mint_columns.iter().map(|column| format!("{} = ?1", column)).collect();
+        let sql = format!(
+            "SELECT {id}, recorded_at, payload FROM {table}
+             WHERE (?1 IS NULL OR {mint})
+               AND (?2 IS NULL OR recorded_at >= ?2)
+               AND (?3 IS NULL OR recorded_at <= ?3)
+             ORDER BY recorded_at, id
+             LIMIT ?4",
+            id = id_column,
+            table = table,
+            mint = mint_filter.join(" OR "),
+        );
+        let mint = query.mint.map(|mint| mint.to_string());
+        let (from, to) = (query.from, query.to);
+        // SQLite treats a negative LIMIT as no limit
+        let limit = query.limit.map_or(-1, |limit| limit as i64);
+
+        self.with_conn(move |conn| {
+            let mut statement = conn
+                .prepare_cached(&sql)
+                .map_err(|e| CoralError::database(format!("cannot query {}", table), e))?;
+            let rows = statement
+                .query_map(params![mint, from, to, limit], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                })
+                .map_err(|e| CoralError::database(format!("cannot query {}", table), e))?;
+
+            rows.collect::<Result<Vec<_>, _>>()
+                .map_err(|e| CoralError::database(format!("cannot read {}", table), e))
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl Store for SqliteStore {
+    async fn record_analysis(&self, analysis: &AnalysisResult) -> Result<(), CoralError> {
+        let payload = encode(analysis)?;
+        let (mint, recorded_at) = (analysis.token_address.to_string(), analysis.analysis_timestamp);
+        self.with_conn(move |conn| {
+            insert(
+                conn,
+                "INSERT INTO analyses (mint, recorded_at, payload) VALUES (?1, ?2, ?3)",
+                params![mint, recorded_at, payload],
+            )
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn record_order(&self, order: &TradeOrder) -> Result<OrderId, CoralError> {
+        let payload = encode(order)?;
+        let (from_mint, to_mint) = (order.from_token.to_string(), order.to_token.to_string());
+        self.with_conn(move |conn| {
+            insert(
+                conn,
+                "INSERT INTO orders (from_mint, to_mint, recorded_at, payload) VALUES (?1, ?2, ?3, ?4)",
+                params![from_mint, to_mint, chrono::Utc::now().timestamp(), payload],
+            )
+        })
+        .await
+    }
+
+    async fn record_trade(&self, order_id: OrderId, result: &TradeResult) -> Result<(), CoralError> {
+        let payload = encode(result)?;
+        let (signature, recorded_at) = (result.transaction_signature.clone(), result.timestamp);
+        self.with_conn(move |conn| {
+            let (from_mint, to_mint) = order_mints(conn, order_id)?;
+            insert(
+                conn,
+                "INSERT INTO trades (order_id, from_mint, to_mint, signature, recorded_at, payload)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![order_id, from_mint, to_mint, signature, recorded_at, payload],
+            )
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn record_position_event(&self, event: &PositionEvent) -> Result<(), CoralError> {
+        let payload = encode(event)?;
+        let (mint, recorded_at) = (event.token_address.to_string(), event.timestamp);
+        self.with_conn(move |conn| {
+            insert(
+                conn,
+                "INSERT INTO position_events (mint, recorded_at, payload) VALUES (?1, ?2, ?3)",
+                params![mint, recorded_at, payload],
+            )
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn record_order_event(&self, event: &OrderEvent) -> Result<(), CoralError> {
+        let payload = encode(event)?;
+        let (order_id, terminal, recorded_at) = (event.order_id, event.kind.is_terminal(), event.timestamp);
+        self.with_conn(move |conn| {
+            let (from_mint, to_mint) = order_mints(conn, order_id)?;
+            insert(
+                conn,
+                "INSERT INTO order_events (order_id, from_mint, to_mint, terminal, recorded_at, payload)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![order_id, from_mint, to_mint, terminal, recorded_at, payload],
+            )
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn record_holder_snapshot(&self, snapshot: &HolderSnapshot) -> Result<(), CoralError> {
+        let payload = encode(snapshot)?;
+        let (mint, recorded_at) = (snapshot.mint.to_string(), snapshot.timestamp);
+        self.with_conn(move |conn| {
+            insert(
+                conn,
+                "INSERT INTO holder_snapshots (mint, recorded_at, payload) VALUES (?1, ?2, ?3)",
+                params![mint, recorded_at, payload],
+            )
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn analyses(&self, query: &StoreQuery) -> Result<Vec<AnalysisResult>, CoralError> {
+        self.select("analyses", "id", &["mint"], query)
+            .await?
+            .into_iter()
+            .map(|(_, _, payload)| decode(&payload))
+            .collect()
+    }
+
+    async fn orders(&self, query: &StoreQuery) -> Result<Vec<StoredOrder>, CoralError> {
+        self.select("orders", "id", &["from_mint", "to_mint"], query)
+            .await?
+            .into_iter()
+            .map(|(id, recorded_at, payload)| {
+                Ok(StoredOrder {
+                    id,
+                    recorded_at,
+                    order: decode(&payload)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn trades(&self, query: &StoreQuery) -> Result<Vec<StoredTrade>, CoralError> {
+        self.select("trades", "order_id", &["from_mint", "to_mint"], query)
+            .await?
+            .into_iter()
+            .map(|(order_id, _, payload)| {
+                Ok(StoredTrade {
+                    order_id,
+                    result: decode(&payload)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn position_events(&self, query: &StoreQuery) -> Result<Vec<PositionEvent>, CoralError> {
+        self.select("position_events", "id", &["mint"], query)
+            .await?
+            .into_iter()
+            .map(|(_, _, payload)| decode(&payload))
+            .collect()
+    }
+
+    async fn order_events(&self, query: &StoreQuery) -> Result<Vec<OrderEvent>, CoralError> {
+        self.select("order_events", "id", &["from_mint", "to_mint"], query)
+            .await?
+            .into_iter()
+            .map(|(_, _, payload)| decode(&payload))
+            .collect()
+    }
+
+    async fn holder_snapshots(&self, query: &StoreQuery) -> Result<Vec<HolderSnapshot>, CoralError> {
+        self.select("holder_snapshots", "id", &["mint"], query)
+            .await?
+            .into_iter()
+            .map(|(_, _, payload)| decode(&payload))
+            .collect()
+    }
+
+    async fn latest_holder_snapshot(&self, mint: &Pubkey) -> Result<Option<HolderSnapshot>, CoralError> {
+        let mint = mint.to_string();
+        let payload: Option<String> = self
+            .with_conn(move |conn| {
+                conn.query_row(
+                    "SELECT payload FROM holder_snapshots WHERE mint = ?1 ORDER BY recorded_at DESC, id DESC LIMIT 1",
+                    params![mint],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| CoralError::database("cannot query holder snapshots", e))
+            })
+            .await?;
+
+        payload.map(|payload| decode(&payload)).transpose()
+    }
+
+    async fn first_supply(&self, mint: &Pubkey, supply: u64) -> Result<u64, CoralError> {
+        let payload = encode(&supply)?;
+        let mint = mint.to_string();
+        let payload: String = self
+            .with_conn(move |conn| {
+                conn.execute(
+                    "INSERT OR IGNORE INTO mint_supplies (mint, recorded_at, payload) VALUES (?1, ?2, ?3)",
+                    params![mint, chrono::Utc::now().timestamp(), payload],
+                )
+                .map_err(|e| CoralError::database("insert failed", e))?;
+
+                conn.query_row(
+                    "SELECT payload FROM mint_supplies WHERE mint = ?1",
+                    params![mint],
+                    |row| row.get(0),
+                )
+                .map_err(|e| CoralError::database("cannot query mint supplies", e))
+            })
+            .await?;
+        decode(&payload)
+    }
+
+    async fn pending_orders(&self) -> Result<Vec<StoredOrder>, CoralError> {
+        let rows = self
+            .with_conn(|conn| {
+                let mut statement = conn
+                    .prepare_cached(
+                        "SELECT id, recorded_at, payload FROM orders o
+                         WHERE NOT EXISTS (SELECT 1 FROM trades t WHERE t.order_id = o.id)
+                           AND NOT EXISTS (SELECT 1 FROM order_events e WHERE e.order_id = o.id AND e.terminal)
+                         ORDER BY recorded_at, id",
+                    )
+                    .map_err(|e| CoralError::database("cannot query pending orders", e))?;
+                let rows = statement
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, String>(2)?)))
+                    .map_err(|e| CoralError::database("cannot query pending orders", e))?
+                    .collect::<Result<Vec<(i64, i64, String)>, _>>()
+                    .map_err(|e| CoralError::database("cannot read pending orders", e))?;
+                Ok(rows)
+            })
+            .await?;
+
+        rows.into_iter()
+            .map(|(id, recorded_at, payload)| {
+                Ok(StoredOrder {
+                    id,
+                    recorded_at,
+                    order: decode(&payload)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Mints of a recorded order, denormalized onto the records that reference it so they
+/// can be queried by mint without a join
+fn order_mints(conn: &Connection, order_id: OrderId) -> Result<(String, String), CoralError> {
+    conn.query_row(
+        "SELECT from_mint, to_mint FROM orders WHERE id = ?1",
+        params![order_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+    .map_err(|e| CoralError::database("cannot look up order", e))?
+    .ok_or_else(|| CoralError::Database {
+        message: format!("no order recorded as {}", order_id),
+        source: None,
+    })
+}
+
+fn migrate(conn: &mut Connection) -> Result<(), CoralError> {
+    let current: i64 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(|e| CoralError::database("cannot read schema version", e))?;
+    let current = current as usize;
+    if current > MIGRATIONS.len() {
+        return Err(CoralError::Database {
+            message: format!(
+                "schema version {} is newer than the {} this build knows",
+                current,
+                MIGRATIONS.len()
+            ),
+            source: None,
+        });
+    }
+
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(current) {
+        let version = index + 1;
+        let tx = conn
+            .transaction()
+            .map_err(|e| CoralError::database("cannot start migration", e))?;
+        tx.execute_batch(sql)
+            .map_err(|e| CoralError::database(format!("migration {} failed", version), e))?;
+        tx.pragma_update(None, "user_version", version as i64)
+            .map_err(|e| CoralError::database(format!("migration {} failed", version), e))?;
+        tx.commit()
+            .map_err(|e| CoralError::database(format!("migration {} failed", version), e))?;
+        log::info!("Applied storage migration {}", version);
+    }
+
+    Ok(())
+}
+
+fn insert(conn: &Connection, sql: &str, params: impl rusqlite::Params) -> Result<i64, CoralError> {
+    conn.execute(sql, params)
+        .map_err(|e| CoralError::database("insert failed", e))?;
+    Ok(conn.last_insert_rowid())
+}
+
+fn encode<T: Serialize>(value: &T) -> Result<String, CoralError> {
+    serde_json::to_string(value).map_err(|e| CoralError::database("cannot encode record", e))
+}
+
+fn decode<T: DeserializeOwned>(payload: &str) -> Result<T, CoralError> {
+    serde_json::from_str(payload).map_err(|e| CoralError::database("cannot decode record", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::amount::Price;
+    use crate::core::types::{ConfirmationStatus, RiskLevel, TradeRecommendation};
+    use crate::storage::OrderEventKind;
+    use crate::traders::RoutePreference;
+
+    fn analysis_at(token: Pubkey, timestamp: i64) -> AnalysisResult {
+        let mut analysis = AnalysisResult::new(token, 0.7, RiskLevel::Low, TradeRecommendation::Avoid {
+            risk_factors: vec![],
+        });
+        analysis.analysis_timestamp = timestamp;
+        analysis
+    }
+
+    fn order(from_token: Pubkey, to_token: Pubkey) -> TradeOrder {
+        TradeOrder {
+            from_token,
+            to_token,
+            amount: 5_000_000,
+            slippage_tolerance: 0.01,
+            minimum_received: None,
+            route_preference: RoutePreference::BestPrice,
+        }
+    }
+
+    fn fill(signature: &str) -> TradeResult {
+        TradeResult {
+            transaction_signature: signature.to_string(),
+            amount: 5_000_000,
+            output_amount: 4_990_000,
+            quoted_price: Price::from_raw(5_000_000, 5_000_000).unwrap(),
+            executed_price: Price::from_raw(5_000_000, 4_990_000).unwrap(),
+            slippage_bps: 20,
+            route: vec![],
+            slot: 42,
+            confirmation_status: ConfirmationStatus::Confirmed,
+            fee: 10_000,
+            priority_fee: 5_000,
+            timestamp: 1_700_000_000,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queries_filter_by_mint_and_time_range() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        for (token, timestamp) in [(a, 100), (b, 150), (a, 200), (a, 300)] {
+            store.record_analysis(&analysis_at(token, timestamp)).await.unwrap();
+        }
+
+        let found = store.analyses(&StoreQuery::all().mint(a).between(150, 300)).await.unwrap();
+        let timestamps: Vec<i64> = found.iter().map(|r| r.analysis_timestamp).collect();
+        assert_eq!(timestamps, vec![200, 300]);
+
+        let found = store.analyses(&StoreQuery::all().since(150).limit(2)).await.unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].token_address, b);
+    }
+
+    #[tokio::test]
+    async fn test_trades_link_to_orders_and_match_either_mint() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let (quote, token) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let id = store.record_order(&order(quote, token)).await.unwrap();
+        store.record_trade(id, &fill("sig")).await.unwrap();
+        assert!(store.record_trade(id + 1, &fill("orphan")).await.is_err());
+
+        let orders = store.orders(&StoreQuery::all().mint(token)).await.unwrap();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].order, order(quote, token));
+
+        let trades = store.trades(&StoreQuery::all().mint(quote)).await.unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].order_id, id);
+        assert_eq!(trades[0].result.transaction_signature, "sig");
+        assert!(store.trades(&StoreQuery::all().mint(Pubkey::new_unique())).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pending_orders_exclude_filled_and_settled() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let (quote, token) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let submitted = OrderEventKind::Submitted { balances: None };
+
+        let filled = store.record_order(&order(quote, token)).await.unwrap();
+        store.record_order_event(&OrderEvent::now(filled, submitted.clone())).await.unwrap();
+        store.record_trade(filled, &fill("sig")).await.unwrap();
+
+        let failed = store.record_order(&order(quote, token)).await.unwrap();
+        store.record_order_event(&OrderEvent::now(failed, submitted.clone())).await.unwrap();
+        store
+            .record_order_event(&OrderEvent::now(failed, OrderEventKind::Failed {
+                reason: "no route".to_string(),
+            }))
+            .await
+            .unwrap();
+
+        let in_flight = store.record_order(&order(quote, token)).await.unwrap();
+        store.record_order_event(&OrderEvent::now(in_flight, submitted.clone())).await.unwrap();
+
+        // Sent without a readable fill is still waiting to be reconciled
+        let unread = store.record_order(&order(quote, token)).await.unwrap();
+        store.record_order_event(&OrderEvent::now(unread, submitted)).await.unwrap();
+        store
+            .record_order_event(&OrderEvent::now(unread, OrderEventKind::Sent {
+                transaction_signature: "unread".to_string(),
+            }))
+            .await
+            .unwrap();
+
+        let pending = store.pending_orders().await.unwrap();
+        assert_eq!(pending.iter().map(|o| o.id).collect::<Vec<_>>(), vec![in_flight, unread]);
+        assert_eq!(store.order_events(&StoreQuery::all().mint(token)).await.unwrap().len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_first_supply_is_kept_per_mint() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        assert_eq!(store.first_supply(&a, 1_000).await.unwrap(), 1_000);
+        assert_eq!(store.first_supply(&a, 5_000).await.unwrap(), 1_000);
+        assert_eq!(store.first_supply(&b, u64::MAX).await.unwrap(), u64::MAX);
+    }
+
+    #[tokio::test]
+    async fn test_latest_holder_snapshot_is_per_mint() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(store.latest_holder_snapshot(&a).await.unwrap(), None);
+
+        for (mint, timestamp, holders) in [(a, 100, 1), (a, 200, 2), (b, 300, 3)] {
+            let mut snapshot = HolderSnapshot::now(mint, vec![(Pubkey::new_unique(), 10); holders], 10);
+            snapshot.timestamp = timestamp;
+            store.record_holder_snapshot(&snapshot).await.unwrap();
+        }
+
+        let latest = store.latest_holder_snapshot(&a).await.unwrap().unwrap();
+        assert_eq!((latest.timestamp, latest.holder_count), (200, 2));
+        assert_eq!(store.holder_snapshots(&StoreQuery::all().mint(a)).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_history_is_append_only_and_migrations_are_idempotent() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        store.record_order(&order(Pubkey::new_unique(), Pubkey::new_unique())).await.unwrap();
+
+        let mut conn = store.conn.lock().unwrap();
+        assert!(conn.execute("DELETE FROM orders", []).is_err());
+        assert!(conn.execute("UPDATE orders SET recorded_at = 0", []).is_err());
+
+        migrate(&mut conn).unwrap();
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0)).unwrap();
+        assert_eq!(version as usize, MIGRATIONS.len());
+    }
+}
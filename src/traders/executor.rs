@@ -1,44 +1,91 @@
 use async_trait::async_trait;
 use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 use crate::core::types::{CoralError, TradeResult};
-use super::{Trader, TradeOrder, TradeRoute};
+use crate::utils::{logger::TradeLogger, metrics::{MetricsCollector, TradeExecutionData}};
+use super::{PriceQuote, RoutePreference, RouteStrategy, Trader, TradeOrder, TradeRoute};
 
 pub struct TradeExecutor {
-    traders: Vec<Box<dyn Trader>>,
+    traders: RwLock<Vec<Arc<dyn Trader>>>,
+    strategies: RwLock<HashMap<String, Arc<dyn RouteStrategy>>>,
     metrics: Arc<MetricsCollector>,
     logger: TradeLogger,
 }
 
 impl TradeExecutor {
     pub fn new(metrics: Arc<MetricsCollector>) -> Self {
+        Self::with_traders(metrics, Vec::new())
+    }
+
+    pub fn with_traders(metrics: Arc<MetricsCollector>, traders: Vec<Box<dyn Trader>>) -> Self {
         Self {
-            traders: Vec::new(),
+            traders: RwLock::new(traders.into_iter().map(Arc::from).collect()),
+            strategies: RwLock::new(HashMap::new()),
             metrics,
             logger: TradeLogger::new("trade_executor".to_string()),
         }
     }
 
-    pub fn register_trader(&mut self, trader: Box<dyn Trader>) {
-        self.traders.push(trader);
+    /// Adds a venue; takes effect for the next trade, including while others are in flight
+    pub async fn register_trader(&self, trader: Box<dyn Trader>) {
+        self.traders.write().await.push(Arc::from(trader));
+    }
+
+    /// Makes `strategy` available to orders with `RoutePreference::Custom(name)`,
+    /// replacing any strategy already registered under that name
+    pub async fn register_strategy(&self, name: impl Into<String>, strategy: Box<dyn RouteStrategy>) {
+        self.strategies.write().await.insert(name.into(), Arc::from(strategy));
+    }
+
+    async fn strategy(&self, name: &str) -> Result<Arc<dyn RouteStrategy>, CoralError> {
+        self.strategies
+            .read()
+            .await
+            .get(name)
+            .cloned()
+            .ok_or_else(|| CoralError::config("route_preference", format!("no route strategy registered as {:?}", name)))
+    }
+
+    /// Snapshot of the registered traders so no lock is held across network calls
+    async fn traders(&self) -> Vec<Arc<dyn Trader>> {
+        self.traders.read().await.clone()
     }
 
-    async fn find_best_execution_route(&self, order: &TradeOrder) -> Result<(Box<&dyn Trader>, TradeRoute), CoralError> {
-        let mut best_route = None;
-        let mut best_price = 0.0;
-        let mut selected_trader = None;
+    /// Best quote for `amount` of `token` in `quote` across every registered trader
+    pub async fn get_best_quote(&self, token: &Pubkey, quote: &Pubkey, amount: u64) -> Result<PriceQuote, CoralError> {
+        let mut best_quote: Option<PriceQuote> = None;
 
-        for trader in &self.traders {
-            match trader.get_available_routes(&order.from_token, &order.to_token).await {
-                Ok(routes) => {
-                    for route in routes {
-                        let quote = trader.get_price_quote(&order.from_token, order.amount).await?;
-                        if quote.output_amount as f64 > best_price {
-                            best_price = quote.output_amount as f64;
-                            best_route = Some(route);
-                            selected_trader = Some(trader);
-                        }
+        for trader in self.traders().await {
+            match trader.get_price_quote(token, quote, amount).await {
+                Ok(quote) => {
+                    if best_quote.as_ref().map_or(true, |best| quote.output_amount > best.output_amount) {
+                        best_quote = Some(quote);
                     }
                 }
+                Err(e) => self.logger.log_error(&format!("Quote error: {}", e)),
+            }
+        }
+
+        best_quote.ok_or_else(|| CoralError::MarketNotFound {
+            market: format!("{}/{}", token, quote),
+        })
+    }
+
+    /// Collects routes from every trader and picks one according to the order's
+    /// `RoutePreference`
+    async fn find_best_execution_route(&self, order: &TradeOrder) -> Result<(Arc<dyn Trader>, TradeRoute), CoralError> {
+        // Resolve the strategy first so an unknown name fails before any route lookups
+        let strategy = match &order.route_preference {
+            RoutePreference::Custom(name) => Some(self.strategy(name).await?),
+            _ => None,
+        };
+
+        let mut candidates: Vec<(Arc<dyn Trader>, TradeRoute)> = Vec::new();
+        for trader in self.traders().await {
+            match trader.get_available_routes(&order.from_token, &order.to_token, order.amount).await {
+                Ok(routes) => candidates.extend(routes.into_iter().map(|route| (trader.clone(), route))),
                 Err(e) => {
                     self.logger.log_error(&format!("Route finding error: {}", e));
                     continue;
@@ -46,10 +93,37 @@ impl TradeExecutor {
             }
         }
 
-        match (selected_trader, best_route) {
-            (Some(trader), Some(route)) => Ok((Box::new(trader), route)),
-            _ => Err(CoralError::TradingFailed("No valid trading route found".to_string())),
-        }
+        let selected = match (&order.route_preference, strategy) {
+            (_, Some(strategy)) => {
+                let mut best: Option<(f64, (Arc<dyn Trader>, TradeRoute))> = None;
+                for candidate in candidates {
+                    let score = strategy.evaluate_route(&candidate.1).await;
+                    if best.as_ref().map_or(true, |(best_score, _)| score > *best_score) {
+                        best = Some((score, candidate));
+                    }
+                }
+                best.map(|(_, candidate)| candidate)
+            }
+            (RoutePreference::MinimumHops, None) => candidates.into_iter().min_by(|(_, a), (_, b)| {
+                a.hops
+                    .len()
+                    .cmp(&b.hops.len())
+                    .then(b.expected_output.cmp(&a.expected_output))
+            }),
+            // Routes entirely on the preferred DEX win; otherwise fall back to best price
+            (RoutePreference::PreferredDex(dex), None) => {
+                let (preferred, others): (Vec<_>, Vec<_>) = candidates
+                    .into_iter()
+                    .partition(|(_, route)| !route.hops.is_empty() && route.hops.iter().all(|hop| hop.dex == *dex));
+                let pool = if preferred.is_empty() { others } else { preferred };
+                pool.into_iter().max_by_key(|(_, route)| route.expected_output)
+            }
+            _ => candidates.into_iter().max_by_key(|(_, route)| route.expected_output),
+        };
+
+        selected.ok_or_else(|| CoralError::MarketNotFound {
+            market: format!("{}/{}", order.from_token, order.to_token),
+        })
     }
 
     pub async fn execute_trade(&self, order: TradeOrder) -> Result<TradeResult, CoralError> {
@@ -65,20 +139,32 @@ impl TradeExecutor {
         self.validate_execution(&order, &route)?;
 
         // Execute the trade
-        let result = trader.execute(order).await;
+        let result = trader.execute(order.clone()).await;
 
         // Record metrics
         let execution_time = start_time.elapsed().as_millis() as u64;
         match &result {
+            // Without the fill there is no output or slippage to measure, so it stays out
+            // of the execution statistics
+            Ok(trade_result) if !trade_result.fill_known() => {
+                self.logger.log_trade_completion(true, &format!(
+                    "Trade sent but its fill could not be read back. Signature: {}",
+                    trade_result.transaction_signature
+                ));
+            }
             Ok(trade_result) => {
                 self.metrics.record_trade_execution(TradeExecutionData {
                     token: order.from_token.to_string(),
                     timestamp: chrono::Utc::now(),
-                    amount: order.amount as f64,
+                    amount: order.amount,
                     price: trade_result.executed_price,
                     success: true,
-                    profit_loss: 0.0, // Calculate actual P&L
+                    profit_loss: None,
                     execution_time_ms: execution_time,
+                    output_amount: trade_result.output_amount,
+                    slippage_bps: trade_result.slippage_bps,
+                    fee: trade_result.fee,
+                    priority_fee: trade_result.priority_fee,
                 }).await;
                 self.logger.log_trade_completion(true, &format!(
                     "Trade executed successfully. Signature: {}",
@@ -97,16 +183,22 @@ impl TradeExecutor {
     fn validate_execution(&self, order: &TradeOrder, route: &TradeRoute) -> Result<(), CoralError> {
         // Implement validation logic
         if route.price_impact > order.slippage_tolerance {
-            return Err(CoralError::TradingFailed(
-                format!("Price impact too high: {}", route.price_impact)
-            ));
+            return Err(CoralError::InvalidPrice {
+                reason: format!(
+                    "price impact {} exceeds slippage tolerance {}",
+                    route.price_impact, order.slippage_tolerance
+                ),
+            });
         }
 
         if let Some(min_received) = order.minimum_received {
             if route.expected_output < min_received {
-                return Err(CoralError::TradingFailed(
-                    "Expected output below minimum threshold".to_string()
-                ));
+                return Err(CoralError::InvalidPrice {
+                    reason: format!(
+                        "expected output {} below minimum {}",
+                        route.expected_output, min_received
+                    ),
+                });
             }
         }
 
@@ -117,15 +209,17 @@ impl TradeExecutor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::amount::Price;
+    use crate::core::types::ConfirmationStatus;
     use crate::utils::MetricsCollector;
 
     #[tokio::test]
     async fn test_trade_executor() {
         let metrics = Arc::new(MetricsCollector::new());
-        let mut executor = TradeExecutor::new(metrics.clone());
+        let executor = TradeExecutor::new(metrics.clone());
         
         // Add mock trader
-        executor.register_trader(Box::new(MockTrader::new()));
+        executor.register_trader(Box::new(MockTrader::new())).await;
 
         let order = TradeOrder {
             from_token: Pubkey::new_unique(),
@@ -133,34 +227,144 @@ mod tests {
             amount: 1000000,
             slippage_tolerance: 0.01,
             minimum_received: None,
-            route_preference: super::RoutePreference::BestPrice,
+            route_preference: RoutePreference::BestPrice,
         };
 
         let result = executor.execute_trade(order).await;
         assert!(result.is_ok());
+
+        let quality = metrics.get_execution_quality().await;
+        assert_eq!(quality.fills, 1);
+        assert_eq!(quality.average_slippage_bps, 50.0);
+        assert_eq!(quality.total_priority_fees, 5000);
+    }
+
+    #[tokio::test]
+    async fn test_unread_fill_stays_out_of_execution_quality() {
+        let metrics = Arc::new(MetricsCollector::new());
+        let executor = TradeExecutor::new(metrics.clone());
+        executor.register_trader(Box::new(MockTrader::unread())).await;
+
+        let order = TradeOrder {
+            from_token: Pubkey::new_unique(),
+            to_token: Pubkey::new_unique(),
+            amount: 1000000,
+            slippage_tolerance: 0.01,
+            minimum_received: None,
+            route_preference: RoutePreference::BestPrice,
+        };
+
+        let result = executor.execute_trade(order).await.unwrap();
+        assert!(!result.fill_known());
+        assert_eq!(metrics.get_execution_quality().await.fills, 0);
     }
 
-    struct MockTrader;
+    #[tokio::test]
+    async fn test_custom_route_preference_requires_registered_strategy() {
+        let executor = TradeExecutor::new(Arc::new(MetricsCollector::new()));
+        executor.register_trader(Box::new(MockTrader::new())).await;
+
+        let order = TradeOrder {
+            from_token: Pubkey::new_unique(),
+            to_token: Pubkey::new_unique(),
+            amount: 1000000,
+            slippage_tolerance: 0.01,
+            minimum_received: None,
+            route_preference: RoutePreference::Custom("lowest_fee".to_string()),
+        };
+
+        let result = executor.execute_trade(order.clone()).await;
+        assert!(matches!(result, Err(CoralError::Config { .. })));
+
+        executor.register_strategy("lowest_fee", Box::new(LowestFee)).await;
+        assert!(executor.execute_trade(order).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_minimum_received_is_checked_against_routes_for_the_order_amount() {
+        let executor = TradeExecutor::new(Arc::new(MetricsCollector::new()));
+        executor.register_trader(Box::new(MockTrader::new())).await;
+
+        let order = TradeOrder {
+            from_token: Pubkey::new_unique(),
+            to_token: Pubkey::new_unique(),
+            amount: 50_000_000,
+            slippage_tolerance: 0.01,
+            minimum_received: Some(49_000_000),
+            route_preference: RoutePreference::BestPrice,
+        };
+        assert!(executor.execute_trade(order.clone()).await.is_ok());
+
+        let too_greedy = TradeOrder {
+            minimum_received: Some(49_600_000),
+            ..order
+        };
+        assert!(matches!(
+            executor.execute_trade(too_greedy).await,
+            Err(CoralError::InvalidPrice { .. })
+        ));
+    }
+
+    struct LowestFee;
+
+    #[async_trait]
+    impl RouteStrategy for LowestFee {
+        async fn evaluate_route(&self, route: &TradeRoute) -> f64 {
+            -(route.total_fee as f64)
+        }
+    }
+
+    struct MockTrader {
+        /// Whether the fill can be read back after sending
+        fill_known: bool,
+    }
 
     impl MockTrader {
         fn new() -> Self {
-            Self
+            Self { fill_known: true }
+        }
+
+        fn unread() -> Self {
+            Self { fill_known: false }
         }
     }
 
     #[async_trait]
     impl Trader for MockTrader {
         async fn execute(&self, _order: TradeOrder) -> Result<TradeResult, CoralError> {
+            if !self.fill_known {
+                return Ok(TradeResult {
+                    transaction_signature: "mock_signature".to_string(),
+                    amount: 1000000,
+                    output_amount: 0,
+                    quoted_price: Price::from_raw(1_000_000, 990_000).unwrap(),
+                    executed_price: Price::ZERO,
+                    slippage_bps: 0,
+                    route: vec![],
+                    slot: 0,
+                    confirmation_status: ConfirmationStatus::Unknown,
+                    fee: 0,
+                    priority_fee: 0,
+                    timestamp: chrono::Utc::now().timestamp(),
+                });
+            }
             Ok(TradeResult {
                 transaction_signature: "mock_signature".to_string(),
-                executed_price: 1.0,
                 amount: 1000000,
-                fee: 1000,
+                output_amount: 985000,
+                quoted_price: Price::from_raw(1_000_000, 990_000).unwrap(),
+                executed_price: Price::from_raw(1_000_000, 985_000).unwrap(),
+                slippage_bps: TradeResult::slippage_bps(990000, 985000),
+                route: vec![],
+                slot: 1,
+                confirmation_status: ConfirmationStatus::Confirmed,
+                fee: 10000,
+                priority_fee: 5000,
                 timestamp: chrono::Utc::now().timestamp(),
             })
         }
 
-        async fn get_price_quote(&self, _token: &Pubkey, _amount: u64) -> Result<super::PriceQuote, CoralError> {
+        async fn get_price_quote(&self, _token: &Pubkey, _quote: &Pubkey, _amount: u64) -> Result<super::PriceQuote, CoralError> {
             Ok(super::PriceQuote {
                 input_amount: 1000000,
                 output_amount: 1000000,
@@ -175,11 +379,11 @@ mod tests {
             })
         }
 
-        async fn get_available_routes(&self, _from: &Pubkey, _to: &Pubkey) -> Result<Vec<TradeRoute>, CoralError> {
+        async fn get_available_routes(&self, _from: &Pubkey, _to: &Pubkey, amount: u64) -> Result<Vec<TradeRoute>, CoralError> {
             Ok(vec![TradeRoute {
                 hops: vec![],
                 total_fee: 1000,
-                expected_output: 990000,
+                expected_output: amount / 100 * 99,
                 price_impact: 0.001,
             }])
         }
@@ -1,17 +1,22 @@
 use async_trait::async_trait;
 use solana_sdk::pubkey::Pubkey;
-use crate::core::types::{CoralError, TradeResult};
+use crate::core::amount::Price;
+use crate::core::types::{ConfirmationStatus, CoralError, TradeResult};
+use crate::utils::solana::SolanaUtils;
+use crate::utils::token_registry::USDC_MINT;
 use super::{Trader, TradeOrder, PriceQuote, TradeRoute};
 
 pub struct JupiterTrader {
     client: JupiterClient,
+    solana: SolanaUtils,
     config: JupiterConfig,
 }
 
 impl JupiterTrader {
-    pub fn new(api_key: &str, config: JupiterConfig) -> Self {
+    pub fn new(api_key: &str, rpc_url: &str, config: JupiterConfig) -> Self {
         Self {
             client: JupiterClient::new(api_key),
+            solana: SolanaUtils::new(rpc_url),
             config,
         }
     }
@@ -38,7 +43,18 @@ impl JupiterTrader {
             }
         }
 
-        best_route.ok_or(CoralError::TradingFailed("No valid routes found".to_string()))
+        best_route.ok_or_else(|| CoralError::execution("no route scored above zero"))
+    }
+
+    /// Quote units per unit of the traded token when `order` yields `output`, whichever
+    /// side of the swap the quote token is on
+    fn price(&self, order: &TradeOrder, output: u64) -> Price {
+        let price = if order.from_token == self.config.quote_token {
+            Price::from_raw(output, order.amount)
+        } else {
+            Price::from_raw(order.amount, output)
+        };
+        price.unwrap_or(Price::ZERO)
     }
 
     fn calculate_route_score(&self, route: &TradeRoute) -> f64 {
@@ -62,23 +78,53 @@ impl Trader for JupiterTrader {
         let route = self.find_best_route(&order.from_token, &order.to_token, order.amount).await?;
         
         if !self.validate_route(&route).await? {
-            return Err(CoralError::TradingFailed("Route validation failed".to_string()));
+            return Err(CoralError::execution("route failed validation"));
         }
 
         let transaction = self.client.create_swap_transaction(&order, &route).await?;
         let signature = self.client.send_and_confirm_transaction(transaction).await?;
 
+        // The swap has landed; a failed read-back must not turn it into an error, but the
+        // output stays unknown rather than assumed to match the quote
+        let fill = match self.solana.get_fill_details(&signature, &order.from_token, &order.to_token).await {
+            Ok(fill) => fill,
+            Err(e) => {
+                log::warn!("Could not read back fill for {}: {}", signature, e);
+                return Ok(TradeResult {
+                    transaction_signature: signature,
+                    amount: order.amount,
+                    output_amount: 0,
+                    quoted_price: self.price(&order, route.expected_output),
+                    executed_price: Price::ZERO,
+                    slippage_bps: 0,
+                    route: route.hops,
+                    slot: 0,
+                    confirmation_status: ConfirmationStatus::Unknown,
+                    fee: 0,
+                    priority_fee: 0,
+                    timestamp: chrono::Utc::now().timestamp(),
+                });
+            }
+        };
+
         Ok(TradeResult {
             transaction_signature: signature,
-            executed_price: self.calculate_execution_price(&route),
             amount: order.amount,
-            fee: route.total_fee,
-            timestamp: chrono::Utc::now().timestamp(),
+            output_amount: fill.output_amount,
+            quoted_price: self.price(&order, route.expected_output),
+            executed_price: self.price(&order, fill.output_amount),
+            slippage_bps: TradeResult::slippage_bps(route.expected_output, fill.output_amount),
+            route: route.hops,
+            slot: fill.slot,
+            confirmation_status: fill.confirmation_status,
+            fee: fill.fee,
+            priority_fee: fill.priority_fee,
+            timestamp: fill.block_time.unwrap_or_else(|| chrono::Utc::now().timestamp()),
         })
     }
 
-    async fn get_price_quote(&self, token: &Pubkey, amount: u64) -> Result<PriceQuote, CoralError> {
-        let route = self.find_best_route(token, &self.config.usdc_token, amount).await?;
+    async fn get_price_quote(&self, token: &Pubkey, quote: &Pubkey, amount: u64) -> Result<PriceQuote, CoralError> {
+        let route = self.find_best_route(token, quote, amount).await?;
         
         Ok(PriceQuote {
             input_amount: amount,
@@ -89,16 +135,28 @@ impl Trader for JupiterTrader {
         })
     }
 
-    async fn get_available_routes(&self, from: &Pubkey, to: &Pubkey) -> Result<Vec<TradeRoute>, CoralError> {
-        self.client.get_routes(from, to, 1_000_000).await
+    async fn get_available_routes(&self, from: &Pubkey, to: &Pubkey, amount: u64) -> Result<Vec<TradeRoute>, CoralError> {
+        self.client.get_routes(from, to, amount).await
     }
 }
 
-struct JupiterConfig {
-    usdc_token: Pubkey,
-    max_price_impact: f64,
-    max_hops: u8,
-    minimum_liquidity: u64,
+#[derive(Debug, Clone)]
+pub struct JupiterConfig {
+    pub quote_token: Pubkey,
+    pub max_price_impact: f64,
+    pub max_hops: u8,
+    pub minimum_liquidity: u64,
+}
+
+impl Default for JupiterConfig {
+    fn default() -> Self {
+        Self {
+            quote_token: USDC_MINT,
+            max_price_impact: 0.05,
+            max_hops: 3,
+            minimum_liquidity: 10_000_000_000,
+        }
+    }
 }
 
 struct RouteValidation {
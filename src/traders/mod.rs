@@ -2,17 +2,20 @@
 //! Provides interfaces and implementations for executing trades across different DEXs
 
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
 use crate::core::types::{CoralError, TradeResult};
 
 #[async_trait]
-pub trait Trader {
+pub trait Trader: Send + Sync {
     async fn execute(&self, order: TradeOrder) -> Result<TradeResult, CoralError>;
-    async fn get_price_quote(&self, token: &Pubkey, amount: u64) -> Result<PriceQuote, CoralError>;
-    async fn get_available_routes(&self, from: &Pubkey, to: &Pubkey) -> Result<Vec<TradeRoute>, CoralError>;
+    /// What `amount` of `token` is worth in `quote`
+    async fn get_price_quote(&self, token: &Pubkey, quote: &Pubkey, amount: u64) -> Result<PriceQuote, CoralError>;
+    /// Routes for swapping `amount` of `from` into `to`, with outputs quoted for that amount
+    async fn get_available_routes(&self, from: &Pubkey, to: &Pubkey, amount: u64) -> Result<Vec<TradeRoute>, CoralError>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TradeOrder {
     pub from_token: Pubkey,
     pub to_token: Pubkey,
@@ -22,7 +25,7 @@ pub struct TradeOrder {
     pub route_preference: RoutePreference,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PriceQuote {
     pub input_amount: u64,
     pub output_amount: u64,
@@ -31,7 +34,7 @@ pub struct PriceQuote {
     pub route: TradeRoute,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TradeRoute {
     pub hops: Vec<RouteHop>,
     pub total_fee: u64,
@@ -39,7 +42,7 @@ pub struct TradeRoute {
     pub price_impact: f64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RouteHop {
     pub dex: DexType,
     pub input_token: Pubkey,
@@ -48,15 +51,16 @@ pub struct RouteHop {
     pub fee_tier: u32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum RoutePreference {
     BestPrice,
     MinimumHops,
     PreferredDex(DexType),
-    Custom(Box<dyn RouteStrategy>),
+    /// Name of a strategy registered with `TradeExecutor::register_strategy`
+    Custom(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DexType {
     Jupiter,
     Orca,
@@ -65,6 +69,7 @@ pub enum DexType {
     Custom(String),
 }
 
+/// Scores routes for `RoutePreference::Custom`; the highest score wins
 #[async_trait]
 pub trait RouteStrategy: Send + Sync {
     async fn evaluate_route(&self, route: &TradeRoute) -> f64;
@@ -3,12 +3,15 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use chrono::{DateTime, Utc};
+use crate::core::amount::{Price, SignedAmount};
 
 /// Performance and operational metrics tracker
 pub struct MetricsCollector {
     trade_count: AtomicU64,
     analysis_count: AtomicU64,
     error_count: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
     performance_metrics: Arc<RwLock<PerformanceMetrics>>,
     trade_metrics: Arc<RwLock<TradeMetrics>>,
 }
@@ -19,6 +22,8 @@ impl MetricsCollector {
             trade_count: AtomicU64::new(0),
             analysis_count: AtomicU64::new(0),
             error_count: AtomicU64::new(0),
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
             performance_metrics: Arc::new(RwLock::new(PerformanceMetrics::default())),
             trade_metrics: Arc::new(RwLock::new(TradeMetrics::default())),
         }
@@ -36,6 +41,21 @@ impl MetricsCollector {
         self.error_count.fetch_add(1, Ordering::SeqCst);
     }
 
+    pub fn record_cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn record_cache_miss(&self) {
+        self.cache_misses.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn get_cache_stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.cache_hits.load(Ordering::SeqCst),
+            misses: self.cache_misses.load(Ordering::SeqCst),
+        }
+    }
+
     pub async fn record_analysis_time(&self, duration_ms: u64) {
         let mut metrics = self.performance_metrics.write().await;
         metrics.record_analysis_time(duration_ms);
@@ -46,6 +66,10 @@ impl MetricsCollector {
         metrics.record_trade(trade_data);
     }
 
+    pub async fn get_execution_quality(&self) -> ExecutionQuality {
+        self.trade_metrics.read().await.execution_quality()
+    }
+
     pub async fn get_metrics_summary(&self) -> MetricsSummary {
         let performance = self.performance_metrics.read().await;
         let trades = self.trade_metrics.read().await;
@@ -102,8 +126,29 @@ impl TradeMetrics {
         successful as f64 / self.trades.len() as f64
     }
 
-    fn calculate_total_pnl(&self) -> f64 {
-        self.trades.iter().map(|t| t.profit_loss).sum()
+    /// Sum of recorded P&L; `None` if it overflows
+    fn calculate_total_pnl(&self) -> Option<SignedAmount> {
+        self.trades
+            .iter()
+            .filter_map(|t| t.profit_loss)
+            .try_fold(SignedAmount::zero(0), |total, pnl| total.checked_add(pnl))
+    }
+
+    fn execution_quality(&self) -> ExecutionQuality {
+        let fills: Vec<&TradeExecutionData> = self.trades.iter().filter(|t| t.success).collect();
+        if fills.is_empty() {
+            return ExecutionQuality::default();
+        }
+
+        ExecutionQuality {
+            fills: fills.len() as u64,
+            average_slippage_bps: fills.iter().map(|t| t.slippage_bps as f64).sum::<f64>() / fills.len() as f64,
+            worst_slippage_bps: fills.iter().map(|t| t.slippage_bps).max().unwrap_or(0),
+            total_fees: fills.iter().map(|t| t.fee).sum(),
+            total_priority_fees: fills.iter().map(|t| t.priority_fee).sum(),
+            average_execution_time_ms: fills.iter().map(|t| t.execution_time_ms as f64).sum::<f64>()
+                / fills.len() as f64,
+        }
     }
 
     fn update_token_performance(&mut self, trade: &TradeExecutionData) {
@@ -115,15 +160,51 @@ impl TradeMetrics {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / total as f64
+    }
+}
+
+/// How well successful fills matched their quotes
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExecutionQuality {
+    pub fills: u64,
+    pub average_slippage_bps: f64,
+    pub worst_slippage_bps: i64,
+    /// Lamports
+    pub total_fees: u64,
+    pub total_priority_fees: u64,
+    pub average_execution_time_ms: f64,
+}
+
 #[derive(Debug)]
 pub struct TradeExecutionData {
     pub token: String,
     pub timestamp: DateTime<Utc>,
-    pub amount: f64,
-    pub price: f64,
+    /// Input amount in base units
+    pub amount: u64,
+    pub price: Price,
     pub success: bool,
-    pub profit_loss: f64,
+    /// Realized P&L when known; the executor alone cannot attribute cost basis
+    pub profit_loss: Option<SignedAmount>,
     pub execution_time_ms: u64,
+    /// Output received in base units
+    pub output_amount: u64,
+    pub slippage_bps: i64,
+    /// Lamports, including `priority_fee`
+    pub fee: u64,
+    pub priority_fee: u64,
 }
 
 #[derive(Debug, Default)]
@@ -0,0 +1,349 @@
+//! Typed view of a mint account under either token program
+//!
+//! Token-2022 mints can carry extensions that change what holding the token means: a
+//! transfer fee taxes every sell, a permanent delegate can move anyone's balance, a
+//! transfer hook runs arbitrary code on each transfer. `MintProfile` exposes them so
+//! analyzers can score them like any other finding.
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::{program_option::COption, pubkey, pubkey::Pubkey};
+use spl_token_2022::extension::{
+    confidential_transfer::ConfidentialTransferMint,
+    default_account_state::DefaultAccountState,
+    interest_bearing_mint::InterestBearingConfig,
+    mint_close_authority::MintCloseAuthority,
+    permanent_delegate::PermanentDelegate,
+    transfer_fee::TransferFeeConfig,
+    transfer_hook::TransferHook,
+    BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
+use spl_token_2022::state::{AccountState, Mint};
+use crate::core::types::{RiskFactor, RiskFactorKind, RiskLevel};
+
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+/// Tokens sent to accounts owned by this address can never move again
+pub const INCINERATOR: Pubkey = pubkey!("1nc1nerator11111111111111111111111111111111");
+
+/// Transfer fees at or above this many basis points are treated as a honeypot
+const PUNITIVE_TRANSFER_FEE_BPS: u16 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TokenProgram {
+    SplToken,
+    Token2022,
+}
+
+impl TokenProgram {
+    pub fn from_owner(owner: &Pubkey) -> Option<Self> {
+        if *owner == spl_token::id() {
+            Some(Self::SplToken)
+        } else if *owner == spl_token_2022::id() {
+            Some(Self::Token2022)
+        } else {
+            None
+        }
+    }
+
+    pub fn id(&self) -> Pubkey {
+        match self {
+            Self::SplToken => spl_token::id(),
+            Self::Token2022 => spl_token_2022::id(),
+        }
+    }
+
+    /// Address of `owner`'s associated token account for `mint` under this program
+    pub fn associated_token_address(&self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[owner.as_ref(), self.id().as_ref(), mint.as_ref()],
+            &ASSOCIATED_TOKEN_PROGRAM_ID,
+        )
+        .0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MintProfile {
+    pub program: TokenProgram,
+    pub mint_authority: Option<Pubkey>,
+    pub freeze_authority: Option<Pubkey>,
+    pub supply: u64,
+    pub decimals: u8,
+    /// Always empty for SPL Token mints
+    pub extensions: Vec<MintExtension>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MintExtension {
+    /// Fee withheld from every transfer. Both the fee in force and one scheduled for a
+    /// later epoch are kept, since the authority can raise it at any time.
+    TransferFee {
+        older_basis_points: u16,
+        newer_basis_points: u16,
+        newer_epoch: u64,
+        maximum_fee: u64,
+        config_authority: Option<Pubkey>,
+        withdraw_authority: Option<Pubkey>,
+    },
+    /// Program invoked on every transfer, which can refuse it
+    TransferHook { program_id: Option<Pubkey>, authority: Option<Pubkey> },
+    /// Account that may transfer or burn tokens from any holder
+    PermanentDelegate { delegate: Option<Pubkey> },
+    NonTransferable,
+    ConfidentialTransfers { authority: Option<Pubkey>, auto_approve_new_accounts: bool },
+    /// New token accounts start frozen until the freeze authority thaws them
+    DefaultAccountState { frozen: bool },
+    MintCloseAuthority { authority: Option<Pubkey> },
+    InterestBearing { rate_basis_points: i16, authority: Option<Pubkey> },
+    /// Extensions with no bearing on risk, by their `ExtensionType` name
+    Other { name: String },
+}
+
+impl MintProfile {
+    /// Parses a mint account owned by `owner`. `None` when the owner is not a token
+    /// program or the data is not an initialized mint.
+    pub fn parse(owner: &Pubkey, data: &[u8]) -> Option<Self> {
+        let program = TokenProgram::from_owner(owner)?;
+        let state = StateWithExtensions::<Mint>::unpack(data).ok()?;
+        if !state.base.is_initialized {
+            return None;
+        }
+
+        let extensions = match program {
+            TokenProgram::SplToken => Vec::new(),
+            TokenProgram::Token2022 => state
+                .get_extension_types()
+                .ok()?
+                .into_iter()
+                .filter_map(|extension_type| parse_extension(&state, extension_type))
+                .collect(),
+        };
+
+        Some(Self {
+            program,
+            mint_authority: option(state.base.mint_authority),
+            freeze_authority: option(state.base.freeze_authority),
+            supply: state.base.supply,
+            decimals: state.base.decimals,
+            extensions,
+        })
+    }
+
+    /// Highest transfer fee the mint charges now or has scheduled, in basis points
+    pub fn transfer_fee_basis_points(&self) -> Option<u16> {
+        self.extensions.iter().find_map(|extension| match extension {
+            MintExtension::TransferFee { older_basis_points, newer_basis_points, .. } => {
+                Some(*older_basis_points.max(newer_basis_points))
+            }
+            _ => None,
+        })
+    }
+
+    pub fn permanent_delegate(&self) -> Option<Pubkey> {
+        self.extensions.iter().find_map(|extension| match extension {
+            MintExtension::PermanentDelegate { delegate } => *delegate,
+            _ => None,
+        })
+    }
+
+    /// Findings from the mint's extensions that affect whether a holder can sell
+    pub fn risk_factors(&self) -> Vec<RiskFactor> {
+        self.extensions
+            .iter()
+            .filter_map(|extension| match extension {
+                MintExtension::TransferFee { maximum_fee, config_authority, .. } => {
+                    let basis_points = self.transfer_fee_basis_points().unwrap_or(0);
+                    let severity = if basis_points >= PUNITIVE_TRANSFER_FEE_BPS {
+                        RiskLevel::Extreme
+                    } else if basis_points > 0 || config_authority.is_some() {
+                        RiskLevel::High
+                    } else {
+                        return None;
+                    };
+                    Some(
+                        RiskFactor::new(RiskFactorKind::TransferFee, severity)
+                            .with_evidence("basis_points", basis_points)
+                            .with_evidence("maximum_fee", maximum_fee)
+                            .with_evidence("config_authority", display(config_authority)),
+                    )
+                }
+                MintExtension::PermanentDelegate { delegate: Some(delegate) } => Some(
+                    RiskFactor::new(RiskFactorKind::PermanentDelegate, RiskLevel::Extreme)
+                        .with_evidence("delegate", delegate),
+                ),
+                MintExtension::TransferHook { program_id: Some(program_id), authority } => Some(
+                    RiskFactor::new(RiskFactorKind::TransferHook, RiskLevel::High)
+                        .with_evidence("program_id", program_id)
+                        .with_evidence("authority", display(authority)),
+                ),
+                MintExtension::NonTransferable => {
+                    Some(RiskFactor::new(RiskFactorKind::NonTransferable, RiskLevel::Extreme))
+                }
+                MintExtension::DefaultAccountState { frozen: true } => Some(
+                    RiskFactor::new(RiskFactorKind::FrozenByDefault, RiskLevel::High)
+                        .with_evidence("freeze_authority", display(&self.freeze_authority)),
+                ),
+                MintExtension::ConfidentialTransfers { authority, .. } => Some(
+                    RiskFactor::new(RiskFactorKind::ConfidentialTransfers, RiskLevel::Medium)
+                        .with_evidence("authority", display(authority)),
+                ),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+fn parse_extension(state: &StateWithExtensions<Mint>, extension_type: ExtensionType) -> Option<MintExtension> {
+    let extension = match extension_type {
+        ExtensionType::TransferFeeConfig => {
+            let config = state.get_extension::<TransferFeeConfig>().ok()?;
+            MintExtension::TransferFee {
+                older_basis_points: config.older_transfer_fee.transfer_fee_basis_points.into(),
+                newer_basis_points: config.newer_transfer_fee.transfer_fee_basis_points.into(),
+                newer_epoch: config.newer_transfer_fee.epoch.into(),
+                maximum_fee: u64::from(config.older_transfer_fee.maximum_fee)
+                    .max(config.newer_transfer_fee.maximum_fee.into()),
+                config_authority: config.transfer_fee_config_authority.into(),
+                withdraw_authority: config.withdraw_withheld_authority.into(),
+            }
+        }
+        ExtensionType::TransferHook => {
+            let hook = state.get_extension::<TransferHook>().ok()?;
+            MintExtension::TransferHook {
+                program_id: hook.program_id.into(),
+                authority: hook.authority.into(),
+            }
+        }
+        ExtensionType::PermanentDelegate => {
+            let delegate = state.get_extension::<PermanentDelegate>().ok()?;
+            MintExtension::PermanentDelegate {
+                delegate: delegate.delegate.into(),
+            }
+        }
+        ExtensionType::NonTransferable => MintExtension::NonTransferable,
+        ExtensionType::ConfidentialTransferMint => {
+            let config = state.get_extension::<ConfidentialTransferMint>().ok()?;
+            MintExtension::ConfidentialTransfers {
+                authority: config.authority.into(),
+                auto_approve_new_accounts: config.auto_approve_new_accounts.into(),
+            }
+        }
+        ExtensionType::DefaultAccountState => {
+            let default_state = state.get_extension::<DefaultAccountState>().ok()?;
+            MintExtension::DefaultAccountState {
+                frozen: default_state.state == AccountState::Frozen as u8,
+            }
+        }
+        ExtensionType::MintCloseAuthority => {
+            let close = state.get_extension::<MintCloseAuthority>().ok()?;
+            MintExtension::MintCloseAuthority {
+                authority: close.close_authority.into(),
+            }
+        }
+        ExtensionType::InterestBearingConfig => {
+            let config = state.get_extension::<InterestBearingConfig>().ok()?;
+            MintExtension::InterestBearing {
+                rate_basis_points: config.current_rate.into(),
+                authority: config.rate_authority.into(),
+            }
+        }
+        ExtensionType::Uninitialized => return None,
+        other => MintExtension::Other {
+            name: format!("{:?}", other),
+        },
+    };
+    Some(extension)
+}
+
+fn option(key: COption<Pubkey>) -> Option<Pubkey> {
+    match key {
+        COption::Some(key) => Some(key),
+        COption::None => None,
+    }
+}
+
+fn display(key: &Option<Pubkey>) -> String {
+    key.map_or_else(|| "none".to_string(), |key| key.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Packed mint with both authorities set, followed by Token-2022 TLV `extensions`
+    fn mint_data(extensions: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(&1_000_000u64.to_le_bytes());
+        data.push(6);
+        data.push(1);
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+
+        if !extensions.is_empty() {
+            // Mints are padded to the token account length, then tagged with the account type
+            data.resize(165, 0);
+            data.push(1);
+            for (extension_type, value) in extensions {
+                data.extend_from_slice(&extension_type.to_le_bytes());
+                data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+                data.extend_from_slice(value);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_parses_both_token_programs() {
+        let legacy = MintProfile::parse(&spl_token::id(), &mint_data(&[])).unwrap();
+        assert_eq!(legacy.program, TokenProgram::SplToken);
+        assert_eq!((legacy.supply, legacy.decimals), (1_000_000, 6));
+        assert!(legacy.mint_authority.is_some() && legacy.freeze_authority.is_some());
+        assert!(legacy.risk_factors().is_empty());
+
+        let plain = MintProfile::parse(&spl_token_2022::id(), &mint_data(&[])).unwrap();
+        assert_eq!(plain.program, TokenProgram::Token2022);
+        assert!(plain.extensions.is_empty());
+
+        assert!(MintProfile::parse(&Pubkey::new_unique(), &mint_data(&[])).is_none());
+        assert!(MintProfile::parse(&spl_token::id(), &[0u8; 10]).is_none());
+    }
+
+    #[test]
+    fn test_flags_risky_token_2022_extensions() {
+        let delegate = Pubkey::new_unique();
+        let transfer_fee = {
+            // No authorities and nothing withheld; older fee: epoch 0, max 1000, 100 bps;
+            // newer fee: epoch 10, max 1000, 900 bps
+            let mut value = vec![0u8; 64];
+            value.extend_from_slice(&0u64.to_le_bytes());
+            value.extend_from_slice(&0u64.to_le_bytes());
+            value.extend_from_slice(&1_000u64.to_le_bytes());
+            value.extend_from_slice(&100u16.to_le_bytes());
+            value.extend_from_slice(&10u64.to_le_bytes());
+            value.extend_from_slice(&1_000u64.to_le_bytes());
+            value.extend_from_slice(&900u16.to_le_bytes());
+            value
+        };
+        let data = mint_data(&[
+            (ExtensionType::TransferFeeConfig as u16, transfer_fee),
+            (ExtensionType::PermanentDelegate as u16, delegate.to_bytes().to_vec()),
+            (ExtensionType::NonTransferable as u16, Vec::new()),
+        ]);
+
+        let profile = MintProfile::parse(&spl_token_2022::id(), &data).unwrap();
+        assert_eq!(profile.transfer_fee_basis_points(), Some(900));
+        assert_eq!(profile.permanent_delegate(), Some(delegate));
+
+        let factors: Vec<(RiskFactorKind, RiskLevel)> =
+            profile.risk_factors().iter().map(|factor| (factor.kind, factor.severity)).collect();
+        assert_eq!(
+            factors,
+            vec![
+                (RiskFactorKind::TransferFee, RiskLevel::Extreme),
+                (RiskFactorKind::PermanentDelegate, RiskLevel::Extreme),
+                (RiskFactorKind::NonTransferable, RiskLevel::Extreme),
+            ]
+        );
+    }
+}
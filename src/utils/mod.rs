@@ -1,19 +1,21 @@
 //! Utility functions and helpers for the Coral trading agent
 
 pub mod solana;
+pub mod mint;
 pub mod metrics;
 pub mod logger;
+pub mod token_registry;
 
 use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
+use crate::core::amount::TokenAmount;
 
 pub fn parse_pubkey(key: &str) -> Result<Pubkey, String> {
     Pubkey::from_str(key).map_err(|e| e.to_string())
 }
 
 pub fn format_amount(amount: u64, decimals: u8) -> String {
-    let amount_f64 = amount as f64 / 10_f64.powi(decimals as i32);
-    format!("{:.precision$}", amount_f64, precision = decimals as usize)
+    TokenAmount::new(amount, decimals).to_string()
 }
 
 pub fn calculate_percentage_change(old_value: f64, new_value: f64) -> f64 {
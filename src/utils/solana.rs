@@ -1,11 +1,34 @@
 use solana_sdk::{
+    account::Account,
     commitment_config::CommitmentConfig,
     pubkey::Pubkey,
     signature::{Keypair, Signature},
     transaction::Transaction,
 };
-use solana_client::rpc_client::RpcClient;
-use crate::core::types::CoralError;
+use solana_account_decoder::{UiAccountData, UiAccountEncoding, UiDataSliceConfig};
+use solana_client::{
+    rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient},
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+    rpc_request::TokenAccountsFilter,
+    rpc_response::RpcConfirmedTransactionStatusWithSignature,
+};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta,
+    TransactionConfirmationStatus, UiTransactionEncoding, UiTransactionTokenBalance,
+};
+use std::str::FromStr;
+use crate::core::types::{ConfirmationStatus, CoralError};
+use super::mint::{MintProfile, TokenProgram};
+
+/// Base fee charged per transaction signature, in lamports
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+/// Size of a token account without extensions; mint at offset 0, owner at 32, amount at 64
+const TOKEN_ACCOUNT_LEN: usize = 165;
+/// Token-2022 accounts with extensions carry this account type byte right after the base layout
+const TOKEN_2022_ACCOUNT_TYPE: u8 = 2;
+/// Pages of 1000 signatures read from an address's history
+const MAX_HISTORY_PAGES: usize = 5;
 
 pub struct SolanaUtils {
     rpc_client: RpcClient,
@@ -25,7 +48,178 @@ impl SolanaUtils {
         self.rpc_client
             .get_token_account_balance(token_account)
             .map(|balance| balance.ui_amount_u64())
-            .map_err(|e| CoralError::NetworkError(e.to_string()))
+            .map_err(|e| CoralError::network("get_token_account_balance failed", e))
+    }
+
+    /// Total balance `owner` holds of `mint` across its token accounts, in base units;
+    /// lamports for the native mint
+    pub async fn get_owner_balance(&self, owner: &Pubkey, mint: &Pubkey) -> Result<u64, CoralError> {
+        if *mint == spl_token::native_mint::id() {
+            return self.rpc_client
+                .get_balance(owner)
+                .map_err(|e| CoralError::network("get_balance failed", e));
+        }
+
+        let accounts = self.rpc_client
+            .get_token_accounts_by_owner(owner, TokenAccountsFilter::Mint(*mint))
+            .map_err(|e| CoralError::network("get_token_accounts_by_owner failed", e))?;
+
+        Ok(accounts
+            .iter()
+            .filter_map(|keyed| match &keyed.account.data {
+                UiAccountData::Json(parsed) => parsed.parsed["info"]["tokenAmount"]["amount"]
+                    .as_str()
+                    .and_then(|amount| amount.parse::<u64>().ok()),
+                _ => None,
+            })
+            .sum())
+    }
+
+    /// Signatures of successful transactions involving `address` with a block time at or
+    /// after `since` (unix seconds), oldest first. Only the most recent 1000 are searched.
+    pub async fn get_signatures_since(&self, address: &Pubkey, since: i64) -> Result<Vec<String>, CoralError> {
+        let statuses = self.rpc_client
+            .get_signatures_for_address_with_config(address, GetConfirmedSignaturesForAddress2Config {
+                commitment: Some(CommitmentConfig::confirmed()),
+                ..Default::default()
+            })
+            .map_err(|e| CoralError::network("get_signatures_for_address failed", e))?;
+
+        // Newest first; transactions without a block time yet are too recent to skip
+        let mut signatures: Vec<String> = statuses
+            .into_iter()
+            .take_while(|status| status.block_time.map_or(true, |time| time >= since))
+            .filter(|status| status.err.is_none())
+            .map(|status| status.signature)
+            .collect();
+        signatures.reverse();
+
+        Ok(signatures)
+    }
+
+    /// Each account in `keys`, in order; `None` for accounts that do not exist
+    pub async fn get_accounts(&self, keys: &[Pubkey]) -> Result<Vec<Option<Account>>, CoralError> {
+        self.rpc_client
+            .get_multiple_accounts(keys)
+            .map_err(|e| CoralError::network("get_multiple_accounts failed", e))
+    }
+
+    /// Raw data of each account in `keys`, in order; `None` for accounts that do not exist
+    pub async fn get_accounts_data(&self, keys: &[Pubkey]) -> Result<Vec<Option<Vec<u8>>>, CoralError> {
+        let accounts = self.get_accounts(keys).await?;
+        Ok(accounts.into_iter().map(|account| account.map(|account| account.data)).collect())
+    }
+
+    /// Address and data of every account of `program` that is `data_size` bytes long, when
+    /// given, and holds `bytes` at `offset` for each of `matches`
+    pub async fn find_program_accounts(
+        &self,
+        program: &Pubkey,
+        data_size: Option<u64>,
+        matches: &[(usize, &[u8])],
+    ) -> Result<Vec<(Pubkey, Vec<u8>)>, CoralError> {
+        let mut filters: Vec<RpcFilterType> = data_size.into_iter().map(RpcFilterType::DataSize).collect();
+        filters.extend(
+            matches
+                .iter()
+                .map(|(offset, bytes)| RpcFilterType::Memcmp(Memcmp::new_raw_bytes(*offset, bytes.to_vec()))),
+        );
+
+        let accounts = self.rpc_client
+            .get_program_accounts_with_config(program, RpcProgramAccountsConfig {
+                filters: Some(filters),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .map_err(|e| CoralError::network("get_program_accounts failed", e))?;
+
+        Ok(accounts.into_iter().map(|(address, account)| (address, account.data)).collect())
+    }
+
+    /// Owner and balance of every token account of `mint`. Only the owner and amount of
+    /// each account are transferred. When the node refuses a full scan, usually because
+    /// the response is too large, the scan is repeated in 256 shards keyed on the owner's
+    /// first byte.
+    pub async fn get_mint_token_accounts(&self, mint: &Pubkey, program: TokenProgram) -> Result<Vec<(Pubkey, u64)>, CoralError> {
+        match self.scan_token_accounts(mint, program, None) {
+            Ok(accounts) => Ok(accounts),
+            Err(e) => {
+                log::warn!("Full token account scan of {} failed, retrying in shards: {}", mint, e);
+                let mut accounts = Vec::new();
+                for shard in 0..=u8::MAX {
+                    accounts.extend(self.scan_token_accounts(mint, program, Some(shard))?);
+                }
+                Ok(accounts)
+            }
+        }
+    }
+
+    /// When `address` was first active and how many transactions it has, paging back
+    /// through at most `MAX_HISTORY_PAGES` of its history
+    pub async fn get_address_history(&self, address: &Pubkey) -> Result<AddressHistory, CoralError> {
+        let (statuses, truncated) = self.signature_history(address)?;
+
+        Ok(AddressHistory {
+            first_seen: if truncated { None } else { statuses.iter().filter_map(|status| status.block_time).min() },
+            transaction_count: statuses.len() as u64,
+            truncated,
+        })
+    }
+
+    /// Slot of the oldest transaction involving `address`; `None` when its history is
+    /// deeper than the search goes
+    pub async fn get_first_slot(&self, address: &Pubkey) -> Result<Option<u64>, CoralError> {
+        Ok(self.oldest_signature(address)?.map(|status| status.slot))
+    }
+
+    /// Fee payer of the oldest transaction involving `address`, which for an account
+    /// created by a program is whoever had it created. `None` when the history is deeper
+    /// than the search goes, rather than the payer of some later transaction.
+    pub async fn get_first_signer(&self, address: &Pubkey) -> Result<Option<Pubkey>, CoralError> {
+        let (_, transaction) = match self.oldest_transaction(address)? {
+            Some(oldest) => oldest,
+            None => return Ok(None),
+        };
+
+        Ok(transaction
+            .transaction
+            .transaction
+            .decode()
+            .and_then(|decoded| decoded.message.static_account_keys().first().copied()))
+    }
+
+    /// Who sent `wallet` its first SOL: the fee payer of its oldest transaction, if that
+    /// transaction raised the wallet's balance and was paid by someone else
+    pub async fn get_first_funding(&self, wallet: &Pubkey) -> Result<Option<FirstFunding>, CoralError> {
+        let (status, transaction) = match self.oldest_transaction(wallet)? {
+            Some(oldest) => oldest,
+            None => return Ok(None),
+        };
+
+        let (meta, decoded) = match (transaction.transaction.meta, transaction.transaction.transaction.decode()) {
+            (Some(meta), Some(decoded)) => (meta, decoded),
+            _ => return Ok(None),
+        };
+        let keys = decoded.message.static_account_keys();
+        let (funder, index) = match (keys.first(), keys.iter().position(|key| key == wallet)) {
+            (Some(funder), Some(index)) if funder != wallet => (*funder, index),
+            _ => return Ok(None),
+        };
+        let lamports = meta.post_balances.get(index).copied().unwrap_or(0)
+            .saturating_sub(meta.pre_balances.get(index).copied().unwrap_or(0));
+        if lamports == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(FirstFunding {
+            funder,
+            block_time: status.block_time.or(transaction.block_time),
+            lamports,
+        }))
     }
 
     pub async fn send_transaction(
@@ -35,7 +229,7 @@ impl SolanaUtils {
     ) -> Result<Signature, CoralError> {
         let signature = self.rpc_client
             .send_and_confirm_transaction_with_spinner(&transaction)
-            .map_err(|e| CoralError::NetworkError(e.to_string()))?;
+            .map_err(|e| CoralError::network("send_and_confirm_transaction failed", e))?;
 
         Ok(signature)
     }
@@ -43,15 +237,254 @@ impl SolanaUtils {
     pub async fn get_latest_blockhash(&self) -> Result<solana_sdk::hash::Hash, CoralError> {
         self.rpc_client
             .get_latest_blockhash()
-            .map_err(|e| CoralError::NetworkError(e.to_string()))
+            .map_err(|e| CoralError::network("get_latest_blockhash failed", e))
     }
 
+    /// Whether `token` is an initialized mint of the SPL Token or Token-2022 program
     pub async fn is_token_valid(&self, token: &Pubkey) -> Result<bool, CoralError> {
         let account = self.rpc_client
             .get_account(token)
-            .map_err(|e| CoralError::NetworkError(e.to_string()))?;
+            .map_err(|e| CoralError::network("get_account failed", e))?;
+
+        Ok(MintProfile::parse(&account.owner, &account.data).is_some())
+    }
 
-        // Verify if the account is a valid SPL token
-        Ok(account.owner == spl_token::id())
+    pub async fn get_mint_profile(&self, mint: &Pubkey) -> Result<MintProfile, CoralError> {
+        let account = self.rpc_client
+            .get_account(mint)
+            .map_err(|e| CoralError::network("get_account failed", e))?;
+
+        MintProfile::parse(&account.owner, &account.data).ok_or_else(|| CoralError::TokenNotFound {
+            query: mint.to_string(),
+            reason: "account is not a token mint".to_string(),
+        })
+    }
+
+    /// Reads back what a confirmed swap actually did. The output is the net gain of the
+    /// fee payer's `output_mint` balance and the input the net loss of its `input_mint`
+    /// balance; for native SOL the lamport change counts, with the fee added back.
+    pub async fn get_fill_details(
+        &self,
+        signature: &str,
+        input_mint: &Pubkey,
+        output_mint: &Pubkey,
+    ) -> Result<FillDetails, CoralError> {
+        let not_found = || CoralError::OrderNotFound {
+            signature: signature.to_string(),
+        };
+        let parsed = Signature::from_str(signature).map_err(|_| not_found())?;
+
+        let transaction = self.rpc_client
+            .get_transaction_with_config(&parsed, RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            })
+            .map_err(|e| CoralError::network("get_transaction failed", e))?;
+
+        let slot = transaction.slot;
+        let meta = transaction.transaction.meta.ok_or_else(not_found)?;
+        let decoded = transaction.transaction.transaction.decode().ok_or_else(not_found)?;
+        let fee_payer = *decoded.message.static_account_keys().first().ok_or_else(not_found)?;
+        let base_fee = LAMPORTS_PER_SIGNATURE * decoded.signatures.len() as u64;
+
+        // Signed change in the fee payer's balance of `mint`, with the fee added back for SOL
+        let balance_change = |mint: &Pubkey| -> i128 {
+            if *mint == spl_token::native_mint::id() {
+                let pre = meta.pre_balances.first().copied().unwrap_or(0) as i128;
+                let post = meta.post_balances.first().copied().unwrap_or(0) as i128;
+                post + meta.fee as i128 - pre
+            } else {
+                token_balance(&meta.post_token_balances, &fee_payer, mint) as i128
+                    - token_balance(&meta.pre_token_balances, &fee_payer, mint) as i128
+            }
+        };
+        let output_amount = balance_change(output_mint).max(0) as u64;
+        let input_amount = (-balance_change(input_mint)).max(0) as u64;
+
+        let confirmation_status = self.rpc_client
+            .get_signature_statuses(&[parsed])
+            .map_err(|e| CoralError::network("get_signature_statuses failed", e))?
+            .value
+            .into_iter()
+            .next()
+            .flatten()
+            .and_then(|status| status.confirmation_status)
+            .map(|status| match status {
+                TransactionConfirmationStatus::Processed => ConfirmationStatus::Processed,
+                TransactionConfirmationStatus::Confirmed => ConfirmationStatus::Confirmed,
+                TransactionConfirmationStatus::Finalized => ConfirmationStatus::Finalized,
+            })
+            .unwrap_or(ConfirmationStatus::Confirmed);
+
+        Ok(FillDetails {
+            slot,
+            confirmation_status,
+            fee: meta.fee,
+            priority_fee: meta.fee.saturating_sub(base_fee),
+            input_amount,
+            output_amount,
+            block_time: transaction.block_time,
+        })
+    }
+
+    /// Oldest transaction involving `address`; `None` when its history goes deeper than
+    /// `MAX_HISTORY_PAGES`, since the oldest transaction seen is then not the first
+    fn oldest_signature(&self, address: &Pubkey) -> Result<Option<RpcConfirmedTransactionStatusWithSignature>, CoralError> {
+        let (mut statuses, truncated) = self.signature_history(address)?;
+        Ok(if truncated { None } else { statuses.pop() })
+    }
+
+    /// Transactions involving `address`, newest first, paging back at most
+    /// `MAX_HISTORY_PAGES`. The flag is set when older history was left unread.
+    fn signature_history(&self, address: &Pubkey) -> Result<(Vec<RpcConfirmedTransactionStatusWithSignature>, bool), CoralError> {
+        const PAGE_SIZE: usize = 1000;
+
+        let mut statuses = Vec::new();
+        let mut before = None;
+        for _ in 0..MAX_HISTORY_PAGES {
+            let page = self.rpc_client
+                .get_signatures_for_address_with_config(address, GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    ..Default::default()
+                })
+                .map_err(|e| CoralError::network("get_signatures_for_address failed", e))?;
+
+            let full_page = page.len() == PAGE_SIZE;
+            before = page.last().and_then(|status| Signature::from_str(&status.signature).ok());
+            statuses.extend(page);
+            if !full_page {
+                return Ok((statuses, false));
+            }
+            if before.is_none() {
+                break;
+            }
+        }
+
+        // The last page was full, so there is older history than was read
+        Ok((statuses, true))
+    }
+
+    /// Oldest transaction involving `address` with its status, see `oldest_signature`
+    fn oldest_transaction(
+        &self,
+        address: &Pubkey,
+    ) -> Result<Option<(RpcConfirmedTransactionStatusWithSignature, EncodedConfirmedTransactionWithStatusMeta)>, CoralError> {
+        let status = match self.oldest_signature(address)? {
+            Some(status) => status,
+            None => return Ok(None),
+        };
+        let signature = match Signature::from_str(&status.signature) {
+            Ok(signature) => signature,
+            Err(_) => return Ok(None),
+        };
+
+        let transaction = self.rpc_client
+            .get_transaction_with_config(&signature, RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            })
+            .map_err(|e| CoralError::network("get_transaction failed", e))?;
+
+        Ok(Some((status, transaction)))
+    }
+
+    fn scan_token_accounts(
+        &self,
+        mint: &Pubkey,
+        program: TokenProgram,
+        owner_shard: Option<u8>,
+    ) -> Result<Vec<(Pubkey, u64)>, CoralError> {
+        // Token-2022 accounts are exactly the base size until an extension is added
+        let layouts = match program {
+            TokenProgram::SplToken => vec![RpcFilterType::DataSize(TOKEN_ACCOUNT_LEN as u64)],
+            TokenProgram::Token2022 => vec![
+                RpcFilterType::DataSize(TOKEN_ACCOUNT_LEN as u64),
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(TOKEN_ACCOUNT_LEN, vec![TOKEN_2022_ACCOUNT_TYPE])),
+            ],
+        };
+
+        let mut accounts = Vec::new();
+        for layout in layouts {
+            let mut filters = vec![layout, RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, mint.to_bytes().to_vec()))];
+            if let Some(shard) = owner_shard {
+                filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(32, vec![shard])));
+            }
+
+            let scanned = self.rpc_client
+                .get_program_accounts_with_config(&program.id(), RpcProgramAccountsConfig {
+                    filters: Some(filters),
+                    account_config: RpcAccountInfoConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        data_slice: Some(UiDataSliceConfig { offset: 32, length: 40 }),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .map_err(|e| CoralError::network("get_program_accounts failed", e))?;
+
+            accounts.extend(scanned.into_iter().filter_map(|(_, account)| {
+                let owner = Pubkey::try_from(account.data.get(..32)?).ok()?;
+                let amount = u64::from_le_bytes(account.data.get(32..40)?.try_into().ok()?);
+                Some((owner, amount))
+            }));
+        }
+
+        Ok(accounts)
     }
 }
+
+/// Activity of an address, as far back as `get_address_history` searches
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AddressHistory {
+    /// Block time of the first transaction; `None` when the history is `truncated`, as
+    /// the oldest transaction read is then not the first
+    pub first_seen: Option<i64>,
+    /// Transactions read; a lower bound when the history is `truncated`
+    pub transaction_count: u64,
+    /// The history goes deeper than the search, so the address is at least that active
+    pub truncated: bool,
+}
+
+/// The transfer that first gave a wallet SOL
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FirstFunding {
+    pub funder: Pubkey,
+    pub block_time: Option<i64>,
+    pub lamports: u64,
+}
+
+/// On-chain outcome of a confirmed transaction
+#[derive(Debug, Clone)]
+pub struct FillDetails {
+    pub slot: u64,
+    pub confirmation_status: ConfirmationStatus,
+    pub fee: u64,
+    pub priority_fee: u64,
+    /// How much of the input mint left the fee payer
+    pub input_amount: u64,
+    pub output_amount: u64,
+    pub block_time: Option<i64>,
+}
+
+/// Sum of `owner`'s balances of `mint` across the accounts listed in a transaction meta
+fn token_balance(
+    balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> u64 {
+    let balances = match balances {
+        OptionSerializer::Some(balances) => balances.as_slice(),
+        _ => &[],
+    };
+    let (owner, mint) = (owner.to_string(), mint.to_string());
+
+    balances
+        .iter()
+        .filter(|balance| balance.mint == mint && balance.owner == OptionSerializer::Some(owner.clone()))
+        .filter_map(|balance| balance.ui_token_amount.amount.parse::<u64>().ok())
+        .sum()
+}
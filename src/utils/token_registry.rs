@@ -0,0 +1,389 @@
+//! Token metadata keyed by mint
+//!
+//! Symbols are not unique on Solana: anyone can create a mint whose Metaplex metadata says
+//! "USDC". The registry therefore only resolves symbols from trusted sources (the built-in
+//! tokens, a local token list, explicit `insert` calls). Metadata read from chain is cached
+//! for display and decimals but never makes a symbol resolvable, and a symbol claimed by
+//! more than one trusted mint is refused as ambiguous rather than guessed.
+
+use serde::Deserialize;
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::RwLock;
+use crate::core::amount::TokenAmount;
+use crate::core::types::CoralError;
+use super::solana::SolanaUtils;
+
+pub const USDC_MINT: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+pub const USDT_MINT: Pubkey = pubkey!("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB");
+pub const WRAPPED_SOL_MINT: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+pub const METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Offset of `decimals` in the SPL Token (and Token-2022) mint layout
+const MINT_DECIMALS_OFFSET: usize = 44;
+/// Key byte, update authority and mint precede the name in a Metaplex metadata account
+const METADATA_NAME_OFFSET: usize = 1 + 32 + 32;
+/// Address, `verified` and `share` of one entry in a metadata account's creator list
+const METADATA_CREATOR_LEN: usize = 32 + 1 + 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenInfo {
+    pub mint: Pubkey,
+    pub symbol: String,
+    pub name: String,
+    pub decimals: u8,
+    /// Whether the entry came from a trusted source and may be looked up by symbol
+    pub verified: bool,
+}
+
+pub struct TokenRegistry {
+    by_mint: RwLock<HashMap<Pubkey, TokenInfo>>,
+    /// Upper-cased symbol to every verified mint using it
+    by_symbol: RwLock<HashMap<String, Vec<Pubkey>>>,
+    solana: Option<SolanaUtils>,
+}
+
+impl TokenRegistry {
+    /// Registry that knows SOL, USDC and USDT and resolves nothing else until told to
+    pub fn new() -> Self {
+        let registry = Self {
+            by_mint: RwLock::new(HashMap::new()),
+            by_symbol: RwLock::new(HashMap::new()),
+            solana: None,
+        };
+        for (mint, symbol, name, decimals) in [
+            (WRAPPED_SOL_MINT, "SOL", "Wrapped SOL", 9),
+            (USDC_MINT, "USDC", "USD Coin", 6),
+            (USDT_MINT, "USDT", "USDT", 6),
+        ] {
+            registry.insert(TokenInfo {
+                mint,
+                symbol: symbol.to_string(),
+                name: name.to_string(),
+                decimals,
+                verified: true,
+            });
+        }
+        registry
+    }
+
+    /// Reads unknown mints from chain through `rpc_url`
+    pub fn with_rpc(mut self, rpc_url: &str) -> Self {
+        self.solana = Some(SolanaUtils::new(rpc_url));
+        self
+    }
+
+    /// Adds or replaces the entry for `info.mint`
+    pub fn insert(&self, info: TokenInfo) {
+        let mut by_mint = self.by_mint.write().expect("token registry lock poisoned");
+        let mut by_symbol = self.by_symbol.write().expect("token registry lock poisoned");
+
+        if let Some(previous) = by_mint.get(&info.mint) {
+            if let Some(mints) = by_symbol.get_mut(&previous.symbol.to_uppercase()) {
+                mints.retain(|mint| *mint != info.mint);
+            }
+        }
+        if info.verified {
+            let mints = by_symbol.entry(info.symbol.to_uppercase()).or_default();
+            if !mints.contains(&info.mint) {
+                mints.push(info.mint);
+            }
+        }
+        by_mint.insert(info.mint, info);
+    }
+
+    /// Loads a token list in the Solana token-list format (`{"tokens": [{"address",
+    /// "symbol", "name", "decimals", ...}]}`). Returns the number of entries loaded.
+    pub fn load_token_list(&self, path: impl AsRef<Path>) -> Result<usize, CoralError> {
+        let path = path.as_ref();
+        let raw = std::fs::read_to_string(path).map_err(|e| CoralError::Initialization {
+            message: format!("cannot read token list {}", path.display()),
+            source: Some(e.into()),
+        })?;
+        self.load_token_list_str(&raw)
+    }
+
+    pub fn load_token_list_str(&self, raw: &str) -> Result<usize, CoralError> {
+        let list: TokenList = serde_json::from_str(raw).map_err(|e| CoralError::Initialization {
+            message: "token list is not valid JSON".to_string(),
+            source: Some(e.into()),
+        })?;
+
+        let mut loaded = 0;
+        for entry in list.tokens {
+            let mint = match Pubkey::from_str(&entry.address) {
+                Ok(mint) => mint,
+                Err(_) => {
+                    log::warn!("Skipping token list entry {} with invalid address {}", entry.symbol, entry.address);
+                    continue;
+                }
+            };
+            self.insert(TokenInfo {
+                mint,
+                symbol: entry.symbol,
+                name: entry.name,
+                decimals: entry.decimals,
+                verified: true,
+            });
+            loaded += 1;
+        }
+
+        Ok(loaded)
+    }
+
+    pub fn get(&self, mint: &Pubkey) -> Option<TokenInfo> {
+        self.by_mint.read().expect("token registry lock poisoned").get(mint).cloned()
+    }
+
+    /// Mint for a base58 address or a verified symbol (case-insensitive)
+    pub fn resolve(&self, query: &str) -> Result<Pubkey, CoralError> {
+        if let Ok(mint) = Pubkey::from_str(query) {
+            return Ok(mint);
+        }
+
+        let not_found = |reason: String| CoralError::TokenNotFound {
+            query: query.to_string(),
+            reason,
+        };
+        let by_symbol = self.by_symbol.read().expect("token registry lock poisoned");
+        match by_symbol.get(&query.to_uppercase()).map(Vec::as_slice) {
+            Some([mint]) => Ok(*mint),
+            Some(mints) if mints.len() > 1 => {
+                let mints: Vec<String> = mints.iter().map(Pubkey::to_string).collect();
+                Err(not_found(format!("symbol is used by several mints: {}", mints.join(", "))))
+            }
+            _ => Err(not_found("no verified token has this symbol".to_string())),
+        }
+    }
+
+    /// Cached entry for `mint`, reading the mint and its Metaplex metadata from chain on a
+    /// miss. Tokens read from chain are never verified.
+    pub async fn fetch(&self, mint: &Pubkey) -> Result<TokenInfo, CoralError> {
+        if let Some(info) = self.get(mint) {
+            return Ok(info);
+        }
+        let solana = self.solana.as_ref().ok_or_else(|| CoralError::TokenNotFound {
+            query: mint.to_string(),
+            reason: "not in the registry and no RPC configured".to_string(),
+        })?;
+
+        let metadata_address = metadata_address(mint);
+        let accounts = solana.get_accounts_data(&[*mint, metadata_address]).await?;
+        let decimals = accounts[0]
+            .as_deref()
+            .and_then(parse_mint_decimals)
+            .ok_or_else(|| CoralError::TokenNotFound {
+                query: mint.to_string(),
+                reason: "account is not a token mint".to_string(),
+            })?;
+        let (name, symbol) = accounts[1]
+            .as_deref()
+            .and_then(TokenMetadata::parse)
+            .map(|metadata| (metadata.name, metadata.symbol))
+            .unwrap_or_default();
+
+        let info = TokenInfo {
+            mint: *mint,
+            symbol,
+            name,
+            decimals,
+            verified: false,
+        };
+        self.insert(info.clone());
+
+        Ok(info)
+    }
+
+    /// Symbol for display, or the abbreviated address when the mint is unknown or has no
+    /// symbol. Unverified symbols are marked with `?` so they cannot pass for the real token.
+    pub fn label(&self, mint: &Pubkey) -> String {
+        match self.get(mint) {
+            Some(info) if !info.symbol.is_empty() && info.verified => info.symbol,
+            Some(info) if !info.symbol.is_empty() => format!("{}?", info.symbol),
+            _ => short_address(mint),
+        }
+    }
+
+    /// `FROM/TO` label for a swap, e.g. `SOL/USDC`
+    pub fn pair(&self, from: &Pubkey, to: &Pubkey) -> String {
+        format!("{}/{}", self.label(from), self.label(to))
+    }
+
+    /// `raw` base units of `mint` scaled by its decimals, e.g. `1.500000 USDC`
+    pub fn format_amount(&self, mint: &Pubkey, raw: u64) -> String {
+        match self.get(mint) {
+            Some(info) => format!("{} {}", TokenAmount::new(raw, info.decimals), self.label(mint)),
+            None => format!("{} (raw) {}", raw, short_address(mint)),
+        }
+    }
+}
+
+impl Default for TokenRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Metaplex metadata PDA for `mint`
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+fn parse_mint_decimals(data: &[u8]) -> Option<u8> {
+    // Byte after decimals is `is_initialized`
+    match data.get(MINT_DECIMALS_OFFSET..MINT_DECIMALS_OFFSET + 2)? {
+        [decimals, 1] => Some(*decimals),
+        _ => None,
+    }
+}
+
+/// The parts of a Metaplex metadata account the agent uses
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenMetadata {
+    pub update_authority: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    /// Whether the update authority can still rewrite name, symbol and URI
+    pub is_mutable: bool,
+}
+
+impl TokenMetadata {
+    /// Name, symbol and uri are borsh strings padded with NULs to a fixed width; they are
+    /// followed by the seller fee, an optional creator list, `primary_sale_happened` and
+    /// `is_mutable`
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let update_authority = Pubkey::try_from(data.get(1..33)?).ok()?;
+        let (name, rest) = read_borsh_string(data.get(METADATA_NAME_OFFSET..)?)?;
+        let (symbol, rest) = read_borsh_string(rest)?;
+        let (_uri, rest) = read_borsh_string(rest)?;
+
+        let mut rest = rest.get(2..)?;
+        if *rest.first()? == 1 {
+            let creators = read_u32(rest.get(1..)?)? as usize;
+            rest = rest.get(1 + 4 + creators * METADATA_CREATOR_LEN..)?;
+        } else {
+            rest = rest.get(1..)?;
+        }
+        let is_mutable = *rest.get(1)? != 0;
+
+        Some(Self {
+            update_authority,
+            name,
+            symbol,
+            is_mutable,
+        })
+    }
+}
+
+fn read_borsh_string(data: &[u8]) -> Option<(String, &[u8])> {
+    let len = read_u32(data)? as usize;
+    let bytes = data.get(4..4 + len)?;
+    let value = String::from_utf8_lossy(bytes).trim_end_matches('\0').trim().to_string();
+    Some((value, &data[4 + len..]))
+}
+
+fn read_u32(data: &[u8]) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(..4)?.try_into().ok()?))
+}
+
+fn short_address(mint: &Pubkey) -> String {
+    let address = mint.to_string();
+    format!("{}..{}", &address[..4], &address[address.len() - 4..])
+}
+
+#[derive(Deserialize)]
+struct TokenList {
+    tokens: Vec<TokenListEntry>,
+}
+
+#[derive(Deserialize)]
+struct TokenListEntry {
+    address: String,
+    symbol: String,
+    name: String,
+    decimals: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolves_trusted_symbols_and_refuses_ambiguous_ones() {
+        let registry = TokenRegistry::new();
+        let (bonk, fake_a, fake_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let list = format!(
+            r#"{{"tokens": [
+                {{"address": "{}", "symbol": "BONK", "name": "Bonk", "decimals": 5}},
+                {{"address": "{}", "symbol": "DUP", "name": "Dup A", "decimals": 6}},
+                {{"address": "{}", "symbol": "DUP", "name": "Dup B", "decimals": 6}},
+                {{"address": "not-a-key", "symbol": "BAD", "name": "Bad", "decimals": 6}}
+            ]}}"#,
+            bonk, fake_a, fake_b
+        );
+        assert_eq!(registry.load_token_list_str(&list).unwrap(), 3);
+
+        assert_eq!(registry.resolve("usdc").unwrap(), USDC_MINT);
+        assert_eq!(registry.resolve("BONK").unwrap(), bonk);
+        assert_eq!(registry.resolve(&bonk.to_string()).unwrap(), bonk);
+        assert!(matches!(registry.resolve("DUP"), Err(CoralError::TokenNotFound { .. })));
+        assert!(matches!(registry.resolve("BAD"), Err(CoralError::TokenNotFound { .. })));
+
+        // Metadata from chain is shown, flagged, and never resolvable
+        let scam = Pubkey::new_unique();
+        registry.insert(TokenInfo {
+            mint: scam,
+            symbol: "USDC".to_string(),
+            name: "USD Coin".to_string(),
+            decimals: 6,
+            verified: false,
+        });
+        assert_eq!(registry.resolve("USDC").unwrap(), USDC_MINT);
+        assert_eq!(registry.pair(&WRAPPED_SOL_MINT, &scam), "SOL/USDC?");
+        assert_eq!(registry.format_amount(&USDC_MINT, 1_500_000), "1.500000 USDC");
+    }
+
+    #[test]
+    fn test_parses_mint_and_metadata_accounts() {
+        let mut mint = vec![0u8; 82];
+        mint[MINT_DECIMALS_OFFSET] = 9;
+        mint[MINT_DECIMALS_OFFSET + 1] = 1;
+        assert_eq!(parse_mint_decimals(&mint), Some(9));
+        mint[MINT_DECIMALS_OFFSET + 1] = 0;
+        assert_eq!(parse_mint_decimals(&mint), None);
+
+        let update_authority = Pubkey::new_unique();
+        let mut metadata = vec![4u8];
+        metadata.extend_from_slice(update_authority.as_ref());
+        metadata.extend_from_slice(Pubkey::new_unique().as_ref());
+        for (value, width) in [("Bonk", 32), ("BONK", 10), ("https://bonk", 200)] {
+            metadata.extend_from_slice(&(width as u32).to_le_bytes());
+            let mut padded = value.as_bytes().to_vec();
+            padded.resize(width, 0);
+            metadata.extend_from_slice(&padded);
+        }
+        metadata.extend_from_slice(&500u16.to_le_bytes());
+        // One creator, then primary_sale_happened and is_mutable
+        metadata.push(1);
+        metadata.extend_from_slice(&1u32.to_le_bytes());
+        metadata.extend_from_slice(&[7u8; METADATA_CREATOR_LEN]);
+        metadata.extend_from_slice(&[1, 1]);
+
+        assert_eq!(
+            TokenMetadata::parse(&metadata),
+            Some(TokenMetadata {
+                update_authority,
+                name: "Bonk".to_string(),
+                symbol: "BONK".to_string(),
+                is_mutable: true,
+            })
+        );
+        assert_eq!(TokenMetadata::parse(&metadata[..metadata.len() - 1]), None);
+    }
+}
@@ -1,8 +1,14 @@
 use coral::{
     analyzers::{Analyzer, AuthenticityAnalyzer, WalletAnalyzer, DeveloperAnalyzer},
-    core::{CoralAgent, types::*},
-    traders::JupiterTrader,
-    utils::{MetricsCollector, SolanaUtils},
+    core::{
+        CoralAgent,
+        amount::{Price, SignedAmount},
+        config::AgentConfig,
+        risk::RiskRejection,
+        types::*,
+    },
+    traders::{DexType, JupiterTrader, PriceQuote, RouteHop, RoutePreference, TradeOrder, TradeRoute},
+    utils::{MetricsCollector, SolanaUtils, token_registry::USDC_MINT},
 };
 use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
@@ -26,16 +32,19 @@ mod mock {
     }
 }
 
+fn testnet_config() -> AgentConfig {
+    let mut config = AgentConfig::default();
+    config.rpc.primary_endpoint = "https://api.testnet.solana.com".to_string();
+    config
+}
+
 #[tokio::test]
 async fn test_token_analysis() {
     // Setup test environment
     let test_token = Pubkey::from_str("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA")
         .expect("Failed to parse token address");
     
-    let agent = CoralAgent::new(
-        "https://api.testnet.solana.com".to_string(),
-        None,
-    );
+    let agent = CoralAgent::new(testnet_config(), None).expect("valid config");
 
     // Perform analysis
     let result = agent.analyze_token(&test_token).await;
@@ -55,13 +64,13 @@ async fn test_trade_execution() {
     
     let trader = JupiterTrader::new(
         "test_api_key",
+        "https://api.testnet.solana.com",
         Default::default(),
     );
 
     let order = TradeOrder {
         from_token: test_token,
-        to_token: Pubkey::from_str("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v")
-            .expect("Failed to parse USDC address"),
+        to_token: USDC_MINT,
         amount: 1000000,
         slippage_tolerance: 0.01,
         minimum_received: None,
@@ -85,11 +94,15 @@ async fn test_metrics_collection() {
     let trade_data = TradeExecutionData {
         token: "TEST".to_string(),
         timestamp: chrono::Utc::now(),
-        amount: 1000.0,
-        price: 1.5,
+        amount: 1_000_000_000,
+        price: Price::from_raw(2, 3).unwrap(),
         success: true,
-        profit_loss: 50.0,
+        profit_loss: Some(SignedAmount::new(50_000_000, 6)),
         execution_time_ms: 150,
+        output_amount: 1_500_000_000,
+        slippage_bps: 12,
+        fee: 15_000,
+        priority_fee: 10_000,
     };
     
     metrics.record_trade_execution(trade_data).await;
@@ -106,11 +119,65 @@ async fn test_error_handling() {
     let invalid_token = Pubkey::from_str("InvalidTokenAddress")
         .expect_err("Should fail with invalid address");
     
-    let agent = CoralAgent::new(
-        "https://api.testnet.solana.com".to_string(),
-        None,
-    );
+    let agent = CoralAgent::new(testnet_config(), None).expect("valid config");
 
     let result = agent.analyze_token(&Pubkey::new_unique()).await;
-    assert!(matches!(result, Err(CoralError::AnalysisFailed(_))));
+    assert!(matches!(result, Err(CoralError::AnalysisFailed { .. })));
+}
+
+#[tokio::test]
+async fn test_unanalyzed_token_is_rejected_before_execution() {
+    let config = testnet_config();
+    let quote_token = config.trading.quote_token;
+    let agent = CoralAgent::new(config, None).expect("valid config");
+
+    let order = TradeOrder {
+        from_token: quote_token,
+        to_token: Pubkey::new_unique(),
+        amount: 10_000_000,
+        slippage_tolerance: 0.01,
+        minimum_received: None,
+        route_preference: RoutePreference::BestPrice,
+    };
+
+    let result = agent.execute_trade(order).await;
+    assert!(matches!(
+        result,
+        Err(CoralError::RiskRejected(RiskRejection::NoRecentAnalysis { .. }))
+    ));
+}
+
+#[test]
+fn test_orders_and_quotes_round_trip_through_json() {
+    let order = TradeOrder {
+        from_token: Pubkey::new_unique(),
+        to_token: Pubkey::new_unique(),
+        amount: 25_000_000,
+        slippage_tolerance: 0.005,
+        minimum_received: Some(24_000_000),
+        route_preference: RoutePreference::Custom("twap".to_string()),
+    };
+    let json = serde_json::to_string(&order).unwrap();
+    assert_eq!(serde_json::from_str::<TradeOrder>(&json).unwrap(), order);
+
+    let quote = PriceQuote {
+        input_amount: 25_000_000,
+        output_amount: 24_900_000,
+        price_impact: 0.002,
+        fee_amount: 5_000,
+        route: TradeRoute {
+            hops: vec![RouteHop {
+                dex: DexType::Custom("phoenix".to_string()),
+                input_token: order.from_token,
+                output_token: order.to_token,
+                pool_address: Pubkey::new_unique(),
+                fee_tier: 30,
+            }],
+            total_fee: 5_000,
+            expected_output: 24_900_000,
+            price_impact: 0.002,
+        },
+    };
+    let json = serde_json::to_string(&quote).unwrap();
+    assert_eq!(serde_json::from_str::<PriceQuote>(&json).unwrap(), quote);
 }